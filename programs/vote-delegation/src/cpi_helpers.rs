@@ -0,0 +1,196 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{instruction::Instruction, system_program},
+    InstructionData,
+};
+
+use crate::state::{
+    delegation::Delegation,
+    rent_payer::RentPayer,
+    settings::Settings,
+    voter_weight_record::{VoterWeightAction, VoterWeightRecord},
+};
+
+/// Accounts of a delegator whose voter weight is aggregated by `update_voter_weight_record`
+pub struct DelegatorAccounts {
+    pub governing_token_owner: Pubkey,
+    /// The VoterWeightRecord produced by the realm's voter weight source
+    pub voter_weight_record: Pubkey,
+    pub token_owner_record: Pubkey,
+}
+
+/// Builds `create_voter_weight_record`, which anyone can call on behalf of a delegate
+pub fn create_voter_weight_record(
+    governance_program_id: &Pubkey,
+    realm: &Pubkey,
+    governing_token_mint: &Pubkey,
+    governing_token_owner: &Pubkey,
+    payer: &Pubkey,
+    action: VoterWeightAction,
+    target: Pubkey,
+) -> Instruction {
+    let voter_weight_record = VoterWeightRecord::get_pda_address(
+        realm,
+        governing_token_mint,
+        governing_token_owner,
+        &target,
+        Some(action),
+    );
+
+    Instruction {
+        program_id: crate::id(),
+        accounts: crate::accounts::CreateVoterWeightRecord {
+            payer: *payer,
+            voter_weight_record,
+            rent_payer: RentPayer::get_pda_address(&voter_weight_record),
+            governance_program_id: *governance_program_id,
+            realm: *realm,
+            realm_governing_token_mint: *governing_token_mint,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: crate::instruction::CreateVoterWeightRecord {
+            governing_token_owner: *governing_token_owner,
+            target,
+            action,
+        }
+        .data(),
+    }
+}
+
+/// Builds `update_voter_weight_record` for a delegate aggregating the given delegators
+///
+/// The delegate is the only signer. A program acting as delegate signs for it with
+/// `invoke_signed`, passing the accounts of the instruction and the payer.
+#[allow(clippy::too_many_arguments)]
+pub fn update_voter_weight_record(
+    governance_program_id: &Pubkey,
+    realm: &Pubkey,
+    governing_token_mint: &Pubkey,
+    delegate: &Pubkey,
+    payer: &Pubkey,
+    action: VoterWeightAction,
+    target: Pubkey,
+    delegators: &[DelegatorAccounts],
+) -> Instruction {
+    let mut accounts = crate::accounts::UpdateVoterWeightRecord {
+        delegate: *delegate,
+        payer: *payer,
+        settings: Settings::get_pda_address(realm, governing_token_mint),
+        voter_weight_record: VoterWeightRecord::get_pda_address(
+            realm,
+            governing_token_mint,
+            delegate,
+            &target,
+            Some(action),
+        ),
+        system_program: system_program::ID,
+        governance_program_id: *governance_program_id,
+        realm: *realm,
+    }
+    .to_account_metas(None);
+    accounts.extend(get_delegator_account_metas(
+        realm,
+        governing_token_mint,
+        action,
+        &target,
+        delegators,
+    ));
+
+    Instruction {
+        program_id: crate::id(),
+        accounts,
+        data: crate::instruction::UpdateVoterWeightRecord {
+            voter_weight_action: action,
+            target: Some(target),
+            delegation_bumps: get_delegation_bumps(
+                realm,
+                governing_token_mint,
+                action,
+                &target,
+                delegators,
+            ),
+        }
+        .data(),
+    }
+}
+
+/// Returns the additional accounts `update_voter_weight_record` expects for the delegators
+///
+/// They name a Delegation record for each delegator, so a realm which records delegations in
+/// a ledger must put `DelegationLedger::get_pda_address` in their place.
+pub fn get_delegator_account_metas(
+    realm: &Pubkey,
+    governing_token_mint: &Pubkey,
+    action: VoterWeightAction,
+    target: &Pubkey,
+    delegators: &[DelegatorAccounts],
+) -> Vec<AccountMeta> {
+    delegators
+        .iter()
+        .flat_map(|delegator| {
+            [
+                AccountMeta::new_readonly(delegator.voter_weight_record, false),
+                AccountMeta::new_readonly(delegator.token_owner_record, false),
+                AccountMeta::new(
+                    Delegation::get_pda_address(
+                        realm,
+                        governing_token_mint,
+                        &delegator.governing_token_owner,
+                        target,
+                        Some(action),
+                    ),
+                    false,
+                ),
+            ]
+        })
+        .collect()
+}
+
+/// Returns the canonical bumps of the Delegation records `update_voter_weight_record`
+/// creates for the delegators, in the same order
+pub fn get_delegation_bumps(
+    realm: &Pubkey,
+    governing_token_mint: &Pubkey,
+    action: VoterWeightAction,
+    target: &Pubkey,
+    delegators: &[DelegatorAccounts],
+) -> Vec<u8> {
+    let encoded_action = borsh::to_vec(&Some(action)).unwrap();
+    delegators
+        .iter()
+        .map(|delegator| {
+            let seeds = Delegation::get_pda_seeds(
+                realm,
+                governing_token_mint,
+                &delegator.governing_token_owner,
+                target,
+                &encoded_action,
+            );
+            Pubkey::find_program_address(&seeds, &crate::id()).1
+        })
+        .collect()
+}
+
+/// Calls `update_voter_weight_record` for a delegate which signs with signer_seeds
+///
+/// delegator_infos holds the accounts of `get_delegator_account_metas`, in the same order,
+/// and delegation_bumps the bumps of `get_delegation_bumps`.
+#[cfg(feature = "cpi")]
+pub fn update_voter_weight_record_signed<'a, 'b, 'c, 'info>(
+    program: AccountInfo<'info>,
+    accounts: crate::cpi::accounts::UpdateVoterWeightRecord<'info>,
+    delegator_infos: Vec<AccountInfo<'info>>,
+    action: VoterWeightAction,
+    target: Pubkey,
+    delegation_bumps: Vec<u8>,
+    signer_seeds: &'a [&'b [&'c [u8]]],
+) -> Result<()> {
+    crate::cpi::update_voter_weight_record(
+        CpiContext::new_with_signer(program, accounts, signer_seeds)
+            .with_remaining_accounts(delegator_infos),
+        action,
+        Some(target),
+        delegation_bumps,
+    )
+}
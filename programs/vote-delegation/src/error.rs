@@ -43,4 +43,87 @@ pub enum DelegationError {
 
     #[msg("Cannot reclaim lamports from delegation record until voter record is closed.")]
     CannotReclaimDelegationRecordYet,
+
+    #[msg("Provided proposal is not the target of the delegated voter weight record.")]
+    IncorrectTarget,
+
+    #[msg("Delegator must sign to revoke their voter weight.")]
+    DelegatorMustSign,
+
+    #[msg("Token owner record does not belong to the governing token owner.")]
+    InvalidTokenOwnerRecord,
+
+    #[msg("Voter weight is still delegated to the delegate of the delegation record.")]
+    DelegationStillCurrent,
+
+    #[msg("Voter weight source record has expired.")]
+    VoterWeightSourceExpired,
+
+    #[msg("Cannot add voter weight to a vote which has already been cast.")]
+    CannotIncreaseCastVote,
+
+    #[msg("Voter weight of the delegator is not recorded in the delegation ledger.")]
+    DelegationNotInLedger,
+
+    #[msg("Provided account is not the rent payer of the record.")]
+    IncorrectRentPayer,
+
+    #[msg("Voter weight aggregated to cast a vote must be revoked with revoke_vote until voting has ended.")]
+    CannotRevokeVoteWithoutRelinquishing,
+
+    #[msg("Partial revocation must revoke some, but not all, of the delegated voter weight.")]
+    InvalidPartialRevocationAmount,
+
+    #[msg(
+        "Voter weight can only be revoked before the delegate votes with upstream spl-governance."
+    )]
+    CannotRevokeFromCastVote,
+
+    #[msg("Governing token mint must be owned by the SPL Token or Token-2022 program.")]
+    InvalidMintOwner,
+
+    #[msg("Signer is not the session key of the provided session.")]
+    InvalidSession,
+
+    #[msg("Session has expired.")]
+    SessionExpired,
+
+    #[msg("Session does not allow this action or realm.")]
+    SessionScopeExceeded,
+
+    #[msg("Sessions must allow at least one action, and cannot allow RevokeVote.")]
+    InvalidSessionActions,
+
+    #[msg("Delegation message must carry the next nonce of the signed delegation.")]
+    InvalidDelegationNonce,
+
+    #[msg("Delegation message has expired.")]
+    DelegationMessageExpired,
+
+    #[msg("Instruction must follow an ed25519 program instruction.")]
+    MissingSignatureVerification,
+
+    #[msg("The ed25519 program instruction did not verify the delegator's signature over the message.")]
+    InvalidSignatureVerification,
+
+    #[msg("Provided vote record is not the vote record of the delegate for the proposal.")]
+    IncorrectVoteRecord,
+
+    #[msg("Delegation store cannot be changed while delegations recorded in it are open.")]
+    DelegationStoreInUse,
+
+    #[msg("Provided account is not the creator of the delegation ledger.")]
+    NotLedgerCreator,
+
+    #[msg("Provided settings are not the settings the delegation is counted in.")]
+    IncorrectSettings,
+
+    #[msg("Voter weight record must not be expired to crank.")]
+    VoterWeightRecordExpired,
+
+    #[msg("Delegation bump must be the canonical bump of its address.")]
+    NonCanonicalDelegationBump,
+
+    #[msg("A delegation bump must be provided for each delegator.")]
+    MissingDelegationBumps,
 }
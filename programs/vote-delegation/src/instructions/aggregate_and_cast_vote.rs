@@ -0,0 +1,165 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{instruction::Instruction, program::invoke},
+};
+
+use crate::{
+    error::DelegationError,
+    instructions::aggregate_delegations,
+    state::{
+        settings::Settings,
+        vote::Vote,
+        voter_weight_record::{VoterWeightAction, VoterWeightRecord},
+    },
+    tools::is_token_mint_owner,
+};
+
+/**
+ * Aggregates delegated voter weight and casts the delegate's vote with it
+ *
+ * This instruction combines `update_voter_weight_record` and spl-governance `cast_vote`, so
+ * a delegate with few enough delegators to fit in one transaction can vote without their
+ * record expiring in between. The additional accounts are those of
+ * `update_voter_weight_record` for the sources; the delegate's CastVote VoterWeightRecord
+ * for the proposal must already exist and may hold previously aggregated voter weight.
+ *
+ * The record expires as soon as the vote is cast, so delegators can revoke from the next
+ * slot on.
+ */
+#[derive(Accounts)]
+pub struct AggregateAndCastVote<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+
+    delegate: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"settings".as_ref(),
+            realm_info.key().as_ref(),
+            realm_governing_token_mint.key().as_ref(),
+        ],
+        bump
+    )]
+    settings: Account<'info, Settings>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"voter-weight-record".as_ref(),
+            realm_info.key().as_ref(),
+            realm_governing_token_mint.key().as_ref(),
+            delegate.key().as_ref(),
+            proposal_info.key().as_ref(),
+            &borsh::to_vec(&Some(VoterWeightAction::CastVote)).unwrap()
+        ],
+        bump,
+        owner = crate::id()
+    )]
+    voter_weight_record: Account<'info, VoterWeightRecord>,
+
+    /// The program id of the spl-governance program the realm belongs to
+    /// CHECK: Can be any instance of spl-governance and it's not known at the compilation time
+    #[account(executable)]
+    governance_program_id: UncheckedAccount<'info>,
+
+    /// Vote record of the delegate, created by spl-governance.
+    /// CHECK: Created and checked by spl-governance
+    #[account(mut)]
+    vote_record_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(owner = governance_program_id.key())]
+    realm_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(owner = governance_program_id.key())]
+    realm_config_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(owner = governance_program_id.key())]
+    governance_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(mut, owner = governance_program_id.key())]
+    proposal_info: UncheckedAccount<'info>,
+
+    /// Token Owner Record of the proposal owner.
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(mut, owner = governance_program_id.key())]
+    proposal_owner_record_info: UncheckedAccount<'info>,
+
+    /// Token Owner Record for Delegate.
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(mut, owner = governance_program_id.key())]
+    delegate_token_owner_record_info: UncheckedAccount<'info>,
+
+    /// Either the realm community mint or the council mint.
+    /// CHECK: Owned by a token program, and checked against the realm during execution
+    #[account(constraint = is_token_mint_owner(realm_governing_token_mint.owner) @ DelegationError::InvalidMintOwner)]
+    realm_governing_token_mint: UncheckedAccount<'info>,
+
+    system_program: Program<'info, System>,
+}
+
+pub fn aggregate_and_cast_vote<'info>(
+    ctx: Context<'_, '_, '_, 'info, AggregateAndCastVote<'info>>,
+    vote: Vote,
+) -> Result<()> {
+    aggregate_delegations(
+        &mut ctx.accounts.voter_weight_record,
+        ctx.accounts.delegate.key(),
+        ctx.remaining_accounts,
+        None,
+        &mut ctx.accounts.settings,
+        ctx.accounts.governance_program_id.key,
+        &ctx.accounts.payer,
+        &ctx.accounts.system_program,
+    )?;
+
+    // spl-governance reads the record during the CPI, so it is written out first.
+    ctx.accounts.voter_weight_record.voter_weight_expiry = Some(Clock::get()?.slot);
+    ctx.accounts.voter_weight_record.exit(&crate::id())?;
+
+    invoke(
+        &ctx.accounts.get_cast_vote_instruction(vote),
+        &[
+            ctx.accounts.realm_info.to_account_info(),
+            ctx.accounts.governance_info.to_account_info(),
+            ctx.accounts.proposal_info.to_account_info(),
+            ctx.accounts.proposal_owner_record_info.to_account_info(),
+            ctx.accounts
+                .delegate_token_owner_record_info
+                .to_account_info(),
+            ctx.accounts.delegate.to_account_info(),
+            ctx.accounts.vote_record_info.to_account_info(),
+            ctx.accounts.realm_governing_token_mint.to_account_info(),
+            ctx.accounts.payer.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.realm_config_info.to_account_info(),
+            ctx.accounts.voter_weight_record.to_account_info(),
+        ],
+    )?;
+
+    Ok(())
+}
+
+impl<'info> AggregateAndCastVote<'info> {
+    pub fn get_cast_vote_instruction(&self, vote: Vote) -> Instruction {
+        spl_governance::instruction::cast_vote(
+            &self.governance_program_id.key(),
+            &self.realm_info.key(),
+            &self.governance_info.key(),
+            &self.proposal_info.key(),
+            &self.proposal_owner_record_info.key(),
+            &self.delegate_token_owner_record_info.key(),
+            &self.delegate.key(),
+            &self.realm_governing_token_mint.key(),
+            &self.payer.key(),
+            Some(self.voter_weight_record.key()),
+            None,
+            vote.into(),
+        )
+    }
+}
@@ -0,0 +1,183 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{instruction::Instruction, program::invoke},
+};
+use spl_governance::state::governance::get_governance_data_for_realm;
+
+use crate::{
+    error::DelegationError,
+    instructions::aggregate_delegations,
+    state::{
+        settings::Settings,
+        vote::VoteType,
+        voter_weight_record::{VoterWeightAction, VoterWeightRecord},
+    },
+    tools::is_token_mint_owner,
+};
+
+/**
+ * Aggregates delegated voter weight and creates a proposal with it
+ *
+ * This instruction combines `update_voter_weight_record` and spl-governance
+ * `create_proposal`, for the same reason as `aggregate_and_cast_vote`. The delegate's
+ * CreateProposal VoterWeightRecord for the governance must already exist, and the additional
+ * accounts are those of `update_voter_weight_record` for the sources.
+ *
+ * The proposal is created for the governing token mint of the record, at the next index of
+ * the governance. Its name, description link, options and vote type are passed through to
+ * spl-governance unchanged.
+ */
+#[derive(Accounts)]
+pub struct AggregateAndCreateProposal<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+
+    delegate: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"settings".as_ref(),
+            realm_info.key().as_ref(),
+            realm_governing_token_mint.key().as_ref(),
+        ],
+        bump
+    )]
+    settings: Account<'info, Settings>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"voter-weight-record".as_ref(),
+            realm_info.key().as_ref(),
+            realm_governing_token_mint.key().as_ref(),
+            delegate.key().as_ref(),
+            governance_info.key().as_ref(),
+            &borsh::to_vec(&Some(VoterWeightAction::CreateProposal)).unwrap()
+        ],
+        bump,
+        owner = crate::id()
+    )]
+    voter_weight_record: Account<'info, VoterWeightRecord>,
+
+    /// The program id of the spl-governance program the realm belongs to
+    /// CHECK: Can be any instance of spl-governance and it's not known at the compilation time
+    #[account(executable)]
+    governance_program_id: UncheckedAccount<'info>,
+
+    /// The proposal, created by spl-governance.
+    /// CHECK: Created and checked by spl-governance
+    #[account(mut)]
+    proposal_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(owner = governance_program_id.key())]
+    realm_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(owner = governance_program_id.key())]
+    realm_config_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(mut, owner = governance_program_id.key())]
+    governance_info: UncheckedAccount<'info>,
+
+    /// Token Owner Record for Delegate, which becomes the owner of the proposal.
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(mut, owner = governance_program_id.key())]
+    delegate_token_owner_record_info: UncheckedAccount<'info>,
+
+    /// Either the realm community mint or the council mint.
+    /// CHECK: Owned by a token program, and checked against the realm during execution
+    #[account(constraint = is_token_mint_owner(realm_governing_token_mint.owner) @ DelegationError::InvalidMintOwner)]
+    realm_governing_token_mint: UncheckedAccount<'info>,
+
+    system_program: Program<'info, System>,
+}
+
+pub fn aggregate_and_create_proposal<'info>(
+    ctx: Context<'_, '_, '_, 'info, AggregateAndCreateProposal<'info>>,
+    name: String,
+    description_link: String,
+    vote_type: VoteType,
+    options: Vec<String>,
+    use_deny_option: bool,
+) -> Result<()> {
+    aggregate_delegations(
+        &mut ctx.accounts.voter_weight_record,
+        ctx.accounts.delegate.key(),
+        ctx.remaining_accounts,
+        None,
+        &mut ctx.accounts.settings,
+        ctx.accounts.governance_program_id.key,
+        &ctx.accounts.payer,
+        &ctx.accounts.system_program,
+    )?;
+
+    // spl-governance reads the record during the CPI, so it is written out first.
+    ctx.accounts.voter_weight_record.voter_weight_expiry = Some(Clock::get()?.slot);
+    ctx.accounts.voter_weight_record.exit(&crate::id())?;
+
+    let proposal_index = get_governance_data_for_realm(
+        ctx.accounts.governance_program_id.key,
+        &ctx.accounts.governance_info.to_account_info(),
+        ctx.accounts.realm_info.key,
+    )?
+    .proposals_count;
+
+    invoke(
+        &ctx.accounts.get_create_proposal_instruction(
+            name,
+            description_link,
+            vote_type,
+            options,
+            use_deny_option,
+            proposal_index,
+        ),
+        &[
+            ctx.accounts.realm_info.to_account_info(),
+            ctx.accounts.proposal_info.to_account_info(),
+            ctx.accounts.governance_info.to_account_info(),
+            ctx.accounts
+                .delegate_token_owner_record_info
+                .to_account_info(),
+            ctx.accounts.realm_governing_token_mint.to_account_info(),
+            ctx.accounts.delegate.to_account_info(),
+            ctx.accounts.payer.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.realm_config_info.to_account_info(),
+            ctx.accounts.voter_weight_record.to_account_info(),
+        ],
+    )?;
+
+    Ok(())
+}
+
+impl<'info> AggregateAndCreateProposal<'info> {
+    pub fn get_create_proposal_instruction(
+        &self,
+        name: String,
+        description_link: String,
+        vote_type: VoteType,
+        options: Vec<String>,
+        use_deny_option: bool,
+        proposal_index: u32,
+    ) -> Instruction {
+        spl_governance::instruction::create_proposal(
+            &self.governance_program_id.key(),
+            &self.governance_info.key(),
+            &self.delegate_token_owner_record_info.key(),
+            &self.delegate.key(),
+            &self.payer.key(),
+            Some(self.voter_weight_record.key()),
+            &self.realm_info.key(),
+            name,
+            description_link,
+            &self.realm_governing_token_mint.key(),
+            vote_type.into(),
+            options,
+            use_deny_option,
+            proposal_index,
+        )
+    }
+}
@@ -0,0 +1,124 @@
+use anchor_lang::prelude::*;
+use spl_governance::state::{
+    token_owner_record::get_token_owner_record_address, vote_record::get_vote_record_address,
+};
+
+use crate::{
+    error::DelegationError,
+    instructions::aggregate_delegations,
+    state::{settings::Settings, voter_weight_record::VoterWeightRecord},
+};
+
+/**
+ * Aggregates delegated voter weight into a delegate's record without their signature
+ *
+ * Aggregation only ever adds voter weight delegated to the owner of the VoterWeightRecord,
+ * so anyone, such as a keeper bot, can add delegators to it. The payer funds the Delegation
+ * records and is refunded when they are closed, while the rent of ledger entries goes back to
+ * the delegate. The additional accounts are those of `update_voter_weight_record`.
+ *
+ * Unlike `update_voter_weight_record`, the expiry, action and target of the record are left
+ * unchanged. Delegators can only revoke once the record has expired, so only the delegate
+ * can extend it, for example by aggregating without any sources right before casting their
+ * vote.
+ *
+ * The record must not have expired, and the delegate must not have voted on its target yet.
+ * Weight added after that would not be in the vote, but would still be taken out of it when
+ * the delegator revokes.
+ */
+#[derive(Accounts)]
+pub struct CrankVoterWeightRecord<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"settings".as_ref(),
+            realm.key().as_ref(),
+            voter_weight_record.governing_token_mint.key().as_ref(),
+        ],
+        bump
+    )]
+    settings: Account<'info, Settings>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"voter-weight-record".as_ref(),
+            voter_weight_record.realm.key().as_ref(),
+            voter_weight_record.governing_token_mint.key().as_ref(),
+            voter_weight_record.governing_token_owner.as_ref(),
+            voter_weight_record.weight_action_target.unwrap().as_ref(),
+            &borsh::to_vec(&voter_weight_record.weight_action).unwrap()
+        ],
+        bump,
+        owner = crate::id()
+    )]
+    voter_weight_record: Account<'info, VoterWeightRecord>,
+
+    system_program: Program<'info, System>,
+
+    /// The program id of the spl-governance program the realm belongs to
+    /// CHECK: Can be any instance of spl-governance and it's not known at the compilation time
+    #[account(executable)]
+    governance_program_id: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(owner = governance_program_id.key())]
+    realm: UncheckedAccount<'info>,
+
+    /// The vote record of the delegate on the target, which must not exist yet.
+    /// CHECK: Address checked during execution
+    vote_record: UncheckedAccount<'info>,
+}
+
+pub fn crank_voter_weight_record<'info>(
+    ctx: Context<'_, '_, '_, 'info, CrankVoterWeightRecord<'info>>,
+) -> Result<()> {
+    require_keys_eq!(
+        ctx.accounts.realm.key(),
+        ctx.accounts.voter_weight_record.realm,
+        DelegationError::InvalidRealm
+    );
+
+    require!(
+        ctx.accounts.voter_weight_record.voter_weight_expiry >= Some(Clock::get()?.slot),
+        DelegationError::VoterWeightRecordExpired
+    );
+
+    let delegate = ctx.accounts.voter_weight_record.governing_token_owner;
+    let delegate_token_owner_record = get_token_owner_record_address(
+        ctx.accounts.governance_program_id.key,
+        ctx.accounts.realm.key,
+        &ctx.accounts.voter_weight_record.governing_token_mint,
+        &delegate,
+    );
+    require_keys_eq!(
+        ctx.accounts.vote_record.key(),
+        get_vote_record_address(
+            ctx.accounts.governance_program_id.key,
+            &ctx.accounts
+                .voter_weight_record
+                .weight_action_target
+                .unwrap(),
+            &delegate_token_owner_record,
+        ),
+        DelegationError::IncorrectVoteRecord
+    );
+    require!(
+        ctx.accounts.vote_record.data_is_empty(),
+        DelegationError::CannotIncreaseCastVote
+    );
+
+    aggregate_delegations(
+        &mut ctx.accounts.voter_weight_record,
+        delegate,
+        ctx.remaining_accounts,
+        None,
+        &mut ctx.accounts.settings,
+        ctx.accounts.governance_program_id.key,
+        &ctx.accounts.payer,
+        &ctx.accounts.system_program,
+    )
+}
@@ -0,0 +1,81 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::DelegationError,
+    state::{delegation_ledger::DelegationLedger, voter_weight_record::VoterWeightAction},
+    tools::is_token_mint_owner,
+};
+
+/**
+ * Creates an empty DelegationLedger
+ *
+ * When the realm records delegations in a ledger, one must be created for each target and
+ * action before voter weight can be aggregated for it. Anyone can create a ledger, and the
+ * payer gets its rent back once the last entry of the ledger is reclaimed.
+ */
+#[derive(Accounts)]
+#[instruction(target: Pubkey, action: VoterWeightAction)]
+pub struct CreateDelegationLedger<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+
+    #[account(
+        init,
+        seeds = [
+            b"delegation-ledger".as_ref(),
+            realm.key().as_ref(),
+            realm_governing_token_mint.key().as_ref(),
+            target.as_ref(),
+            &borsh::to_vec(&Some(action)).unwrap()
+        ],
+        bump,
+        payer = payer,
+        space = DelegationLedger::HEADER_SIZE,
+        owner = crate::ID
+    )]
+    delegation_ledger: Account<'info, DelegationLedger>,
+
+    /// The program id of the spl-governance program the realm belongs to
+    /// CHECK: Can be any instance of spl-governance and it's not known at the compilation time
+    #[account(executable)]
+    pub governance_program_id: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(owner = governance_program_id.key())]
+    pub realm: UncheckedAccount<'info>,
+
+    /// Either the realm community mint or the council mint.
+    /// CHECK: Owned by a token program, and checked against the realm during execution
+    #[account(constraint = is_token_mint_owner(realm_governing_token_mint.owner) @ DelegationError::InvalidMintOwner)]
+    pub realm_governing_token_mint: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn create_delegation_ledger(
+    ctx: Context<CreateDelegationLedger>,
+    target: Pubkey,
+    action: VoterWeightAction,
+) -> Result<()> {
+    require!(
+        action != VoterWeightAction::RevokeVote,
+        DelegationError::InvalidActionType
+    );
+
+    spl_governance::state::realm::get_realm_data_for_governing_token_mint(
+        &ctx.accounts.governance_program_id.key(),
+        &ctx.accounts.realm,
+        &ctx.accounts.realm_governing_token_mint.key(),
+    )?;
+
+    let delegation_ledger = &mut ctx.accounts.delegation_ledger;
+
+    delegation_ledger.realm = ctx.accounts.realm.key();
+    delegation_ledger.governing_token_mint = ctx.accounts.realm_governing_token_mint.key();
+    delegation_ledger.target = target;
+    delegation_ledger.action = action;
+    delegation_ledger.creator = ctx.accounts.payer.key();
+    delegation_ledger.entry_count = 0;
+
+    Ok(())
+}
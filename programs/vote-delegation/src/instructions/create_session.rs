@@ -0,0 +1,66 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::DelegationError,
+    state::{session::Session, voter_weight_record::VoterWeightAction},
+};
+
+/**
+ * Registers a session key for a delegate
+ *
+ * The session key can then sign `update_voter_weight_record` for the delegate, until the
+ * expiry slot, for the allowed actions and, if a realm is given, only in that realm. The
+ * delegate can end the session at any time with `revoke_session`.
+ */
+#[derive(Accounts)]
+#[instruction(session_key: Pubkey)]
+pub struct CreateSession<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+
+    delegate: Signer<'info>,
+
+    #[account(
+        init,
+        seeds = [
+            b"session".as_ref(),
+            delegate.key().as_ref(),
+            session_key.as_ref(),
+        ],
+        bump,
+        payer = payer,
+        space = Session::size()
+    )]
+    session: Account<'info, Session>,
+
+    system_program: Program<'info, System>,
+}
+
+pub fn create_session(
+    ctx: Context<CreateSession>,
+    session_key: Pubkey,
+    realm: Option<Pubkey>,
+    allowed_actions: Vec<VoterWeightAction>,
+    expiry: u64,
+) -> Result<()> {
+    require!(
+        !allowed_actions.is_empty()
+            && allowed_actions.len() <= Session::MAX_ACTIONS
+            && !allowed_actions.contains(&VoterWeightAction::RevokeVote),
+        DelegationError::InvalidSessionActions
+    );
+    require!(
+        expiry >= Clock::get()?.slot,
+        DelegationError::SessionExpired
+    );
+
+    ctx.accounts.session.set_inner(Session {
+        delegate: ctx.accounts.delegate.key(),
+        session_key,
+        realm,
+        allowed_actions,
+        expiry,
+    });
+
+    Ok(())
+}
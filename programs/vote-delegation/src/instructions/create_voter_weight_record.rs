@@ -2,15 +2,21 @@ use anchor_lang::prelude::*;
 
 use crate::{
     error::DelegationError,
-    state::voter_weight_record::{VoterWeightAction, VoterWeightRecord},
+    state::{
+        rent_payer::RentPayer,
+        voter_weight_record::{VoterWeightAction, VoterWeightRecord},
+    },
+    tools::is_token_mint_owner,
 };
-use anchor_spl::token::Mint;
 
 /**
  * Creates a new VoterWeightRecord
  *
  * You must create a VoterWeightRecord for each target and action you wish to use. Created
- * VoterWeightRecords initially have 0 voter weight.
+ * VoterWeightRecords initially have 0 voter weight. The payer is recorded so that anyone
+ * can refund them once the target is finished, see `refund_voter_weight_record`.
+ *
+ * Records for the council mint may target a community proposal, to veto it.
  */
 #[derive(Accounts)]
 #[instruction(governing_token_owner: Pubkey, target: Pubkey, action: VoterWeightAction)]
@@ -35,6 +41,15 @@ pub struct CreateVoterWeightRecord<'info> {
     )]
     voter_weight_record: Account<'info, VoterWeightRecord>,
 
+    #[account(
+        init,
+        seeds = [b"rent-payer".as_ref(), voter_weight_record.key().as_ref()],
+        bump,
+        payer = payer,
+        space = 8 + std::mem::size_of::<RentPayer>()
+    )]
+    rent_payer: Account<'info, RentPayer>,
+
     /// The program id of the spl-governance program the realm belongs to
     /// CHECK: Can be any instance of spl-governance and it's not known at the compilation time
     #[account(executable)]
@@ -45,7 +60,9 @@ pub struct CreateVoterWeightRecord<'info> {
     pub realm: UncheckedAccount<'info>,
 
     /// Either the realm community mint or the council mint.
-    pub realm_governing_token_mint: Account<'info, Mint>,
+    /// CHECK: Owned by a token program, and checked against the realm during execution
+    #[account(constraint = is_token_mint_owner(realm_governing_token_mint.owner) @ DelegationError::InvalidMintOwner)]
+    pub realm_governing_token_mint: UncheckedAccount<'info>,
 
     pub system_program: Program<'info, System>,
 }
@@ -78,5 +95,7 @@ pub fn create_voter_weight_record(
     // Set expiry to expired
     voter_weight_record.voter_weight_expiry = Some(0);
 
+    ctx.accounts.rent_payer.payer = ctx.accounts.payer.key();
+
     Ok(())
 }
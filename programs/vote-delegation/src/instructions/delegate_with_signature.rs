@@ -0,0 +1,84 @@
+use anchor_lang::{prelude::*, solana_program::sysvar};
+
+use crate::{
+    error::DelegationError,
+    state::signed_delegation::{DelegationMessage, SignedDelegation},
+    tools::assert_ed25519_signature,
+};
+
+/**
+ * Delegates voter weight with a message signed by the delegator
+ *
+ * The delegator signs a DelegationMessage off-chain and anyone can relay it, paying the
+ * fees and the rent of the delegator's SignedDelegation. The instruction must directly
+ * follow an ed25519 program instruction verifying the delegator's signature over
+ * `DelegationMessage::to_signed_bytes`, with the signature, key and message in its own data.
+ *
+ * Each message must carry the next nonce of the record, so it can only be relayed once. A
+ * message without a delegate ends the delegation; voter weight it already aggregated stays
+ * delegated until the delegator revokes it.
+ */
+#[derive(Accounts)]
+#[instruction(message: DelegationMessage)]
+pub struct DelegateWithSignature<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+
+    /// CHECK: Proven by the signature over the message
+    delegator: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        seeds = [
+            b"signed-delegation".as_ref(),
+            message.realm.as_ref(),
+            message.governing_token_mint.as_ref(),
+            delegator.key().as_ref(),
+        ],
+        bump,
+        payer = payer,
+        space = SignedDelegation::size()
+    )]
+    signed_delegation: Account<'info, SignedDelegation>,
+
+    /// CHECK: The instructions sysvar
+    #[account(address = sysvar::instructions::ID)]
+    instructions: UncheckedAccount<'info>,
+
+    system_program: Program<'info, System>,
+}
+
+pub fn delegate_with_signature(
+    ctx: Context<DelegateWithSignature>,
+    message: DelegationMessage,
+) -> Result<()> {
+    require_eq!(
+        message.nonce,
+        ctx.accounts.signed_delegation.next_nonce,
+        DelegationError::InvalidDelegationNonce
+    );
+    if message.delegate.is_some() {
+        require!(
+            message.expiry >= Clock::get()?.slot,
+            DelegationError::DelegationMessageExpired
+        );
+    }
+
+    assert_ed25519_signature(
+        &ctx.accounts.instructions,
+        ctx.accounts.delegator.key,
+        &message.to_signed_bytes(),
+    )?;
+
+    ctx.accounts.signed_delegation.set_inner(SignedDelegation {
+        realm: message.realm,
+        governing_token_mint: message.governing_token_mint,
+        delegator: ctx.accounts.delegator.key(),
+        delegate: message.delegate,
+        scope: message.scope,
+        expiry: message.expiry,
+        next_nonce: message.nonce + 1,
+    });
+
+    Ok(())
+}
@@ -1,13 +1,49 @@
+pub mod aggregate_and_cast_vote;
+pub mod aggregate_and_create_proposal;
+pub mod crank_voter_weight_record;
+pub mod create_delegation_ledger;
+pub mod create_session;
 pub mod create_voter_weight_record;
+pub mod delegate_with_signature;
+pub mod override_vote;
 pub mod reclaim_delegation;
+pub mod reclaim_delegations;
 pub mod reclaim_voter_weight_record;
+pub mod reclaim_voter_weight_records;
+pub mod refresh_delegation;
+pub mod refund_voter_weight_record;
+pub mod release_delegation;
+pub mod revoke_delegation;
+pub mod revoke_partial;
+pub mod revoke_session;
+pub mod revoke_stale_delegation;
 pub mod revoke_vote;
+pub mod revoke_votes;
+pub mod set_delegation_store;
 pub mod set_precursor;
 pub mod update_voter_weight_record;
 
+pub use aggregate_and_cast_vote::*;
+pub use aggregate_and_create_proposal::*;
+pub use crank_voter_weight_record::*;
+pub use create_delegation_ledger::*;
+pub use create_session::*;
 pub use create_voter_weight_record::*;
+pub use delegate_with_signature::*;
+pub use override_vote::*;
 pub use reclaim_delegation::*;
+pub use reclaim_delegations::*;
 pub use reclaim_voter_weight_record::*;
+pub use reclaim_voter_weight_records::*;
+pub use refresh_delegation::*;
+pub use refund_voter_weight_record::*;
+pub use release_delegation::*;
+pub use revoke_delegation::*;
+pub use revoke_partial::*;
+pub use revoke_session::*;
+pub use revoke_stale_delegation::*;
 pub use revoke_vote::*;
+pub use revoke_votes::*;
+pub use set_delegation_store::*;
 pub use set_precursor::*;
 pub use update_voter_weight_record::*;
@@ -0,0 +1,322 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{instruction::Instruction, program::invoke},
+};
+
+use crate::{
+    error::DelegationError,
+    state::{
+        delegation::{Delegation, DelegationVersion},
+        settings::Settings,
+        vote::Vote,
+        voter_weight_record::{VoterWeightAction, VoterWeightRecord},
+    },
+    tools::{dispose_account, grow_account, is_token_mint_owner, RelinquishAccounts},
+};
+
+/**
+ * Revokes delegated vote weight and casts the delegator's own vote with it
+ *
+ * This instruction can be called by a delegator who disagrees with the vote cast (or about
+ * to be cast) by their delegate. The delegated voter weight is revoked exactly as in
+ * `revoke_vote`, then credited to the delegator's own VoterWeightRecord for the proposal,
+ * which is used to cast the provided vote through spl-governance.
+ *
+ * The delegation record is kept and reassigned to the delegator so the same voter weight
+ * cannot be delegated again for this proposal.
+ */
+#[derive(Accounts)]
+pub struct OverrideVote<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+
+    /// This account is created, used, and freed within this instruction.
+    #[account(
+        init,
+        seeds = [
+            b"revocation".as_ref(),
+            realm_info.key().as_ref(),
+            realm_governing_token_mint.key().as_ref(),
+            governing_token_owner.key().as_ref(),
+            delegated_voter_weight_record.weight_action_target.unwrap().key().as_ref(),
+            &borsh::to_vec(&delegated_voter_weight_record.weight_action).unwrap()
+        ],
+        bump,
+        payer = payer,
+        space = 8 + std::mem::size_of::<VoterWeightRecord>(),
+        owner = crate::ID
+    )]
+    revoke_weight_record: Account<'info, VoterWeightRecord>,
+
+    /// User who cast the vote which is now being overridden.
+    /// CHECK: Delegate
+    #[account(mut)]
+    delegate: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"voter-weight-record-delegation".as_ref(),
+            realm_info.key().as_ref(),
+            realm_governing_token_mint.key().as_ref(),
+            governing_token_owner.key().as_ref(),
+            delegated_voter_weight_record.weight_action_target.unwrap().key().as_ref(),
+            &borsh::to_vec(&delegated_voter_weight_record.weight_action).unwrap()
+        ],
+        bump
+    )]
+    delegation_record: Account<'info, Delegation>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"voter-weight-record".as_ref(),
+            realm_info.key().as_ref(),
+            realm_governing_token_mint.key().as_ref(),
+            delegate.key().as_ref(),
+            delegated_voter_weight_record.weight_action_target.unwrap().key().as_ref(),
+            &borsh::to_vec(&delegated_voter_weight_record.weight_action).unwrap()
+        ],
+        bump,
+        owner = crate::ID
+    )]
+    delegated_voter_weight_record: Account<'info, VoterWeightRecord>,
+
+    /// The delegator's own record, used to cast their vote.
+    #[account(
+        init_if_needed,
+        seeds = [
+            b"voter-weight-record".as_ref(),
+            realm_info.key().as_ref(),
+            realm_governing_token_mint.key().as_ref(),
+            governing_token_owner.key().as_ref(),
+            proposal_info.key().as_ref(),
+            &borsh::to_vec(&Some(VoterWeightAction::CastVote)).unwrap()
+        ],
+        bump,
+        payer = payer,
+        space = 8 + std::mem::size_of::<VoterWeightRecord>(),
+        owner = crate::ID
+    )]
+    voter_weight_record: Account<'info, VoterWeightRecord>,
+
+    /// The program id of the spl-governance program the realm belongs to
+    /// CHECK: Can be any instance of spl-governance and it's not known at the compilation time
+    #[account(executable)]
+    governance_program_id: UncheckedAccount<'info>,
+
+    /// Vote record of the delegate.
+    /// CHECK: Ownership checked during execution
+    #[account(mut)]
+    vote_record_info: UncheckedAccount<'info>,
+
+    /// Vote record of the delegator, created by spl-governance.
+    /// CHECK: Created and checked by spl-governance
+    #[account(mut)]
+    governing_token_owner_vote_record_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(owner = governance_program_id.key())]
+    realm_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(owner = governance_program_id.key())]
+    realm_config_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(owner = governance_program_id.key())]
+    governance_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(mut, owner = governance_program_id.key())]
+    proposal_info: UncheckedAccount<'info>,
+
+    /// Token Owner Record of the proposal owner.
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(mut, owner = governance_program_id.key())]
+    proposal_owner_record_info: UncheckedAccount<'info>,
+
+    /// Token Owner Record for Delegate.
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(mut, owner = governance_program_id.key())]
+    delegate_token_owner_record_info: UncheckedAccount<'info>,
+
+    /// Token Owner Record for the user overriding their delegate.
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(mut, owner = governance_program_id.key())]
+    governing_token_owner_record_info: UncheckedAccount<'info>,
+
+    /// Either the realm community mint or the council mint.
+    /// CHECK: Owned by a token program, and checked against the realm during execution
+    #[account(constraint = is_token_mint_owner(realm_governing_token_mint.owner) @ DelegationError::InvalidMintOwner)]
+    realm_governing_token_mint: UncheckedAccount<'info>,
+
+    /// User who wants to cast their own vote.
+    governing_token_owner: Signer<'info>,
+
+    system_program: Program<'info, System>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"settings".as_ref(),
+            realm_info.key().as_ref(),
+            realm_governing_token_mint.key().as_ref(),
+        ],
+        bump
+    )]
+    settings: Account<'info, Settings>,
+}
+
+pub fn override_vote(ctx: Context<OverrideVote>, vote: Vote) -> Result<()> {
+    require_keys_eq!(
+        ctx.accounts.delegation_record.delegate,
+        ctx.accounts.delegate.key(),
+        DelegationError::NonMatchingDelegationRecordProvided
+    );
+    require!(
+        ctx.accounts.delegated_voter_weight_record.weight_action
+            == Some(VoterWeightAction::CastVote),
+        DelegationError::InvalidActionType
+    );
+    require_keys_eq!(
+        ctx.accounts
+            .delegated_voter_weight_record
+            .weight_action_target
+            .unwrap(),
+        ctx.accounts.proposal_info.key(),
+        DelegationError::IncorrectTarget
+    );
+
+    let vote_cast = ctx.accounts.relinquish_accounts().load_vote()?;
+
+    let voter_weight = ctx.accounts.delegation_record.voter_weight;
+
+    ctx.accounts.delegated_voter_weight_record.voter_weight = ctx
+        .accounts
+        .delegated_voter_weight_record
+        .voter_weight
+        .checked_sub(voter_weight)
+        .unwrap();
+
+    // See revoke_vote for why the delegate's record must be expired.
+    if ctx
+        .accounts
+        .delegated_voter_weight_record
+        .voter_weight_expiry
+        >= Some(Clock::get()?.slot)
+    {
+        return Err(DelegationError::VoterWeightRecordMustBeExpired.into());
+    }
+
+    if vote_cast {
+        ctx.accounts
+            .relinquish_accounts()
+            .relinquish_weight(ctx.accounts.governing_token_owner.key, voter_weight)?;
+    }
+
+    // This account is disposed here to prevent double-relinquishment.
+    dispose_account(
+        &ctx.accounts.revoke_weight_record.to_account_info(),
+        &ctx.accounts.payer,
+    );
+
+    Settings::migrate(
+        &mut ctx.accounts.settings,
+        &ctx.accounts.payer,
+        &ctx.accounts.system_program,
+    )?;
+
+    // V1 records are upgraded to store the delegate who paid for them, as they are about to
+    // be replaced as delegate. They are counted in the settings from now on.
+    if ctx.accounts.delegation_record.version == DelegationVersion::V1 {
+        grow_account(
+            &ctx.accounts.delegation_record.to_account_info(),
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            Delegation::size(),
+        )?;
+        let (rent_payer, settings) = (ctx.accounts.delegate.key(), ctx.accounts.settings.key());
+        let bump = *ctx.bumps.get("delegation_record").unwrap();
+        ctx.accounts
+            .delegation_record
+            .upgrade(rent_payer, settings, bump);
+        ctx.accounts.settings.open_delegations += 1;
+    }
+
+    // Keeping the delegation record prevents the weight from being delegated again.
+    ctx.accounts.delegation_record.delegate = ctx.accounts.governing_token_owner.key();
+    ctx.accounts.delegation_record.voter_weight_record = ctx.accounts.voter_weight_record.key();
+
+    let voter_weight_record = &mut ctx.accounts.voter_weight_record;
+    voter_weight_record.realm = ctx.accounts.realm_info.key();
+    voter_weight_record.governing_token_mint = ctx.accounts.realm_governing_token_mint.key();
+    voter_weight_record.governing_token_owner = ctx.accounts.governing_token_owner.key();
+    voter_weight_record.voter_weight = voter_weight_record
+        .voter_weight
+        .checked_add(voter_weight)
+        .unwrap();
+    voter_weight_record.voter_weight_expiry = Some(Clock::get()?.slot);
+    voter_weight_record.weight_action = Some(VoterWeightAction::CastVote);
+    voter_weight_record.weight_action_target = Some(ctx.accounts.proposal_info.key());
+    voter_weight_record.exit(&crate::id())?;
+
+    invoke(
+        &ctx.accounts.get_cast_vote_instruction(vote),
+        &[
+            ctx.accounts.realm_info.to_account_info(),
+            ctx.accounts.governance_info.to_account_info(),
+            ctx.accounts.proposal_info.to_account_info(),
+            ctx.accounts.proposal_owner_record_info.to_account_info(),
+            ctx.accounts
+                .governing_token_owner_record_info
+                .to_account_info(),
+            ctx.accounts.governing_token_owner.to_account_info(),
+            ctx.accounts
+                .governing_token_owner_vote_record_info
+                .to_account_info(),
+            ctx.accounts.realm_governing_token_mint.to_account_info(),
+            ctx.accounts.payer.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.realm_config_info.to_account_info(),
+            ctx.accounts.voter_weight_record.to_account_info(),
+        ],
+    )?;
+
+    Ok(())
+}
+
+impl<'info> OverrideVote<'info> {
+    fn relinquish_accounts(&self) -> RelinquishAccounts<'_, 'info> {
+        RelinquishAccounts {
+            governance_program_id: &self.governance_program_id,
+            realm: &self.realm_info,
+            realm_config: &self.realm_config_info,
+            governance: &self.governance_info,
+            proposal: &self.proposal_info,
+            delegate_token_owner_record: &self.delegate_token_owner_record_info,
+            vote_record: &self.vote_record_info,
+            governing_token_mint: self.realm_governing_token_mint.as_ref(),
+            governance_authority: &self.governing_token_owner,
+            beneficiary: &self.delegate,
+            revoke_weight_record: self.revoke_weight_record.as_ref(),
+        }
+    }
+
+    pub fn get_cast_vote_instruction(&self, vote: Vote) -> Instruction {
+        spl_governance::instruction::cast_vote(
+            &self.governance_program_id.key(),
+            &self.realm_info.key(),
+            &self.governance_info.key(),
+            &self.proposal_info.key(),
+            &self.proposal_owner_record_info.key(),
+            &self.governing_token_owner_record_info.key(),
+            &self.governing_token_owner.key(),
+            &self.realm_governing_token_mint.key(),
+            &self.payer.key(),
+            Some(self.voter_weight_record.key()),
+            None,
+            vote.into(),
+        )
+    }
+}
@@ -1,39 +1,168 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{prelude::*, Discriminator};
 
-use crate::{error::DelegationError, state::delegation::Delegation};
+use crate::{
+    error::DelegationError,
+    state::{
+        delegation::Delegation, delegation_ledger::DelegationLedger, settings::Settings,
+        voter_weight_record::VoterWeightRecord,
+    },
+    tools::close_account,
+};
 
+/**
+ * Reclaims lamports from a delegation record
+ *
+ * The delegation can be reclaimed once the delegate's VoterWeightRecord has been closed.
+ * If it has not been, the following accounts can be provided as additional accounts to
+ * show that the target of the VoterWeightRecord can be reclaimed instead, as checked by
+ * `VoterWeightRecord::assert_can_reclaim_target`:
+ *  - The target of the VoterWeightRecord (not signer, not writable).
+ *  - The spl-governance program the target belongs to (not signer, not writable).
+ *
+ * The voter weight of the delegation is then taken off the VoterWeightRecord.
+ *
+ * Delegations recorded in a DelegationLedger are reclaimed by passing the ledger as
+ * `delegation`, once the delegate's VoterWeightRecord has been closed. Every entry delegated
+ * to the delegate is removed and its rent returned to them. The ledger is closed when its
+ * last entry is removed, and its rent returned to its creator, passed as `rent_payer`.
+ */
 #[derive(Accounts)]
 pub struct ReclaimDelegation<'info> {
     #[account(mut)]
     payer: Signer<'info>,
 
-    /// CHECK: Account to refund.
+    /// CHECK: Delegate of the delegation record, refunded the rent of their ledger entries.
     #[account(mut)]
     delegate: AccountInfo<'info>,
 
-    /// CHECK: Not deserialized, but must already be zeroed to continue.
+    /// CHECK: Deserialized during execution if it has not been closed.
+    #[account(mut)]
     voter_weight_record: AccountInfo<'info>,
 
-    #[account(mut, close = delegate)]
-    delegation: Account<'info, Delegation>,
+    /// The Delegation record, or the DelegationLedger holding the delegate's entries
+    /// CHECK: Deserialized during execution, as a ledger may be closed.
+    #[account(mut)]
+    delegation: UncheckedAccount<'info>,
+
+    /// Settings the delegation is counted in
+    /// CHECK: Checked against the delegation record or the ledger during execution
+    #[account(mut)]
+    settings: UncheckedAccount<'info>,
+
+    /// Account which paid for the delegation record, or the creator of the ledger
+    /// CHECK: Checked against the delegation during execution, and gets its rent back.
+    #[account(mut)]
+    rent_payer: UncheckedAccount<'info>,
 }
 
 pub fn reclaim_delegation(ctx: Context<ReclaimDelegation>) -> Result<()> {
+    let is_ledger = ctx
+        .accounts
+        .delegation
+        .try_borrow_data()?
+        .starts_with(&DelegationLedger::discriminator());
+
+    if is_ledger {
+        return reclaim_ledger_entries(ctx);
+    }
+
+    let delegation = Account::<Delegation>::try_from(&ctx.accounts.delegation)?;
+
+    require_keys_eq!(
+        ctx.accounts.rent_payer.key(),
+        delegation.rent_payer,
+        DelegationError::IncorrectRentPayer
+    );
+    require_keys_eq!(
+        delegation.voter_weight_record,
+        ctx.accounts.voter_weight_record.key(),
+        DelegationError::IncorrectDelegationAddress
+    );
+    require_keys_eq!(
+        delegation.delegate,
+        ctx.accounts.delegate.key(),
+        DelegationError::NonMatchingDelegationRecordProvided,
+    );
+
+    if !ctx.accounts.voter_weight_record.data_is_empty() {
+        require_eq!(
+            ctx.remaining_accounts.len(),
+            2,
+            DelegationError::CannotReclaimDelegationRecordYet
+        );
+        let target_info = &ctx.remaining_accounts[0];
+        let governance_program_info = &ctx.remaining_accounts[1];
+        require!(
+            governance_program_info.executable,
+            ErrorCode::ConstraintExecutable
+        );
+
+        let mut voter_weight_record =
+            Account::<VoterWeightRecord>::try_from(&ctx.accounts.voter_weight_record)?;
+        voter_weight_record.assert_can_reclaim_target(
+            ctx.accounts.voter_weight_record.key,
+            target_info,
+            governance_program_info.key,
+        )?;
+
+        // Once the delegation is closed its voter weight can be delegated again, so it must
+        // leave the record which still holds it.
+        voter_weight_record.voter_weight = voter_weight_record
+            .voter_weight
+            .checked_sub(delegation.voter_weight)
+            .unwrap();
+        voter_weight_record.exit(&crate::id())?;
+    }
+
+    delegation.close_in(&ctx.accounts.settings)?;
+
+    close_account(&ctx.accounts.delegation, &ctx.accounts.rent_payer)
+}
+
+/// Removes the ledger entries delegated to the delegate, see `reclaim_delegation`
+fn reclaim_ledger_entries(ctx: Context<ReclaimDelegation>) -> Result<()> {
     require!(
         ctx.accounts.voter_weight_record.data_is_empty(),
         DelegationError::CannotReclaimDelegationRecordYet
     );
 
+    let ledger_info = ctx.accounts.delegation.to_account_info();
+    let mut delegation_ledger = Account::<DelegationLedger>::try_from(&ledger_info)?;
+
     require_keys_eq!(
-        ctx.accounts.delegation.voter_weight_record,
+        VoterWeightRecord::get_pda_address(
+            &delegation_ledger.realm,
+            &delegation_ledger.governing_token_mint,
+            ctx.accounts.delegate.key,
+            &delegation_ledger.target,
+            Some(delegation_ledger.action),
+        ),
         ctx.accounts.voter_weight_record.key(),
         DelegationError::IncorrectDelegationAddress
     );
+
     require_keys_eq!(
-        ctx.accounts.delegation.delegate,
-        ctx.accounts.delegate.key(),
-        DelegationError::NonMatchingDelegationRecordProvided,
+        delegation_ledger.creator,
+        ctx.accounts.rent_payer.key(),
+        DelegationError::NotLedgerCreator
     );
+    require_keys_eq!(
+        Settings::get_pda_address(
+            &delegation_ledger.realm,
+            &delegation_ledger.governing_token_mint
+        ),
+        ctx.accounts.settings.key(),
+        DelegationError::IncorrectSettings
+    );
+
+    let removed = delegation_ledger.remove_entries_of(&ledger_info, &ctx.accounts.delegate)?;
+    Settings::close_delegations(&ctx.accounts.settings, removed as u64)?;
+
+    if removed > 0 && delegation_ledger.entry_count == 0 {
+        close_account(&ledger_info, &ctx.accounts.rent_payer)?;
+    } else {
+        delegation_ledger.exit(&crate::id())?;
+    }
 
     Ok(())
 }
@@ -0,0 +1,105 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::DelegationError, state::delegation::Delegation, tools::close_account};
+
+/**
+ * Reclaims lamports from several delegation records at once
+ *
+ * This instruction behaves like `reclaim_delegation` for every record provided. Records
+ * which cannot be reclaimed yet, or are provided with the wrong rent payer or settings,
+ * are skipped and the reason logged.
+ *
+ * For each delegation to reclaim, the following accounts must be provided as additional
+ * accounts:
+ *  - The delegate's VoterWeightRecord the delegation was aggregated into, which must
+ * already be closed (not signer, not writable).
+ *  - The Delegation PDA account (not signer, writable).
+ *  - The account which paid for the Delegation, `Delegation::rent_payer` (not signer,
+ * writable).
+ */
+#[derive(Accounts)]
+pub struct ReclaimDelegations<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+
+    /// CHECK: Delegate of the delegation records
+    delegate: AccountInfo<'info>,
+
+    /// Settings the delegation records are counted in
+    /// CHECK: Checked against each delegation record during execution
+    #[account(mut)]
+    settings: UncheckedAccount<'info>,
+}
+
+pub fn reclaim_delegations<'info>(
+    ctx: Context<'_, '_, '_, 'info, ReclaimDelegations<'info>>,
+) -> Result<u32> {
+    require_eq!(
+        ctx.remaining_accounts.len() % 3,
+        0,
+        DelegationError::MissingDelegatorAccounts
+    );
+
+    let mut reclaimed: u32 = 0;
+
+    for to_reclaim in ctx.remaining_accounts.chunks_exact(3) {
+        let mut to_reclaim_iter = to_reclaim.iter();
+        let voter_weight_record_info = to_reclaim_iter.next().unwrap();
+        let delegation_info = to_reclaim_iter.next().unwrap();
+        let rent_payer_info = to_reclaim_iter.next().unwrap();
+
+        match assert_can_reclaim_delegation(
+            voter_weight_record_info,
+            delegation_info,
+            rent_payer_info,
+            ctx.accounts.delegate.key,
+        )
+        .and_then(|delegation| delegation.close_in(&ctx.accounts.settings))
+        {
+            Ok(()) => {
+                close_account(delegation_info, rent_payer_info)?;
+                reclaimed += 1;
+            }
+            Err(err) => msg!("Skipped {}: {}", delegation_info.key, err),
+        }
+    }
+
+    msg!(
+        "Reclaimed {} of {} delegation records",
+        reclaimed,
+        ctx.remaining_accounts.len() / 3
+    );
+
+    Ok(reclaimed)
+}
+
+fn assert_can_reclaim_delegation(
+    voter_weight_record_info: &AccountInfo,
+    delegation_info: &AccountInfo,
+    rent_payer_info: &AccountInfo,
+    delegate: &Pubkey,
+) -> Result<Delegation> {
+    let delegation = Account::<Delegation>::try_from(delegation_info)?;
+
+    require!(
+        voter_weight_record_info.data_is_empty(),
+        DelegationError::CannotReclaimDelegationRecordYet
+    );
+    require_keys_eq!(
+        delegation.voter_weight_record,
+        voter_weight_record_info.key(),
+        DelegationError::IncorrectDelegationAddress
+    );
+    require_keys_eq!(
+        delegation.delegate,
+        *delegate,
+        DelegationError::NonMatchingDelegationRecordProvided,
+    );
+    require_keys_eq!(
+        delegation.rent_payer,
+        rent_payer_info.key(),
+        DelegationError::IncorrectRentPayer
+    );
+
+    Ok(delegation.into_inner())
+}
@@ -1,6 +1,9 @@
 use anchor_lang::prelude::*;
 
-use crate::{error::DelegationError, state::voter_weight_record::VoterWeightRecord};
+use crate::{
+    error::DelegationError,
+    state::{rent_payer::RentPayer, voter_weight_record::VoterWeightRecord},
+};
 
 #[derive(Accounts)]
 pub struct ReclaimVoterWeightRecord<'info> {
@@ -17,6 +20,18 @@ pub struct ReclaimVoterWeightRecord<'info> {
     )]
     voter_weight_record: Account<'info, VoterWeightRecord>,
 
+    /// CHECK: Closed with the record if it exists, see RentPayer
+    #[account(
+        mut,
+        seeds = [b"rent-payer".as_ref(), voter_weight_record.key().as_ref()],
+        bump
+    )]
+    rent_payer: UncheckedAccount<'info>,
+
+    /// CHECK: Account which paid for the record, checked against rent_payer if it exists.
+    #[account(mut)]
+    record_payer: UncheckedAccount<'info>,
+
     /// CHECK: Will be deserialized depending on voter_weight_record type.
     #[account(owner = governance_program_id.key())]
     target: AccountInfo<'info>,
@@ -34,9 +49,11 @@ pub fn reclaim_voter_weight_record(ctx: Context<ReclaimVoterWeightRecord>) -> Re
         DelegationError::VoterWeightRecordWrongOwner
     );
 
-    ctx.accounts
-        .voter_weight_record
-        .assert_can_reclaim(&ctx.accounts.target, ctx.accounts.governance_program_id.key)?;
+    ctx.accounts.voter_weight_record.assert_can_reclaim_target(
+        &ctx.accounts.voter_weight_record.key(),
+        &ctx.accounts.target,
+        ctx.accounts.governance_program_id.key,
+    )?;
 
-    Ok(())
+    RentPayer::try_close(&ctx.accounts.rent_payer, &ctx.accounts.record_payer)
 }
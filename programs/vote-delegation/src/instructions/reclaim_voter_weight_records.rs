@@ -0,0 +1,104 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::DelegationError,
+    state::{rent_payer::RentPayer, voter_weight_record::VoterWeightRecord},
+    tools::close_account,
+};
+
+/**
+ * Reclaims lamports from several voter weight records at once
+ *
+ * This instruction behaves like `reclaim_voter_weight_record` for every record provided.
+ * Records which cannot be reclaimed yet are skipped and the reason logged, so one
+ * unfinished target does not prevent the others from being reclaimed.
+ *
+ * For each record to reclaim, the following accounts must be provided as additional
+ * accounts:
+ *  - The VoterWeightRecord owned by the caller (not signer, writable).
+ *  - The target of the VoterWeightRecord (not signer, not writable).
+ *  - The RentPayer PDA of the VoterWeightRecord, which is closed with it if it exists (not
+ * signer, writable).
+ *  - The account which paid for the VoterWeightRecord (not signer, writable).
+ */
+#[derive(Accounts)]
+pub struct ReclaimVoterWeightRecords<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+
+    #[account(mut)]
+    caller: Signer<'info>,
+
+    /// The program id of the spl-governance program the realm belongs to
+    /// CHECK: Can be any instance of spl-governance and it's not known at the compilation time
+    #[account(executable)]
+    governance_program_id: UncheckedAccount<'info>,
+}
+
+pub fn reclaim_voter_weight_records<'info>(
+    ctx: Context<'_, '_, '_, 'info, ReclaimVoterWeightRecords<'info>>,
+) -> Result<u32> {
+    require_eq!(
+        ctx.remaining_accounts.len() % 4,
+        0,
+        DelegationError::MissingDelegatorAccounts
+    );
+
+    let mut reclaimed: u32 = 0;
+
+    for to_reclaim in ctx.remaining_accounts.chunks_exact(4) {
+        let mut to_reclaim_iter = to_reclaim.iter();
+        let voter_weight_record_info = to_reclaim_iter.next().unwrap();
+        let target_info = to_reclaim_iter.next().unwrap();
+        let rent_payer_info = to_reclaim_iter.next().unwrap();
+        let record_payer_info = to_reclaim_iter.next().unwrap();
+
+        require_keys_eq!(
+            rent_payer_info.key(),
+            RentPayer::get_pda_address(voter_weight_record_info.key),
+            ErrorCode::ConstraintSeeds
+        );
+
+        match assert_can_reclaim_record(
+            voter_weight_record_info,
+            target_info,
+            &ctx.accounts.caller.key(),
+            ctx.accounts.governance_program_id.key,
+        ) {
+            Ok(()) => {
+                close_account(voter_weight_record_info, &ctx.accounts.caller)?;
+                RentPayer::try_close(rent_payer_info, record_payer_info)?;
+                reclaimed += 1;
+            }
+            Err(err) => msg!("Skipped {}: {}", voter_weight_record_info.key, err),
+        }
+    }
+
+    msg!(
+        "Reclaimed {} of {} voter weight records",
+        reclaimed,
+        ctx.remaining_accounts.len() / 4
+    );
+
+    Ok(reclaimed)
+}
+
+fn assert_can_reclaim_record(
+    voter_weight_record_info: &AccountInfo,
+    target_info: &AccountInfo,
+    caller: &Pubkey,
+    governance_program_id: &Pubkey,
+) -> Result<()> {
+    let voter_weight_record = Account::<VoterWeightRecord>::try_from(voter_weight_record_info)?;
+
+    require_keys_eq!(
+        voter_weight_record.governing_token_owner,
+        *caller,
+        DelegationError::VoterWeightRecordWrongOwner
+    );
+    voter_weight_record.assert_can_reclaim_target(
+        voter_weight_record_info.key,
+        target_info,
+        governance_program_id,
+    )
+}
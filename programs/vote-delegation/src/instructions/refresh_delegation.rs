@@ -0,0 +1,258 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::DelegationError,
+    state::{delegation::Delegation, settings::Settings, voter_weight_record::VoterWeightRecord},
+    tools::{dispose_account, is_token_mint_owner, RelinquishAccounts},
+};
+
+/**
+ * Refreshes delegated vote weight
+ *
+ * Delegated voter weight is recorded when it is aggregated. This instruction can be called
+ * by anyone once a delegator's voter weight source has changed since then, to bring the
+ * delegation record and the delegate's VoterWeightRecord in line with the current source
+ * weight.
+ *
+ * Voter weight can only increase while the delegate has not voted. If voter weight
+ * decreases after the delegate has voted, the difference is relinquished from the vote.
+ */
+#[derive(Accounts)]
+pub struct RefreshDelegation<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+
+    /// This account is created, used, and freed within this instruction.
+    #[account(
+        init,
+        seeds = [
+            b"revocation".as_ref(),
+            realm_info.key().as_ref(),
+            realm_governing_token_mint.key().as_ref(),
+            governing_token_owner.key().as_ref(),
+            delegated_voter_weight_record.weight_action_target.unwrap().key().as_ref(),
+            &borsh::to_vec(&delegated_voter_weight_record.weight_action).unwrap()
+        ],
+        bump,
+        payer = payer,
+        space = 8 + std::mem::size_of::<VoterWeightRecord>(),
+        owner = crate::ID
+    )]
+    revoke_weight_record: Account<'info, VoterWeightRecord>,
+
+    /// User the voter weight is delegated to.
+    /// CHECK: Delegate
+    #[account(mut)]
+    delegate: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"voter-weight-record-delegation".as_ref(),
+            realm_info.key().as_ref(),
+            realm_governing_token_mint.key().as_ref(),
+            governing_token_owner.key().as_ref(),
+            delegated_voter_weight_record.weight_action_target.unwrap().key().as_ref(),
+            &borsh::to_vec(&delegated_voter_weight_record.weight_action).unwrap()
+        ],
+        bump
+    )]
+    delegation_record: Account<'info, Delegation>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"voter-weight-record".as_ref(),
+            realm_info.key().as_ref(),
+            realm_governing_token_mint.key().as_ref(),
+            delegate.key().as_ref(),
+            delegated_voter_weight_record.weight_action_target.unwrap().key().as_ref(),
+            &borsh::to_vec(&delegated_voter_weight_record.weight_action).unwrap()
+        ],
+        bump,
+        owner = crate::ID
+    )]
+    delegated_voter_weight_record: Account<'info, VoterWeightRecord>,
+
+    #[account(
+        seeds = [
+            b"settings".as_ref(),
+            realm_info.key().as_ref(),
+            realm_governing_token_mint.key().as_ref(),
+        ],
+        bump
+    )]
+    settings: Account<'info, Settings>,
+
+    /// VoterWeightRecord of the delegator produced by this program's voter weight source.
+    /// CHECK: Owner checked during execution
+    voter_weight_source_record: UncheckedAccount<'info>,
+
+    /// The program id of the spl-governance program the realm belongs to
+    /// CHECK: Can be any instance of spl-governance and it's not known at the compilation time
+    #[account(executable)]
+    governance_program_id: UncheckedAccount<'info>,
+
+    /// CHECK: Ownership checked during execution
+    #[account(mut)]
+    vote_record_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(owner = governance_program_id.key())]
+    realm_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(owner = governance_program_id.key())]
+    realm_config_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(owner = governance_program_id.key())]
+    governance_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(mut, owner = governance_program_id.key())]
+    proposal_info: UncheckedAccount<'info>,
+
+    /// Token Owner Record for Delegate.
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(mut, owner = governance_program_id.key())]
+    delegate_token_owner_record_info: UncheckedAccount<'info>,
+
+    /// Either the realm community mint or the council mint.
+    /// CHECK: Owned by a token program, and checked against the realm during execution
+    #[account(constraint = is_token_mint_owner(realm_governing_token_mint.owner) @ DelegationError::InvalidMintOwner)]
+    realm_governing_token_mint: UncheckedAccount<'info>,
+
+    /// User whose voter weight is being refreshed.
+    /// CHECK: Only used to derive the delegation record address
+    governing_token_owner: UncheckedAccount<'info>,
+
+    system_program: Program<'info, System>,
+}
+
+pub fn refresh_delegation(ctx: Context<RefreshDelegation>) -> Result<()> {
+    require_keys_eq!(
+        ctx.accounts.delegation_record.delegate,
+        ctx.accounts.delegate.key(),
+        DelegationError::NonMatchingDelegationRecordProvided
+    );
+    require_keys_eq!(
+        ctx.accounts.delegation_record.voter_weight_record,
+        ctx.accounts.delegated_voter_weight_record.key(),
+        DelegationError::NonMatchingDelegationRecordProvided
+    );
+
+    require_keys_eq!(
+        *ctx.accounts.voter_weight_source_record.owner,
+        ctx.accounts.settings.voter_weight_source,
+        DelegationError::InvalidVoterWeightRecordSource
+    );
+    let mut data: &[u8] = &ctx.accounts.voter_weight_source_record.try_borrow_data()?;
+    let source = VoterWeightRecord::try_deserialize(&mut data)?;
+    require_keys_eq!(
+        source.governing_token_owner,
+        ctx.accounts.governing_token_owner.key(),
+        DelegationError::InvalidVoterWeightRecordSource
+    );
+    require_keys_eq!(
+        source.realm,
+        ctx.accounts.realm_info.key(),
+        DelegationError::InvalidRealm
+    );
+    require_keys_eq!(
+        source.governing_token_mint,
+        ctx.accounts.realm_governing_token_mint.key(),
+        DelegationError::InvalidGoverningTokenMint
+    );
+    let current_slot = Clock::get()?.slot;
+    require!(
+        source
+            .voter_weight_expiry
+            .map_or(true, |expiry| expiry >= current_slot),
+        DelegationError::VoterWeightSourceExpired
+    );
+
+    let vote_cast = ctx.accounts.relinquish_accounts().load_vote()?;
+    let previous_weight = ctx.accounts.delegation_record.voter_weight;
+
+    if source.voter_weight > previous_weight {
+        // A cast vote can only ever be relinquished from, never added to.
+        require!(!vote_cast, DelegationError::CannotIncreaseCastVote);
+
+        ctx.accounts.delegated_voter_weight_record.voter_weight = ctx
+            .accounts
+            .delegated_voter_weight_record
+            .voter_weight
+            .checked_add(source.voter_weight - previous_weight)
+            .unwrap();
+    } else if source.voter_weight < previous_weight {
+        let decrease = previous_weight - source.voter_weight;
+
+        ctx.accounts.delegated_voter_weight_record.voter_weight = ctx
+            .accounts
+            .delegated_voter_weight_record
+            .voter_weight
+            .checked_sub(decrease)
+            .unwrap();
+
+        // See revoke_vote for why the delegate's record must be expired.
+        if ctx
+            .accounts
+            .delegated_voter_weight_record
+            .voter_weight_expiry
+            >= Some(current_slot)
+        {
+            return Err(DelegationError::VoterWeightRecordMustBeExpired.into());
+        }
+
+        if vote_cast {
+            // Nobody signs for the delegation here, so its record authorises the relinquish.
+            let realm = ctx.accounts.realm_info.key();
+            let mint = ctx.accounts.realm_governing_token_mint.key();
+            let governing_token_owner = ctx.accounts.governing_token_owner.key();
+            let target = ctx
+                .accounts
+                .delegated_voter_weight_record
+                .weight_action_target
+                .unwrap();
+            let action =
+                borsh::to_vec(&ctx.accounts.delegated_voter_weight_record.weight_action).unwrap();
+            let bump = [*ctx.bumps.get("delegation_record").unwrap()];
+            let seeds =
+                Delegation::get_pda_seeds(&realm, &mint, &governing_token_owner, &target, &action);
+            let signer_seeds = [&seeds[..], &[&bump[..]]].concat();
+
+            ctx.accounts
+                .relinquish_accounts()
+                .relinquish_weight_signed(&governing_token_owner, decrease, &[&signer_seeds[..]])?;
+        }
+    }
+
+    ctx.accounts.delegation_record.voter_weight = source.voter_weight;
+
+    // This account is disposed here to prevent double-relinquishment.
+    dispose_account(
+        &ctx.accounts.revoke_weight_record.to_account_info(),
+        &ctx.accounts.payer,
+    );
+
+    Ok(())
+}
+
+impl<'info> RefreshDelegation<'info> {
+    fn relinquish_accounts(&self) -> RelinquishAccounts<'_, 'info> {
+        RelinquishAccounts {
+            governance_program_id: &self.governance_program_id,
+            realm: &self.realm_info,
+            realm_config: &self.realm_config_info,
+            governance: &self.governance_info,
+            proposal: &self.proposal_info,
+            delegate_token_owner_record: &self.delegate_token_owner_record_info,
+            vote_record: &self.vote_record_info,
+            governing_token_mint: self.realm_governing_token_mint.as_ref(),
+            governance_authority: self.delegation_record.as_ref(),
+            beneficiary: &self.delegate,
+            revoke_weight_record: self.revoke_weight_record.as_ref(),
+        }
+    }
+}
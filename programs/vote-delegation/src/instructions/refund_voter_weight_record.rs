@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::DelegationError,
+    state::{rent_payer::RentPayer, voter_weight_record::VoterWeightRecord},
+};
+
+/**
+ * Refunds the rent of a VoterWeightRecord to the account which paid for it
+ *
+ * Unlike `reclaim_voter_weight_record`, this instruction can be called by anyone once the
+ * target of the record is finished.
+ */
+#[derive(Accounts)]
+pub struct RefundVoterWeightRecord<'info> {
+    /// CHECK: Account to refund.
+    #[account(mut, address = rent_payer.payer @ DelegationError::IncorrectRentPayer)]
+    payer: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"rent-payer".as_ref(), voter_weight_record.key().as_ref()],
+        bump,
+        close = payer
+    )]
+    rent_payer: Account<'info, RentPayer>,
+
+    #[account(mut, owner = crate::ID, close = payer)]
+    voter_weight_record: Account<'info, VoterWeightRecord>,
+
+    /// CHECK: Will be deserialized depending on voter_weight_record type.
+    target: UncheckedAccount<'info>,
+
+    /// The program id of the spl-governance program the realm belongs to
+    /// CHECK: Can be any instance of spl-governance and it's not known at the compilation time
+    #[account(executable)]
+    governance_program_id: UncheckedAccount<'info>,
+}
+
+pub fn refund_voter_weight_record(ctx: Context<RefundVoterWeightRecord>) -> Result<()> {
+    ctx.accounts.voter_weight_record.assert_can_reclaim_target(
+        &ctx.accounts.voter_weight_record.key(),
+        &ctx.accounts.target,
+        ctx.accounts.governance_program_id.key,
+    )
+}
@@ -0,0 +1,201 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::DelegationError,
+    state::{delegation::Delegation, voter_weight_record::VoterWeightRecord},
+    tools::{dispose_account, is_token_mint_owner, RelinquishAccounts},
+};
+
+/**
+ * Releases voter weight delegated to the caller
+ *
+ * This instruction is the delegate's counterpart to `revoke_vote`. It can be called by a
+ * delegate to give back the voter weight delegated to them by a single user for a single
+ * target. If that voter weight has been used to cast a vote, the vote will be modified to
+ * reflect this. The delegator is then free to use their voter weight directly.
+ */
+#[derive(Accounts)]
+pub struct ReleaseDelegation<'info> {
+    /// CHECK: Payer
+    #[account(mut)]
+    payer: UncheckedAccount<'info>,
+
+    /// This account is created, used, and freed within this instruction.
+    #[account(
+        init,
+        seeds = [
+            b"revocation".as_ref(),
+            realm_info.key().as_ref(),
+            realm_governing_token_mint.key().as_ref(),
+            governing_token_owner.key().as_ref(),
+            delegated_voter_weight_record.weight_action_target.unwrap().key().as_ref(),
+            &borsh::to_vec(&delegated_voter_weight_record.weight_action).unwrap()
+        ],
+        bump,
+        payer = payer,
+        space = 8 + std::mem::size_of::<VoterWeightRecord>(),
+        owner = crate::ID
+    )]
+    revoke_weight_record: Account<'info, VoterWeightRecord>,
+
+    /// User releasing the voter weight delegated to them.
+    #[account(mut)]
+    delegate: Signer<'info>,
+
+    #[account(
+        mut,
+        close = rent_payer,
+        seeds = [
+            b"voter-weight-record-delegation".as_ref(),
+            realm_info.key().as_ref(),
+            realm_governing_token_mint.key().as_ref(),
+            governing_token_owner.key().as_ref(),
+            delegated_voter_weight_record.weight_action_target.unwrap().key().as_ref(),
+            &borsh::to_vec(&delegated_voter_weight_record.weight_action).unwrap()
+        ],
+        bump
+    )]
+    delegation_record: Account<'info, Delegation>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"voter-weight-record".as_ref(),
+            realm_info.key().as_ref(),
+            realm_governing_token_mint.key().as_ref(),
+            delegate.key().as_ref(),
+            delegated_voter_weight_record.weight_action_target.unwrap().key().as_ref(),
+            &borsh::to_vec(&delegated_voter_weight_record.weight_action).unwrap()
+        ],
+        bump,
+        owner = crate::ID
+    )]
+    delegated_voter_weight_record: Account<'info, VoterWeightRecord>,
+
+    /// The program id of the spl-governance program the realm belongs to
+    /// CHECK: Can be any instance of spl-governance and it's not known at the compilation time
+    #[account(executable)]
+    governance_program_id: UncheckedAccount<'info>,
+
+    /// CHECK: Ownership checked during execution
+    #[account(mut)]
+    vote_record_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(owner = governance_program_id.key())]
+    realm_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(owner = governance_program_id.key())]
+    realm_config_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(owner = governance_program_id.key())]
+    governance_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(mut, owner = governance_program_id.key())]
+    proposal_info: UncheckedAccount<'info>,
+
+    /// Token Owner Record for Delegate.
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(mut, owner = governance_program_id.key())]
+    delegate_token_owner_record_info: UncheckedAccount<'info>,
+
+    /// Either the realm community mint or the council mint.
+    /// CHECK: Owned by a token program, and checked against the realm during execution
+    #[account(constraint = is_token_mint_owner(realm_governing_token_mint.owner) @ DelegationError::InvalidMintOwner)]
+    realm_governing_token_mint: UncheckedAccount<'info>,
+
+    /// User whose voter weight is being released.
+    /// CHECK: Only used to derive the delegation record address
+    governing_token_owner: UncheckedAccount<'info>,
+
+    system_program: Program<'info, System>,
+
+    /// Settings the delegation record is counted in
+    /// CHECK: Checked against the delegation record during execution
+    #[account(mut)]
+    settings: UncheckedAccount<'info>,
+
+    /// CHECK: Account which paid for the delegation record, and gets its rent back.
+    #[account(
+        mut,
+        address = delegation_record.rent_payer @ DelegationError::IncorrectRentPayer
+    )]
+    rent_payer: UncheckedAccount<'info>,
+}
+
+pub fn release_delegation(ctx: Context<ReleaseDelegation>) -> Result<()> {
+    let delegation_record_data = &ctx.accounts.delegation_record;
+
+    require_keys_eq!(
+        delegation_record_data.delegate,
+        ctx.accounts.delegate.key(),
+        DelegationError::NonMatchingDelegationRecordProvided
+    );
+    require_keys_eq!(
+        delegation_record_data.voter_weight_record,
+        ctx.accounts.delegated_voter_weight_record.key(),
+        DelegationError::NonMatchingDelegationRecordProvided
+    );
+
+    let vote_cast = ctx.accounts.relinquish_accounts().load_vote()?;
+
+    ctx.accounts.delegated_voter_weight_record.voter_weight = ctx
+        .accounts
+        .delegated_voter_weight_record
+        .voter_weight
+        .checked_sub(delegation_record_data.voter_weight)
+        .unwrap();
+
+    // See revoke_vote for why the delegate's record must be expired.
+    if ctx
+        .accounts
+        .delegated_voter_weight_record
+        .voter_weight_expiry
+        >= Some(Clock::get()?.slot)
+    {
+        return Err(DelegationError::VoterWeightRecordMustBeExpired.into());
+    }
+
+    if vote_cast {
+        ctx.accounts.relinquish_accounts().relinquish_weight(
+            ctx.accounts.governing_token_owner.key,
+            delegation_record_data.voter_weight,
+        )?;
+    }
+
+    // This account is disposed here to prevent double-relinquishment.
+    dispose_account(
+        &ctx.accounts.revoke_weight_record.to_account_info(),
+        &ctx.accounts.payer,
+    );
+
+    delegation_record_data.close_in(&ctx.accounts.settings)?;
+
+    dispose_account(
+        &ctx.accounts.delegation_record.to_account_info(),
+        &ctx.accounts.rent_payer,
+    );
+
+    Ok(())
+}
+
+impl<'info> ReleaseDelegation<'info> {
+    fn relinquish_accounts(&self) -> RelinquishAccounts<'_, 'info> {
+        RelinquishAccounts {
+            governance_program_id: &self.governance_program_id,
+            realm: &self.realm_info,
+            realm_config: &self.realm_config_info,
+            governance: &self.governance_info,
+            proposal: &self.proposal_info,
+            delegate_token_owner_record: &self.delegate_token_owner_record_info,
+            vote_record: &self.vote_record_info,
+            governing_token_mint: self.realm_governing_token_mint.as_ref(),
+            governance_authority: &self.delegate,
+            beneficiary: &self.delegate,
+            revoke_weight_record: self.revoke_weight_record.as_ref(),
+        }
+    }
+}
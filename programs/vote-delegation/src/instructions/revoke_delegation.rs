@@ -0,0 +1,136 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::DelegationError,
+    state::{
+        delegation::Delegation,
+        voter_weight_record::{VoterWeightAction, VoterWeightRecord},
+    },
+};
+
+/**
+ * Revokes delegated voter weight without relinquishing a vote
+ *
+ * This instruction is a lightweight alternative to `revoke_vote` for VoterWeightRecords
+ * aggregated for any action other than casting a vote, such as creating a proposal. As
+ * there is no vote to relinquish, it only subtracts the delegated voter weight from the
+ * delegate's record and closes the delegation record, refunding whoever paid for it, so no
+ * spl-governance accounts are needed.
+ *
+ * Voter weight aggregated to cast a vote can also be revoked this way once voting on the
+ * proposal has ended, as relinquishing the vote would no longer change the outcome. The
+ * following accounts must then be provided as additional accounts:
+ *  - The proposal (not signer, not writable).
+ *  - The spl-governance program the proposal belongs to (not signer, not writable).
+ */
+#[derive(Accounts)]
+pub struct RevokeDelegation<'info> {
+    /// User the voter weight was delegated to.
+    /// CHECK: Delegate
+    delegate: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        close = rent_payer,
+        seeds = [
+            b"voter-weight-record-delegation".as_ref(),
+            delegated_voter_weight_record.realm.as_ref(),
+            delegated_voter_weight_record.governing_token_mint.as_ref(),
+            governing_token_owner.key().as_ref(),
+            delegated_voter_weight_record.weight_action_target.unwrap().as_ref(),
+            &borsh::to_vec(&delegated_voter_weight_record.weight_action).unwrap()
+        ],
+        bump
+    )]
+    delegation_record: Account<'info, Delegation>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"voter-weight-record".as_ref(),
+            delegated_voter_weight_record.realm.as_ref(),
+            delegated_voter_weight_record.governing_token_mint.as_ref(),
+            delegate.key().as_ref(),
+            delegated_voter_weight_record.weight_action_target.unwrap().as_ref(),
+            &borsh::to_vec(&delegated_voter_weight_record.weight_action).unwrap()
+        ],
+        bump,
+        owner = crate::ID
+    )]
+    delegated_voter_weight_record: Account<'info, VoterWeightRecord>,
+
+    /// User who wants their voter weight revoked.
+    governing_token_owner: Signer<'info>,
+
+    /// Settings the delegation record is counted in
+    /// CHECK: Checked against the delegation record during execution
+    #[account(mut)]
+    settings: UncheckedAccount<'info>,
+
+    /// CHECK: Account which paid for the delegation record, and gets its rent back.
+    #[account(
+        mut,
+        address = delegation_record.rent_payer @ DelegationError::IncorrectRentPayer
+    )]
+    rent_payer: UncheckedAccount<'info>,
+}
+
+pub fn revoke_delegation(ctx: Context<RevokeDelegation>) -> Result<()> {
+    let delegation_record_data = &ctx.accounts.delegation_record;
+
+    if ctx.accounts.delegated_voter_weight_record.weight_action == Some(VoterWeightAction::CastVote)
+    {
+        require_eq!(
+            ctx.remaining_accounts.len(),
+            2,
+            DelegationError::CannotRevokeVoteWithoutRelinquishing
+        );
+        let proposal_info = &ctx.remaining_accounts[0];
+        let governance_program_info = &ctx.remaining_accounts[1];
+        require!(
+            governance_program_info.executable,
+            ErrorCode::ConstraintExecutable
+        );
+
+        // A record which could be reclaimed is one whose proposal is no longer voting.
+        ctx.accounts
+            .delegated_voter_weight_record
+            .assert_can_reclaim_target(
+                &ctx.accounts.delegated_voter_weight_record.key(),
+                proposal_info,
+                governance_program_info.key,
+            )?;
+    }
+
+    require_keys_eq!(
+        delegation_record_data.delegate,
+        ctx.accounts.delegate.key(),
+        DelegationError::NonMatchingDelegationRecordProvided
+    );
+    require_keys_eq!(
+        delegation_record_data.voter_weight_record,
+        ctx.accounts.delegated_voter_weight_record.key(),
+        DelegationError::NonMatchingDelegationRecordProvided
+    );
+
+    ctx.accounts.delegated_voter_weight_record.voter_weight = ctx
+        .accounts
+        .delegated_voter_weight_record
+        .voter_weight
+        .checked_sub(delegation_record_data.voter_weight)
+        .unwrap();
+
+    // See revoke_vote for why the delegate's record must be expired.
+    if ctx
+        .accounts
+        .delegated_voter_weight_record
+        .voter_weight_expiry
+        >= Some(Clock::get()?.slot)
+    {
+        return Err(DelegationError::VoterWeightRecordMustBeExpired.into());
+    }
+
+    delegation_record_data.close_in(&ctx.accounts.settings)?;
+
+    Ok(())
+}
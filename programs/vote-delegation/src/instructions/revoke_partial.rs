@@ -0,0 +1,188 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::DelegationError,
+    state::{delegation::Delegation, voter_weight_record::VoterWeightRecord},
+    tools::{dispose_account, is_token_mint_owner, RelinquishAccounts},
+};
+
+/**
+ * Revokes part of delegated vote weight
+ *
+ * This instruction behaves like `revoke_vote`, but only revokes the given amount of the
+ * caller's delegated voter weight. The rest stays delegated, so the delegation record is
+ * updated instead of being closed. If the voter weight has been used to cast a vote,
+ * exactly the revoked amount is relinquished from it.
+ */
+#[derive(Accounts)]
+pub struct RevokePartial<'info> {
+    /// CHECK: Payer
+    #[account(mut)]
+    payer: UncheckedAccount<'info>,
+
+    /// This account is created, used, and freed within this instruction.
+    #[account(
+        init,
+        seeds = [
+            b"revocation".as_ref(),
+            realm_info.key().as_ref(),
+            realm_governing_token_mint.key().as_ref(),
+            governing_token_owner.key().as_ref(),
+            delegated_voter_weight_record.weight_action_target.unwrap().key().as_ref(),
+            &borsh::to_vec(&delegated_voter_weight_record.weight_action).unwrap()
+        ],
+        bump,
+        payer = payer,
+        space = 8 + std::mem::size_of::<VoterWeightRecord>(),
+        owner = crate::ID
+    )]
+    revoke_weight_record: Account<'info, VoterWeightRecord>,
+
+    /// User who cast the vote which is now being revoked.
+    /// CHECK: Delegate
+    #[account(mut)]
+    delegate: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"voter-weight-record-delegation".as_ref(),
+            realm_info.key().as_ref(),
+            realm_governing_token_mint.key().as_ref(),
+            governing_token_owner.key().as_ref(),
+            delegated_voter_weight_record.weight_action_target.unwrap().key().as_ref(),
+            &borsh::to_vec(&delegated_voter_weight_record.weight_action).unwrap()
+        ],
+        bump
+    )]
+    delegation_record: Account<'info, Delegation>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"voter-weight-record".as_ref(),
+            realm_info.key().as_ref(),
+            realm_governing_token_mint.key().as_ref(),
+            delegate.key().as_ref(),
+            delegated_voter_weight_record.weight_action_target.unwrap().key().as_ref(),
+            &borsh::to_vec(&delegated_voter_weight_record.weight_action).unwrap()
+        ],
+        bump,
+        owner = crate::ID
+    )]
+    delegated_voter_weight_record: Account<'info, VoterWeightRecord>,
+
+    /// The program id of the spl-governance program the realm belongs to
+    /// CHECK: Can be any instance of spl-governance and it's not known at the compilation time
+    #[account(executable)]
+    governance_program_id: UncheckedAccount<'info>,
+
+    /// CHECK: Ownership checked during execution
+    #[account(mut)]
+    vote_record_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(owner = governance_program_id.key())]
+    realm_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(owner = governance_program_id.key())]
+    realm_config_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(owner = governance_program_id.key())]
+    governance_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(mut, owner = governance_program_id.key())]
+    proposal_info: UncheckedAccount<'info>,
+
+    /// Token Owner Record for Delegate.
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(mut, owner = governance_program_id.key())]
+    delegate_token_owner_record_info: UncheckedAccount<'info>,
+
+    /// Either the realm community mint or the council mint.
+    /// CHECK: Owned by a token program, and checked against the realm during execution
+    #[account(constraint = is_token_mint_owner(realm_governing_token_mint.owner) @ DelegationError::InvalidMintOwner)]
+    realm_governing_token_mint: UncheckedAccount<'info>,
+
+    /// User who wants their voter weight revoked.
+    governing_token_owner: Signer<'info>,
+
+    system_program: Program<'info, System>,
+}
+
+pub fn revoke_partial(ctx: Context<RevokePartial>, amount: u64) -> Result<()> {
+    let delegation_record_data = &ctx.accounts.delegation_record;
+
+    // Revoking all of the voter weight is done with revoke_vote, which closes the record.
+    require!(
+        amount > 0 && amount < delegation_record_data.voter_weight,
+        DelegationError::InvalidPartialRevocationAmount
+    );
+
+    require_keys_eq!(
+        delegation_record_data.delegate,
+        ctx.accounts.delegate.key(),
+        DelegationError::NonMatchingDelegationRecordProvided
+    );
+
+    let vote_cast = ctx.accounts.relinquish_accounts().load_vote()?;
+
+    ctx.accounts.delegated_voter_weight_record.voter_weight = ctx
+        .accounts
+        .delegated_voter_weight_record
+        .voter_weight
+        .checked_sub(amount)
+        .unwrap();
+
+    // See revoke_vote for why the delegate's record must be expired.
+    if ctx
+        .accounts
+        .delegated_voter_weight_record
+        .voter_weight_expiry
+        >= Some(Clock::get()?.slot)
+    {
+        return Err(DelegationError::VoterWeightRecordMustBeExpired.into());
+    }
+
+    if vote_cast {
+        ctx.accounts
+            .relinquish_accounts()
+            .relinquish_weight(ctx.accounts.governing_token_owner.key, amount)?;
+    }
+
+    ctx.accounts.delegation_record.voter_weight = ctx
+        .accounts
+        .delegation_record
+        .voter_weight
+        .checked_sub(amount)
+        .unwrap();
+
+    // This account is disposed here to prevent double-relinquishment.
+    dispose_account(
+        &ctx.accounts.revoke_weight_record.to_account_info(),
+        &ctx.accounts.payer,
+    );
+
+    Ok(())
+}
+
+impl<'info> RevokePartial<'info> {
+    fn relinquish_accounts(&self) -> RelinquishAccounts<'_, 'info> {
+        RelinquishAccounts {
+            governance_program_id: &self.governance_program_id,
+            realm: &self.realm_info,
+            realm_config: &self.realm_config_info,
+            governance: &self.governance_info,
+            proposal: &self.proposal_info,
+            delegate_token_owner_record: &self.delegate_token_owner_record_info,
+            vote_record: &self.vote_record_info,
+            governing_token_mint: self.realm_governing_token_mint.as_ref(),
+            governance_authority: &self.governing_token_owner,
+            beneficiary: &self.delegate,
+            revoke_weight_record: self.revoke_weight_record.as_ref(),
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::DelegationError, state::session::Session};
+
+/**
+ * Revokes a session key
+ *
+ * The session is closed, so the session key can no longer sign for the delegate in any
+ * later instruction. Its rent is returned to the delegate.
+ */
+#[derive(Accounts)]
+pub struct RevokeSession<'info> {
+    #[account(mut)]
+    delegate: Signer<'info>,
+
+    #[account(
+        mut,
+        close = delegate,
+        has_one = delegate @ DelegationError::InvalidSession
+    )]
+    session: Account<'info, Session>,
+}
+
+pub fn revoke_session(_ctx: Context<RevokeSession>) -> Result<()> {
+    Ok(())
+}
@@ -0,0 +1,243 @@
+use anchor_lang::prelude::*;
+use spl_governance::state::token_owner_record::get_token_owner_record_data_for_realm_and_governing_mint;
+
+use crate::{
+    error::DelegationError,
+    state::{delegation::Delegation, voter_weight_record::VoterWeightRecord},
+    tools::{close_account, dispose_account, is_token_mint_owner, RelinquishAccounts},
+};
+
+/**
+ * Revokes delegated vote weight which is no longer delegated to its delegate
+ *
+ * This instruction can be called by anyone. If a user changes the governance delegate of
+ * their TokenOwnerRecord after their voter weight has been aggregated, the delegation
+ * record still points at the previous delegate. This instruction revokes it exactly as
+ * `revoke_vote` would, leaving the voter weight free to be aggregated by the new delegate,
+ * even within the same transaction.
+ */
+#[derive(Accounts)]
+pub struct RevokeStaleDelegation<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+
+    /// This account is created, used, and freed within this instruction.
+    #[account(
+        init,
+        seeds = [
+            b"revocation".as_ref(),
+            realm_info.key().as_ref(),
+            realm_governing_token_mint.key().as_ref(),
+            governing_token_owner.key().as_ref(),
+            delegated_voter_weight_record.weight_action_target.unwrap().key().as_ref(),
+            &borsh::to_vec(&delegated_voter_weight_record.weight_action).unwrap()
+        ],
+        bump,
+        payer = payer,
+        space = 8 + std::mem::size_of::<VoterWeightRecord>(),
+        owner = crate::ID
+    )]
+    revoke_weight_record: Account<'info, VoterWeightRecord>,
+
+    /// User who cast the vote which is now being revoked.
+    /// CHECK: Delegate
+    #[account(mut)]
+    delegate: UncheckedAccount<'info>,
+
+    /// CHECK: Deserialized during execution so it can be closed manually
+    #[account(
+        mut,
+        seeds = [
+            b"voter-weight-record-delegation".as_ref(),
+            realm_info.key().as_ref(),
+            realm_governing_token_mint.key().as_ref(),
+            governing_token_owner.key().as_ref(),
+            delegated_voter_weight_record.weight_action_target.unwrap().key().as_ref(),
+            &borsh::to_vec(&delegated_voter_weight_record.weight_action).unwrap()
+        ],
+        bump
+    )]
+    delegation_record: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"voter-weight-record".as_ref(),
+            realm_info.key().as_ref(),
+            realm_governing_token_mint.key().as_ref(),
+            delegate.key().as_ref(),
+            delegated_voter_weight_record.weight_action_target.unwrap().key().as_ref(),
+            &borsh::to_vec(&delegated_voter_weight_record.weight_action).unwrap()
+        ],
+        bump,
+        owner = crate::ID
+    )]
+    delegated_voter_weight_record: Account<'info, VoterWeightRecord>,
+
+    /// The program id of the spl-governance program the realm belongs to
+    /// CHECK: Can be any instance of spl-governance and it's not known at the compilation time
+    #[account(executable)]
+    governance_program_id: UncheckedAccount<'info>,
+
+    /// CHECK: Ownership checked during execution
+    #[account(mut)]
+    vote_record_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(owner = governance_program_id.key())]
+    realm_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(owner = governance_program_id.key())]
+    realm_config_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(owner = governance_program_id.key())]
+    governance_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(mut, owner = governance_program_id.key())]
+    proposal_info: UncheckedAccount<'info>,
+
+    /// Token Owner Record for Delegate.
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(mut, owner = governance_program_id.key())]
+    delegate_token_owner_record_info: UncheckedAccount<'info>,
+
+    /// Either the realm community mint or the council mint.
+    /// CHECK: Owned by a token program, and checked against the realm during execution
+    #[account(constraint = is_token_mint_owner(realm_governing_token_mint.owner) @ DelegationError::InvalidMintOwner)]
+    realm_governing_token_mint: UncheckedAccount<'info>,
+
+    /// Token Owner Record of the user whose voter weight is being revoked.
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(owner = governance_program_id.key())]
+    governing_token_owner_record_info: UncheckedAccount<'info>,
+
+    /// User whose voter weight is being revoked.
+    /// CHECK: Checked against governing_token_owner_record_info
+    governing_token_owner: UncheckedAccount<'info>,
+
+    system_program: Program<'info, System>,
+
+    /// Settings the delegation record is counted in
+    /// CHECK: Checked against the delegation record during execution
+    #[account(mut)]
+    settings: UncheckedAccount<'info>,
+
+    /// Account which paid for the delegation record, and gets its rent back.
+    /// CHECK: Checked against the delegation record during execution
+    #[account(mut)]
+    rent_payer: UncheckedAccount<'info>,
+}
+
+pub fn revoke_stale_delegation(ctx: Context<RevokeStaleDelegation>) -> Result<()> {
+    let delegation_record_data = Account::<Delegation>::try_from(&ctx.accounts.delegation_record)?;
+
+    require_keys_eq!(
+        delegation_record_data.delegate,
+        ctx.accounts.delegate.key(),
+        DelegationError::NonMatchingDelegationRecordProvided
+    );
+    require_keys_eq!(
+        delegation_record_data.voter_weight_record,
+        ctx.accounts.delegated_voter_weight_record.key(),
+        DelegationError::NonMatchingDelegationRecordProvided
+    );
+    require_keys_eq!(
+        delegation_record_data.rent_payer,
+        ctx.accounts.rent_payer.key(),
+        DelegationError::IncorrectRentPayer
+    );
+
+    let vote_cast = ctx.accounts.relinquish_accounts().load_vote()?;
+    let token_owner_record_data = get_token_owner_record_data_for_realm_and_governing_mint(
+        ctx.accounts.governance_program_id.key,
+        &ctx.accounts.governing_token_owner_record_info,
+        ctx.accounts.realm_info.key,
+        &ctx.accounts.realm_governing_token_mint.key(),
+    )?;
+
+    require_keys_eq!(
+        token_owner_record_data.governing_token_owner,
+        ctx.accounts.governing_token_owner.key(),
+        DelegationError::InvalidTokenOwnerRecord
+    );
+    require!(
+        delegation_record_data.is_stale(&token_owner_record_data),
+        DelegationError::DelegationStillCurrent
+    );
+
+    ctx.accounts.delegated_voter_weight_record.voter_weight = ctx
+        .accounts
+        .delegated_voter_weight_record
+        .voter_weight
+        .checked_sub(delegation_record_data.voter_weight)
+        .unwrap();
+
+    // See revoke_vote for why the delegate's record must be expired.
+    if ctx
+        .accounts
+        .delegated_voter_weight_record
+        .voter_weight_expiry
+        >= Some(Clock::get()?.slot)
+    {
+        return Err(DelegationError::VoterWeightRecordMustBeExpired.into());
+    }
+
+    if vote_cast {
+        // Nobody signs for the delegation here, so its record authorises the relinquish.
+        let realm = ctx.accounts.realm_info.key();
+        let mint = ctx.accounts.realm_governing_token_mint.key();
+        let governing_token_owner = ctx.accounts.governing_token_owner.key();
+        let target = ctx
+            .accounts
+            .delegated_voter_weight_record
+            .weight_action_target
+            .unwrap();
+        let action =
+            borsh::to_vec(&ctx.accounts.delegated_voter_weight_record.weight_action).unwrap();
+        let bump = [*ctx.bumps.get("delegation_record").unwrap()];
+        let seeds =
+            Delegation::get_pda_seeds(&realm, &mint, &governing_token_owner, &target, &action);
+        let signer_seeds = [&seeds[..], &[&bump[..]]].concat();
+
+        ctx.accounts
+            .relinquish_accounts()
+            .relinquish_weight_signed(
+                &governing_token_owner,
+                delegation_record_data.voter_weight,
+                &[&signer_seeds[..]],
+            )?;
+    }
+
+    // This account is disposed here to prevent double-relinquishment.
+    dispose_account(
+        &ctx.accounts.revoke_weight_record.to_account_info(),
+        &ctx.accounts.payer,
+    );
+
+    delegation_record_data.close_in(&ctx.accounts.settings)?;
+
+    close_account(&ctx.accounts.delegation_record, &ctx.accounts.rent_payer)?;
+
+    Ok(())
+}
+
+impl<'info> RevokeStaleDelegation<'info> {
+    fn relinquish_accounts(&self) -> RelinquishAccounts<'_, 'info> {
+        RelinquishAccounts {
+            governance_program_id: &self.governance_program_id,
+            realm: &self.realm_info,
+            realm_config: &self.realm_config_info,
+            governance: &self.governance_info,
+            proposal: &self.proposal_info,
+            delegate_token_owner_record: &self.delegate_token_owner_record_info,
+            vote_record: &self.vote_record_info,
+            governing_token_mint: self.realm_governing_token_mint.as_ref(),
+            governance_authority: &self.delegation_record,
+            beneficiary: &self.delegate,
+            revoke_weight_record: self.revoke_weight_record.as_ref(),
+        }
+    }
+}
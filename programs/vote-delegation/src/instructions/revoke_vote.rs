@@ -1,23 +1,12 @@
-use anchor_lang::{
-    prelude::*,
-    solana_program::{instruction::Instruction, program::invoke},
-};
-use anchor_spl::token::Mint;
-use spl_governance::state::{
-    governance::get_governance_data_for_realm,
-    proposal::get_proposal_data_for_governance_and_governing_mint,
-    realm::get_realm_data_for_governing_token_mint,
-    token_owner_record::get_token_owner_record_data_for_realm_and_governing_mint,
-    vote_record::get_vote_record_data_for_proposal_and_token_owner_record,
-};
+use anchor_lang::{prelude::*, Discriminator};
 
 use crate::{
     error::DelegationError,
     state::{
-        delegation::Delegation,
-        voter_weight_record::{VoterWeightAction, VoterWeightRecord},
+        delegation::Delegation, delegation_ledger::DelegationLedger, settings::Settings,
+        voter_weight_record::VoterWeightRecord,
     },
-    tools::dispose_account,
+    tools::{close_account, dispose_account, is_token_mint_owner, RelinquishAccounts},
 };
 
 /**
@@ -27,6 +16,18 @@ use crate::{
  * program which includes voter weight delegated by them. When called, it revokes their
  * delegated voter weight. If that voter weight has been used to cast a vote, the vote
  * will be modified to reflect this.
+ *
+ * The revoked voter weight is taken off every choice the vote approved, as each approved
+ * choice of a multi-choice vote carries the full weight of the vote, or off the deny
+ * weight of a Deny vote. spl-governance does not accept Abstain votes, so there is never
+ * an Abstain vote to revoke from.
+ *
+ * Delegated council voter weight can be revoked from a council veto of a community
+ * proposal too, with the council mint as `realm_governing_token_mint`.
+ *
+ * Delegations recorded in a DelegationLedger are revoked by passing the ledger as
+ * `delegation_record`. The caller's entry is removed from it and its rent returned to the
+ * delegate, who must then also be passed as `rent_payer`.
  */
 #[derive(Accounts)]
 pub struct RevokeVote<'info> {
@@ -57,20 +58,10 @@ pub struct RevokeVote<'info> {
     #[account(mut)]
     delegate: UncheckedAccount<'info>,
 
-    #[account(
-        mut,
-        close = delegate,
-        seeds = [
-            b"voter-weight-record-delegation".as_ref(),
-            realm_info.key().as_ref(),
-            realm_governing_token_mint.key().as_ref(),
-            governing_token_owner.key().as_ref(),
-            delegated_voter_weight_record.weight_action_target.unwrap().key().as_ref(),
-            &borsh::to_vec(&delegated_voter_weight_record.weight_action).unwrap()
-        ],
-        bump
-    )]
-    delegation_record: Account<'info, Delegation>,
+    /// The Delegation PDA of governing_token_owner, or the DelegationLedger of the target
+    /// CHECK: Checked against both addresses during execution
+    #[account(mut)]
+    delegation_record: UncheckedAccount<'info>,
 
     #[account(
         seeds = [
@@ -116,53 +107,38 @@ pub struct RevokeVote<'info> {
     #[account(mut, owner = governance_program_id.key())]
     delegate_token_owner_record_info: UncheckedAccount<'info>,
 
-    /// Either the realm community mint or the council mint.
-    realm_governing_token_mint: Account<'info, Mint>,
+    /// The proposal's governing mint, or the council mint if the vote is a council veto.
+    /// It may be an SPL Token or a Token-2022 mint.
+    /// CHECK: Owned by a token program, and checked against the realm and proposal during execution
+    #[account(constraint = is_token_mint_owner(realm_governing_token_mint.owner) @ DelegationError::InvalidMintOwner)]
+    realm_governing_token_mint: UncheckedAccount<'info>,
 
     /// User who wants their voter weight revoked.
     governing_token_owner: Signer<'info>,
 
     system_program: Program<'info, System>,
+
+    /// Settings the delegation is counted in
+    /// CHECK: Checked against the delegation record or the mint during execution
+    #[account(mut)]
+    settings: UncheckedAccount<'info>,
+
+    /// Account which paid for the delegation record, or the delegate for a ledger entry
+    /// CHECK: Checked against the delegation during execution, and gets its rent back.
+    #[account(mut)]
+    rent_payer: UncheckedAccount<'info>,
 }
 
 pub fn revoke_vote(ctx: Context<RevokeVote>) -> Result<()> {
-    let delegation_record_data = &ctx.accounts.delegation_record;
+    let voter_weight = ctx.accounts.remove_delegation()?;
 
-    require_keys_eq!(
-        delegation_record_data.delegate,
-        ctx.accounts.delegate.key(),
-        DelegationError::NonMatchingDelegationRecordProvided
-    );
-
-    let realm_data = get_realm_data_for_governing_token_mint(
-        ctx.accounts.governance_program_id.key,
-        &ctx.accounts.realm_info,
-        &ctx.accounts.realm_governing_token_mint.key(),
-    )?;
-    get_governance_data_for_realm(
-        ctx.accounts.governance_program_id.key,
-        &ctx.accounts.governance_info.to_account_info(),
-        ctx.accounts.realm_info.key,
-    )?;
-    let proposal_data = get_proposal_data_for_governance_and_governing_mint(
-        ctx.accounts.governance_program_id.key,
-        &ctx.accounts.proposal_info.to_account_info(),
-        ctx.accounts.governance_info.key,
-        &ctx.accounts.realm_governing_token_mint.key(),
-    )?;
-    let delegate_token_owner_record_data =
-        get_token_owner_record_data_for_realm_and_governing_mint(
-            ctx.accounts.governance_program_id.key,
-            &ctx.accounts.delegate_token_owner_record_info,
-            ctx.accounts.realm_info.key,
-            &ctx.accounts.realm_governing_token_mint.key(),
-        )?;
+    let vote_cast = ctx.accounts.relinquish_accounts().load_vote()?;
 
     ctx.accounts.delegated_voter_weight_record.voter_weight = ctx
         .accounts
         .delegated_voter_weight_record
         .voter_weight
-        .checked_sub(delegation_record_data.voter_weight)
+        .checked_sub(voter_weight)
         .unwrap();
 
     // This is needed to prevent double-voting when stacking voter weight plugins
@@ -180,40 +156,10 @@ pub fn revoke_vote(ctx: Context<RevokeVote>) -> Result<()> {
         return Err(DelegationError::VoterWeightRecordMustBeExpired.into());
     }
 
-    // We only need to unvote if a vote has actually been cast.
-    if !ctx.accounts.vote_record_info.data_is_empty() {
-        require_keys_eq!(
-            *ctx.accounts.vote_record_info.owner,
-            ctx.accounts.governance_program_id.key(),
-            ErrorCode::ConstraintOwner
-        );
-        get_vote_record_data_for_proposal_and_token_owner_record(
-            ctx.accounts.governance_program_id.key,
-            &ctx.accounts.vote_record_info,
-            &realm_data,
-            &ctx.accounts.proposal_info.key(),
-            &proposal_data,
-            &delegate_token_owner_record_data,
-        )?;
-
+    if vote_cast {
         ctx.accounts
-            .revoke_weight_record
-            .set_inner(VoterWeightRecord {
-                realm: ctx.accounts.realm_info.key(),
-                governing_token_mint: ctx.accounts.realm_governing_token_mint.key(),
-                governing_token_owner: ctx.accounts.governing_token_owner.key(),
-                voter_weight: delegation_record_data.voter_weight,
-                voter_weight_expiry: None,
-                weight_action: Some(VoterWeightAction::RevokeVote),
-                weight_action_target: Some(ctx.accounts.vote_record_info.key()),
-                reserved: Default::default(),
-            });
-        ctx.accounts.revoke_weight_record.exit(&crate::id())?;
-
-        invoke(
-            &ctx.accounts.get_relinquish_instruction(),
-            &get_relinquish_accounts(&ctx)[..],
-        )?;
+            .relinquish_accounts()
+            .relinquish_weight(ctx.accounts.governing_token_owner.key, voter_weight)?;
     }
 
     // This account is disposed here to prevent double-relinquishment.
@@ -222,44 +168,98 @@ pub fn revoke_vote(ctx: Context<RevokeVote>) -> Result<()> {
         &ctx.accounts.payer,
     );
 
-    // The delegate paid for this account so they get the lamports back.
-    dispose_account(
-        &ctx.accounts.delegation_record.to_account_info(),
-        &ctx.accounts.delegate,
-    );
-
     Ok(())
 }
 
-fn get_relinquish_accounts<'e, 'f>(ctx: &'f Context<RevokeVote<'e>>) -> [AccountInfo<'e>; 10] {
-    [
-        ctx.accounts.realm_info.to_account_info(),
-        ctx.accounts.governance_info.to_account_info(),
-        ctx.accounts.proposal_info.to_account_info(),
-        ctx.accounts
-            .delegate_token_owner_record_info
-            .to_account_info(),
-        ctx.accounts.vote_record_info.to_account_info(),
-        ctx.accounts.realm_governing_token_mint.to_account_info(),
-        ctx.accounts.governing_token_owner.to_account_info(),
-        ctx.accounts.delegate.to_account_info(),
-        ctx.accounts.revoke_weight_record.to_account_info(),
-        ctx.accounts.realm_config_info.to_account_info(),
-    ]
-}
+impl<'info> RevokeVote<'info> {
+    /// Removes the delegation of governing_token_owner's voter weight to the delegate from the
+    /// store it is recorded in, returning the delegated voter weight
+    fn remove_delegation(&self) -> Result<u64> {
+        let realm = self.realm_info.key();
+        let mint = self.realm_governing_token_mint.key();
+        let target = self
+            .delegated_voter_weight_record
+            .weight_action_target
+            .unwrap();
+        let action = self.delegated_voter_weight_record.weight_action;
+        let delegation_info = self.delegation_record.as_ref();
+
+        let is_ledger = delegation_info
+            .try_borrow_data()?
+            .starts_with(&DelegationLedger::discriminator());
+
+        if is_ledger {
+            require_keys_eq!(
+                delegation_info.key(),
+                DelegationLedger::get_pda_address(&realm, &mint, &target, action),
+                DelegationError::IncorrectDelegationAddress
+            );
+            require_keys_eq!(
+                self.settings.key(),
+                Settings::get_pda_address(&realm, &mint),
+                DelegationError::IncorrectSettings
+            );
+            require_keys_eq!(
+                self.rent_payer.key(),
+                self.delegate.key(),
+                DelegationError::IncorrectRentPayer
+            );
+
+            let mut ledger = Account::<DelegationLedger>::try_from(delegation_info)?;
+            let index = ledger
+                .find_entry(delegation_info, self.governing_token_owner.key)
+                .map_err(|_| DelegationError::DelegationNotInLedger)?;
+
+            // Ledger entries do not store who paid for them, their rent goes to the delegate.
+            let entry = ledger.remove_entry(delegation_info, &self.delegate, index)?;
+            ledger.exit(&crate::id())?;
+            Settings::close_delegations(&self.settings, 1)?;
+
+            return Ok(entry.voter_weight);
+        }
+
+        let delegation = Account::<Delegation>::try_from(delegation_info)?;
+        let encoded_action = borsh::to_vec(&action).unwrap();
+        delegation.assert_address(
+            delegation_info.key,
+            &Delegation::get_pda_seeds(
+                &realm,
+                &mint,
+                self.governing_token_owner.key,
+                &target,
+                &encoded_action,
+            ),
+        )?;
+        require_keys_eq!(
+            delegation.delegate,
+            self.delegate.key(),
+            DelegationError::NonMatchingDelegationRecordProvided
+        );
+        require_keys_eq!(
+            self.rent_payer.key(),
+            delegation.rent_payer,
+            DelegationError::IncorrectRentPayer
+        );
+
+        delegation.close_in(&self.settings)?;
+        close_account(delegation_info, &self.rent_payer)?;
+
+        Ok(delegation.voter_weight)
+    }
 
-impl<'a> RevokeVote<'a> {
-    pub fn get_relinquish_instruction(&self) -> Instruction {
-        spl_governance::instruction::relinquish_vote(
-            &self.governance_program_id.key(),
-            &self.realm_info.key(),
-            &self.governance_info.key(),
-            &self.proposal_info.key(),
-            &self.delegate_token_owner_record_info.key(),
-            &self.realm_governing_token_mint.key(),
-            Some(self.governing_token_owner.key()),
-            Some(self.delegate.key()),
-            Some(self.revoke_weight_record.key()),
-        )
+    fn relinquish_accounts(&self) -> RelinquishAccounts<'_, 'info> {
+        RelinquishAccounts {
+            governance_program_id: &self.governance_program_id,
+            realm: &self.realm_info,
+            realm_config: &self.realm_config_info,
+            governance: &self.governance_info,
+            proposal: &self.proposal_info,
+            delegate_token_owner_record: &self.delegate_token_owner_record_info,
+            vote_record: &self.vote_record_info,
+            governing_token_mint: self.realm_governing_token_mint.as_ref(),
+            governance_authority: &self.governing_token_owner,
+            beneficiary: &self.delegate,
+            revoke_weight_record: self.revoke_weight_record.as_ref(),
+        }
     }
 }
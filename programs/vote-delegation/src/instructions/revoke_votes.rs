@@ -0,0 +1,247 @@
+use anchor_lang::prelude::*;
+use spl_governance::state::token_owner_record::get_token_owner_record_data_for_realm_and_governing_mint;
+
+use crate::{
+    error::DelegationError,
+    state::{delegation::Delegation, voter_weight_record::VoterWeightRecord},
+    tools::{close_account, dispose_account, is_token_mint_owner, RelinquishAccounts},
+};
+
+/**
+ * Revokes voter weight delegated by several users at once
+ *
+ * This instruction behaves like `revoke_vote` for every delegator provided, but only needs
+ * the accounts shared between them once. It returns the total voter weight revoked.
+ *
+ * For each delegator to revoke, the following accounts must be provided as additional
+ * accounts:
+ *  - The Realms TokenOwnerRecord of the delegator (not signer, not writable).
+ *  - The Delegation PDA account `Delegation::get_pda_address` (not signer, writable).
+ *  - The delegator, who must be the governing token owner of the TokenOwnerRecord (signer,
+ * not writable).
+ *  - The account which paid for the Delegation, `Delegation::rent_payer` (not signer,
+ * writable).
+ */
+#[derive(Accounts)]
+pub struct RevokeVotes<'info> {
+    /// CHECK: Payer
+    #[account(mut)]
+    payer: UncheckedAccount<'info>,
+
+    /// This account is created, used, and freed within this instruction.
+    #[account(
+        init,
+        seeds = [
+            b"revocation".as_ref(),
+            realm_info.key().as_ref(),
+            realm_governing_token_mint.key().as_ref(),
+            delegate.key().as_ref(),
+            delegated_voter_weight_record.weight_action_target.unwrap().key().as_ref(),
+            &borsh::to_vec(&delegated_voter_weight_record.weight_action).unwrap()
+        ],
+        bump,
+        payer = payer,
+        space = 8 + std::mem::size_of::<VoterWeightRecord>(),
+        owner = crate::ID
+    )]
+    revoke_weight_record: Account<'info, VoterWeightRecord>,
+
+    /// User who cast the vote which is now being revoked.
+    /// CHECK: Delegate
+    #[account(mut)]
+    delegate: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"voter-weight-record".as_ref(),
+            realm_info.key().as_ref(),
+            realm_governing_token_mint.key().as_ref(),
+            delegate.key().as_ref(),
+            delegated_voter_weight_record.weight_action_target.unwrap().key().as_ref(),
+            &borsh::to_vec(&delegated_voter_weight_record.weight_action).unwrap()
+        ],
+        bump,
+        owner = crate::ID
+    )]
+    delegated_voter_weight_record: Account<'info, VoterWeightRecord>,
+
+    /// The program id of the spl-governance program the realm belongs to
+    /// CHECK: Can be any instance of spl-governance and it's not known at the compilation time
+    #[account(executable)]
+    governance_program_id: UncheckedAccount<'info>,
+
+    /// CHECK: Ownership checked during execution
+    #[account(mut)]
+    vote_record_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(owner = governance_program_id.key())]
+    realm_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(owner = governance_program_id.key())]
+    realm_config_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(owner = governance_program_id.key())]
+    governance_info: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(mut, owner = governance_program_id.key())]
+    proposal_info: UncheckedAccount<'info>,
+
+    /// Token Owner Record for Delegate.
+    /// CHECK: Owned by spl-governance instance specified in governance_program_id
+    #[account(mut, owner = governance_program_id.key())]
+    delegate_token_owner_record_info: UncheckedAccount<'info>,
+
+    /// Either the realm community mint or the council mint.
+    /// CHECK: Owned by a token program, and checked against the realm during execution
+    #[account(constraint = is_token_mint_owner(realm_governing_token_mint.owner) @ DelegationError::InvalidMintOwner)]
+    realm_governing_token_mint: UncheckedAccount<'info>,
+
+    system_program: Program<'info, System>,
+
+    /// Settings the delegation records are counted in
+    /// CHECK: Checked against each delegation record during execution
+    #[account(mut)]
+    settings: UncheckedAccount<'info>,
+}
+
+pub fn revoke_votes<'info>(ctx: Context<'_, '_, '_, 'info, RevokeVotes<'info>>) -> Result<u64> {
+    require_eq!(
+        ctx.remaining_accounts.len() % 4,
+        0,
+        DelegationError::MissingDelegatorAccounts
+    );
+
+    let vote_cast = ctx
+        .accounts
+        .relinquish_accounts(&ctx.accounts.payer)
+        .load_vote()?;
+
+    // See revoke_vote for why the delegate's record must be expired.
+    if ctx
+        .accounts
+        .delegated_voter_weight_record
+        .voter_weight_expiry
+        >= Some(Clock::get()?.slot)
+    {
+        return Err(DelegationError::VoterWeightRecordMustBeExpired.into());
+    }
+
+    let target = ctx
+        .accounts
+        .delegated_voter_weight_record
+        .weight_action_target
+        .unwrap();
+    let encoded_action =
+        borsh::to_vec(&ctx.accounts.delegated_voter_weight_record.weight_action).unwrap();
+    let mut total_revoked: u64 = 0;
+
+    for to_revoke in ctx.remaining_accounts.chunks_exact(4) {
+        let mut to_revoke_iter = to_revoke.iter();
+        let token_owner_info = to_revoke_iter.next().unwrap();
+        let delegation_info = to_revoke_iter.next().unwrap();
+        let governing_token_owner_info = to_revoke_iter.next().unwrap();
+        let rent_payer_info = to_revoke_iter.next().unwrap();
+
+        let token_owner_record = get_token_owner_record_data_for_realm_and_governing_mint(
+            ctx.accounts.governance_program_id.key,
+            token_owner_info,
+            ctx.accounts.realm_info.key,
+            &ctx.accounts.realm_governing_token_mint.key(),
+        )?;
+
+        require_keys_eq!(
+            token_owner_record.governing_token_owner,
+            governing_token_owner_info.key(),
+            DelegationError::DelegatorMustSign
+        );
+        require!(
+            governing_token_owner_info.is_signer,
+            DelegationError::DelegatorMustSign
+        );
+
+        let delegation = Account::<Delegation>::try_from(delegation_info)?;
+        delegation.assert_address(
+            delegation_info.key,
+            &Delegation::get_pda_seeds(
+                ctx.accounts.realm_info.key,
+                &token_owner_record.governing_token_mint,
+                &token_owner_record.governing_token_owner,
+                &target,
+                &encoded_action,
+            ),
+        )?;
+        require_keys_eq!(
+            delegation.delegate,
+            ctx.accounts.delegate.key(),
+            DelegationError::NonMatchingDelegationRecordProvided
+        );
+        require_keys_eq!(
+            delegation.voter_weight_record,
+            ctx.accounts.delegated_voter_weight_record.key(),
+            DelegationError::NonMatchingDelegationRecordProvided
+        );
+        require_keys_eq!(
+            delegation.rent_payer,
+            rent_payer_info.key(),
+            DelegationError::IncorrectRentPayer
+        );
+
+        ctx.accounts.delegated_voter_weight_record.voter_weight = ctx
+            .accounts
+            .delegated_voter_weight_record
+            .voter_weight
+            .checked_sub(delegation.voter_weight)
+            .unwrap();
+        total_revoked = total_revoked.checked_add(delegation.voter_weight).unwrap();
+
+        if vote_cast {
+            ctx.accounts
+                .relinquish_accounts(governing_token_owner_info)
+                .relinquish_weight(governing_token_owner_info.key, delegation.voter_weight)?;
+        }
+
+        delegation.close_in(&ctx.accounts.settings)?;
+
+        close_account(delegation_info, rent_payer_info)?;
+    }
+
+    // This account is disposed here to prevent double-relinquishment.
+    dispose_account(
+        &ctx.accounts.revoke_weight_record.to_account_info(),
+        &ctx.accounts.payer,
+    );
+
+    msg!(
+        "Revoked {} voter weight from {} delegations",
+        total_revoked,
+        ctx.remaining_accounts.len() / 4
+    );
+
+    Ok(total_revoked)
+}
+
+impl<'info> RevokeVotes<'info> {
+    fn relinquish_accounts<'a>(
+        &'a self,
+        governing_token_owner: &'a AccountInfo<'info>,
+    ) -> RelinquishAccounts<'a, 'info> {
+        RelinquishAccounts {
+            governance_program_id: &self.governance_program_id,
+            realm: &self.realm_info,
+            realm_config: &self.realm_config_info,
+            governance: &self.governance_info,
+            proposal: &self.proposal_info,
+            delegate_token_owner_record: &self.delegate_token_owner_record_info,
+            vote_record: &self.vote_record_info,
+            governing_token_mint: self.realm_governing_token_mint.as_ref(),
+            governance_authority: governing_token_owner,
+            beneficiary: &self.delegate,
+            revoke_weight_record: self.revoke_weight_record.as_ref(),
+        }
+    }
+}
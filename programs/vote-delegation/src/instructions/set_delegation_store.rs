@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+use spl_governance::state::realm::get_realm_data;
+
+use crate::{
+    error::DelegationError,
+    state::settings::{DelegationStore, Settings},
+};
+
+/**
+ * Chooses where delegations of voter weight are recorded
+ *
+ * This instruction must be signed by the realm's authority, and `set_precursor` must
+ * already have been called for the mint. Delegations made in one store are not visible
+ * to the other, so the store can only be changed once every delegation recorded in the
+ * current store has been closed.
+ */
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct SetDelegationStore<'info> {
+    signer: Signer<'info>,
+
+    #[account(mut)]
+    payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"settings".as_ref(),
+            realm_info.key().as_ref(),
+            mint.as_ref(),
+        ],
+        bump
+    )]
+    settings: Account<'info, Settings>,
+
+    /// The program id of the spl-governance program the realm belongs to
+    /// CHECK: Can be any instance of spl-governance and it's not known at the compilation time
+    #[account(executable)]
+    governance_program_id: UncheckedAccount<'info>,
+
+    /// CHECK: Manually deserialized.
+    realm_info: UncheckedAccount<'info>,
+
+    system_program: Program<'info, System>,
+}
+
+pub fn set_delegation_store(
+    ctx: Context<SetDelegationStore>,
+    _mint: Pubkey,
+    delegation_store: DelegationStore,
+) -> Result<()> {
+    let realm_data = get_realm_data(
+        ctx.accounts.governance_program_id.key,
+        &ctx.accounts.realm_info,
+    )?;
+
+    require!(
+        realm_data.authority.is_some(),
+        DelegationError::NotRealmAuthority
+    );
+    require_keys_eq!(
+        realm_data.authority.unwrap(),
+        ctx.accounts.signer.key(),
+        DelegationError::NotRealmAuthority
+    );
+
+    require!(
+        delegation_store == ctx.accounts.settings.delegation_store
+            || ctx.accounts.settings.open_delegations == 0,
+        DelegationError::DelegationStoreInUse
+    );
+
+    Settings::migrate(
+        &mut ctx.accounts.settings,
+        &ctx.accounts.payer,
+        &ctx.accounts.system_program,
+    )?;
+
+    ctx.accounts.settings.delegation_store = delegation_store;
+
+    Ok(())
+}
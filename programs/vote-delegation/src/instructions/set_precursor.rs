@@ -7,8 +7,8 @@ use crate::{error::DelegationError, state::settings::Settings};
  * Sets up voter weight delegation middleware
  *
  * This instruction must be signed by the realm's authority. It can be used to configure
- * the source of voter weight used by this program for a particular realm and community
- * mint.
+ * the source of voter weight used by this program for a particular realm and governing
+ * token mint, which can be the council mint.
  */
 #[derive(Accounts)]
 #[instruction(mint: Pubkey, voter_weight_source: Pubkey)]
@@ -22,7 +22,7 @@ pub struct SetPrecursor<'info> {
     #[account(
         init_if_needed,
         payer = payer,
-        space = 8 + std::mem::size_of::<Settings>(),
+        space = Settings::size(),
         seeds = [
             b"settings".as_ref(),
             realm_info.key().as_ref(),
@@ -5,8 +5,11 @@ use static_assertions::const_assert;
 use crate::{
     error::DelegationError,
     state::{
-        delegation::Delegation,
-        settings::Settings,
+        delegation::{Delegation, DelegationV2},
+        delegation_ledger::{DelegationLedger, LedgerEntry},
+        session::Session,
+        settings::{DelegationStore, Settings},
+        signed_delegation::SignedDelegation,
         voter_weight_record::{VoterWeightAction, VoterWeightRecord},
     },
 };
@@ -19,14 +22,28 @@ use crate::{
  *
  * For each source of voter weight to be aggregated, the following accounts must be
  * provided as additional accounts:
- *  - The VoterWeightRecord account produced by this program's voter weight source (not
- * signer, not writable).
+ *  - The delegator's VoterWeightRecord account produced by this program's voter weight
+ * source, which must be unexpired (not signer, not writable).
  *  - The Realms TokenOwnerRecord of the delegator (not signer, not writable).
- *  - The Delegation PDA account `Delegation::get_pda_address` (not signer, writable).
+ *  - The Delegation PDA account `Delegation::get_pda_address` (not signer, writable), or
+ * the DelegationLedger of the target `DelegationLedger::get_pda_address` if the settings
+ * record delegations in a ledger.
+ *
+ * A delegator who delegated with `delegate_with_signature` provides their SignedDelegation
+ * in place of the TokenOwnerRecord.
+ *
+ * delegation_bumps holds the canonical bump of each source's Delegation PDA, in the order of
+ * the sources. They are not used when the settings record delegations in a ledger.
+ *
+ * A session key registered with `create_session` can sign instead of the delegate. The
+ * delegate's Session account must then be provided as the first additional account, before
+ * the accounts of the sources. A session key can only aggregate for the action and target the
+ * Voter Weight Record was created for.
  */
 #[derive(Accounts)]
 #[instruction(voter_weight_action: VoterWeightAction, target: Option<Pubkey>)]
 pub struct UpdateVoterWeightRecord<'info> {
+    /// The delegate, or a session key signing for them.
     delegate: Signer<'info>,
 
     /// CHECK: Payer
@@ -34,6 +51,7 @@ pub struct UpdateVoterWeightRecord<'info> {
     payer: UncheckedAccount<'info>,
 
     #[account(
+        mut,
         seeds = [
             b"settings".as_ref(),
             realm.key().as_ref(),
@@ -71,12 +89,13 @@ pub struct UpdateVoterWeightRecord<'info> {
 }
 
 const_assert!(APPROX_SLOTS_PER_MINUTE > 0);
-const APPROX_SLOTS_PER_MINUTE: u64 = (60.0 / DEFAULT_S_PER_SLOT) as u64;
+pub(crate) const APPROX_SLOTS_PER_MINUTE: u64 = (60.0 / DEFAULT_S_PER_SLOT) as u64;
 
 pub fn update_voter_weight_record<'info>(
     ctx: Context<'_, '_, '_, 'info, UpdateVoterWeightRecord<'info>>,
     voter_weight_action: VoterWeightAction,
     target: Option<Pubkey>,
+    delegation_bumps: Vec<u8>,
 ) -> Result<()> {
     require_keys_eq!(
         ctx.accounts.realm.key(),
@@ -84,99 +103,264 @@ pub fn update_voter_weight_record<'info>(
         DelegationError::InvalidRealm
     );
 
+    let (delegate, delegator_accounts) = if ctx.remaining_accounts.len() % 3 == 1 {
+        // A session key may only aggregate for the action and target the record was created
+        // for, so it cannot repurpose the record for an action outside its scope.
+        let voter_weight_record = &ctx.accounts.voter_weight_record;
+        require!(
+            voter_weight_record.weight_action == Some(voter_weight_action),
+            DelegationError::InvalidActionType
+        );
+        require!(
+            voter_weight_record.weight_action_target == target,
+            DelegationError::IncorrectTarget
+        );
+
+        let session = Account::<Session>::try_from(&ctx.remaining_accounts[0])?;
+        session.assert_can_sign(
+            ctx.accounts.delegate.key,
+            ctx.accounts.realm.key,
+            voter_weight_record.weight_action.unwrap(),
+        )?;
+        (session.delegate, &ctx.remaining_accounts[1..])
+    } else {
+        (ctx.accounts.delegate.key(), ctx.remaining_accounts)
+    };
+    aggregate_delegations(
+        &mut ctx.accounts.voter_weight_record,
+        delegate,
+        delegator_accounts,
+        Some(delegation_bumps.as_slice()),
+        &mut ctx.accounts.settings,
+        ctx.accounts.governance_program_id.key,
+        &ctx.accounts.payer,
+        &ctx.accounts.system_program,
+    )?;
+
+    // Give some time to spend multiple transactions aggregating.
+    ctx.accounts.voter_weight_record.voter_weight_expiry =
+        Some(Clock::get()?.slot + APPROX_SLOTS_PER_MINUTE);
+    ctx.accounts.voter_weight_record.weight_action = Some(voter_weight_action);
+    ctx.accounts.voter_weight_record.weight_action_target = target;
+
+    Ok(())
+}
+
+/// Aggregates the voter weight of each source in delegator_accounts into voter_weight_record,
+/// recording their delegation to delegate in the store of the settings
+///
+/// delegator_accounts holds the additional accounts of `update_voter_weight_record` for the
+/// sources. The caller checks the realm of the record first, and the settings must be
+/// writable to count the new delegations.
+///
+/// delegation_bumps holds the bumps of the Delegation PDAs supplied by the client, which are
+/// checked to be canonical. Without them the bumps are derived here.
+pub(crate) fn aggregate_delegations<'info>(
+    voter_weight_record: &mut Account<'info, VoterWeightRecord>,
+    delegate: Pubkey,
+    delegator_accounts: &[AccountInfo<'info>],
+    delegation_bumps: Option<&[u8]>,
+    settings: &mut Account<'info, Settings>,
+    governance_program_id: &Pubkey,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
     require_eq!(
-        ctx.remaining_accounts.len() % 3,
+        delegator_accounts.len() % 3,
         0,
         DelegationError::MissingDelegatorAccounts
     );
 
-    for to_aggregate in ctx.remaining_accounts.chunks_exact(3) {
+    Settings::migrate(settings, payer, system_program)?;
+
+    // Every delegation of a ledger store goes to the one ledger of the target.
+    let mut ledger = match (settings.delegation_store, delegator_accounts.get(2)) {
+        (DelegationStore::Ledger, Some(ledger_info)) => {
+            require_keys_eq!(
+                ledger_info.key(),
+                DelegationLedger::get_pda_address(
+                    &voter_weight_record.realm,
+                    &voter_weight_record.governing_token_mint,
+                    &voter_weight_record.weight_action_target.unwrap(),
+                    voter_weight_record.weight_action,
+                ),
+                DelegationError::IncorrectDelegationAddress
+            );
+            Some(Account::<DelegationLedger>::try_from(ledger_info)?)
+        }
+        _ => None,
+    };
+
+    // Seeds shared by every delegation record are encoded once for the whole batch.
+    let encoded_action = borsh::to_vec(&voter_weight_record.weight_action).unwrap();
+    let delegation_target = voter_weight_record.weight_action_target.unwrap();
+    let voter_weight_record_key = voter_weight_record.key();
+    let current_slot = Clock::get()?.slot;
+
+    for (index, to_aggregate) in delegator_accounts.chunks_exact(3).enumerate() {
         // Accumulate vote weight
         let mut to_aggregate_iter = to_aggregate.iter();
         let vwr_account = to_aggregate_iter.next().unwrap();
         let token_owner_info = to_aggregate_iter.next().unwrap();
         let delegation_info = to_aggregate_iter.next().unwrap();
 
-        let token_owner_record = get_token_owner_record_data_for_realm_and_governing_mint(
-            ctx.accounts.governance_program_id.key,
-            token_owner_info,
-            &ctx.accounts.voter_weight_record.realm,
-            &ctx.accounts.voter_weight_record.governing_token_mint,
-        )?;
-
-        // You can always aggregate your own voter weight.
-        if token_owner_record.governing_token_owner != ctx.accounts.delegate.key() {
-            require!(
-                token_owner_record.governance_delegate.is_some(),
-                DelegationError::VoterWeightNotDelegatedToDelegate
+        let (governing_token_owner, signed) = if *token_owner_info.owner == crate::id() {
+            let mut data: &[u8] = &token_owner_info.try_borrow_data()?;
+            let signed_delegation = SignedDelegation::try_deserialize(&mut data)?;
+            require_keys_eq!(
+                signed_delegation.realm,
+                voter_weight_record.realm,
+                DelegationError::InvalidRealm
             );
             require_keys_eq!(
-                token_owner_record.governance_delegate.unwrap(),
-                ctx.accounts.delegate.key(),
+                signed_delegation.governing_token_mint,
+                voter_weight_record.governing_token_mint,
+                DelegationError::InvalidGoverningTokenMint
+            );
+            require!(
+                signed_delegation.delegates_to(
+                    &delegate,
+                    voter_weight_record.weight_action.unwrap(),
+                    current_slot
+                ),
                 DelegationError::VoterWeightNotDelegatedToDelegate
             );
-        }
+            (signed_delegation.delegator, true)
+        } else {
+            let token_owner_record = get_token_owner_record_data_for_realm_and_governing_mint(
+                governance_program_id,
+                token_owner_info,
+                &voter_weight_record.realm,
+                &voter_weight_record.governing_token_mint,
+            )?;
+
+            // You can always aggregate your own voter weight.
+            if token_owner_record.governing_token_owner != delegate {
+                require!(
+                    token_owner_record.governance_delegate.is_some(),
+                    DelegationError::VoterWeightNotDelegatedToDelegate
+                );
+                require_keys_eq!(
+                    token_owner_record.governance_delegate.unwrap(),
+                    delegate,
+                    DelegationError::VoterWeightNotDelegatedToDelegate
+                );
+            }
+            (token_owner_record.governing_token_owner, false)
+        };
 
         require_keys_eq!(
             *vwr_account.owner,
-            ctx.accounts.settings.voter_weight_source,
+            settings.voter_weight_source,
             DelegationError::InvalidVoterWeightRecordSource
         );
 
+        // The source must hold the voter weight of the delegator the record is created for,
+        // whichever way they delegated.
+        let mut data: &[u8] = &vwr_account.try_borrow_data()?;
+        let to_agg = VoterWeightRecord::try_deserialize(&mut data)?;
+        require_keys_eq!(
+            to_agg.governing_token_owner,
+            governing_token_owner,
+            DelegationError::InvalidVoterWeightRecordSource
+        );
+        require_keys_eq!(
+            to_agg.realm,
+            voter_weight_record.realm,
+            DelegationError::InvalidRealm
+        );
+        require_keys_eq!(
+            to_agg.governing_token_mint,
+            voter_weight_record.governing_token_mint,
+            DelegationError::InvalidGoverningTokenMint
+        );
         require!(
-            delegation_info.data_is_empty(),
-            DelegationError::VoterWeightAlreadyDelegated
+            to_agg
+                .voter_weight_expiry
+                .map_or(true, |expiry| expiry >= current_slot),
+            DelegationError::VoterWeightSourceExpired
         );
+        voter_weight_record.voter_weight = voter_weight_record.try_aggregate(&to_agg)?;
 
-        let mut data: &[u8] = &vwr_account.try_borrow_data()?;
-        let to_agg = VoterWeightRecord::try_deserialize(&mut data)?;
-        ctx.accounts.voter_weight_record.voter_weight =
-            ctx.accounts.voter_weight_record.try_aggregate(&to_agg)?;
+        if let Some(ledger) = ledger.as_mut() {
+            // Record delegation
+            require_keys_eq!(
+                delegation_info.key(),
+                ledger.key(),
+                DelegationError::IncorrectDelegationAddress
+            );
+            let index = match ledger.find_entry(delegation_info, &governing_token_owner) {
+                Ok(_) => return Err(DelegationError::VoterWeightAlreadyDelegated.into()),
+                Err(index) => index,
+            };
+            ledger.insert_entry(
+                delegation_info,
+                payer,
+                system_program,
+                index,
+                &LedgerEntry {
+                    delegator: governing_token_owner,
+                    delegate,
+                    voter_weight: to_agg.voter_weight,
+                },
+            )?;
+            continue;
+        }
 
         // Create delegation record
-        let encoded_action =
-            borsh::to_vec(&ctx.accounts.voter_weight_record.weight_action).unwrap();
-        let target = ctx
-            .accounts
-            .voter_weight_record
-            .weight_action_target
-            .unwrap();
-        let signer_seeds = Delegation::get_pda_seeds(
-            ctx.accounts.realm.key,
-            &token_owner_record.governing_token_mint,
-            &token_owner_record.governing_token_owner,
-            &target,
+        require!(
+            delegation_info.data_is_empty(),
+            DelegationError::VoterWeightAlreadyDelegated
+        );
+
+        let seeds = Delegation::get_pda_seeds(
+            &voter_weight_record.realm,
+            &voter_weight_record.governing_token_mint,
+            &governing_token_owner,
+            &delegation_target,
             &encoded_action,
         );
-        let (_, bump) = Pubkey::find_program_address(&signer_seeds, &crate::id());
+        let bump = match delegation_bumps {
+            Some(bumps) => {
+                let bump = *bumps
+                    .get(index)
+                    .ok_or(DelegationError::MissingDelegationBumps)?;
+                Delegation::assert_canonical_bump(delegation_info.key, &seeds, bump)?;
+                bump
+            }
+            None => {
+                let (address, bump) = Pubkey::find_program_address(&seeds, &crate::id());
+                require_keys_eq!(
+                    address,
+                    delegation_info.key(),
+                    DelegationError::IncorrectDelegationAddress
+                );
+                bump
+            }
+        };
         Delegation::try_create(
             delegation_info,
-            &ctx.accounts.payer,
-            &ctx.accounts.system_program,
-            &Delegation::get_pda_seeds(
-                ctx.accounts.realm.key,
-                &token_owner_record.governing_token_mint.key(),
-                &token_owner_record.governing_token_owner,
-                &ctx.accounts
-                    .voter_weight_record
-                    .weight_action_target
-                    .unwrap(),
-                &borsh::to_vec(&ctx.accounts.voter_weight_record.weight_action).unwrap(),
-            ),
+            payer,
+            system_program,
+            &seeds,
             &[bump],
-            &Delegation {
-                delegate: ctx.accounts.delegate.key(),
-                voter_weight_record: ctx.accounts.voter_weight_record.key(),
+            &DelegationV2 {
+                delegate,
+                voter_weight_record: voter_weight_record_key,
                 voter_weight: to_agg.voter_weight,
+                rent_payer: payer.key(),
+                settings: settings.key(),
+                signed,
+                bump,
             },
         )?;
     }
 
-    // Give some time to spend multiple transactions aggregating.
-    ctx.accounts.voter_weight_record.voter_weight_expiry =
-        Some(Clock::get()?.slot + APPROX_SLOTS_PER_MINUTE);
-    ctx.accounts.voter_weight_record.weight_action = Some(voter_weight_action);
-    ctx.accounts.voter_weight_record.weight_action_target = target;
+    if let Some(ledger) = ledger {
+        ledger.exit(&crate::id())?;
+    }
+
+    settings.open_delegations += (delegator_accounts.len() / 3) as u64;
 
     Ok(())
 }
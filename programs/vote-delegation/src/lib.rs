@@ -1,3 +1,10 @@
+#[cfg(all(feature = "relinquish-partial", feature = "upstream-governance"))]
+compile_error!("Features relinquish-partial and upstream-governance are mutually exclusive.");
+
+#[cfg(feature = "upstream-governance")]
+extern crate spl_governance_upstream as spl_governance;
+
+pub mod cpi_helpers;
 pub mod error;
 mod instructions;
 pub mod state;
@@ -5,7 +12,12 @@ mod tools;
 
 use anchor_lang::prelude::*;
 use instructions::*;
-use state::voter_weight_record::VoterWeightAction;
+use state::{
+    settings::DelegationStore,
+    signed_delegation::DelegationMessage,
+    vote::{Vote, VoteType},
+    voter_weight_record::VoterWeightAction,
+};
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 
@@ -22,10 +34,67 @@ pub mod vote_delegation {
         instructions::create_voter_weight_record(ctx, governing_token_owner, target, action)
     }
 
+    pub fn create_delegation_ledger(
+        ctx: Context<CreateDelegationLedger>,
+        target: Pubkey,
+        action: VoterWeightAction,
+    ) -> Result<()> {
+        instructions::create_delegation_ledger(ctx, target, action)
+    }
+
+    pub fn create_session(
+        ctx: Context<CreateSession>,
+        session_key: Pubkey,
+        realm: Option<Pubkey>,
+        allowed_actions: Vec<VoterWeightAction>,
+        expiry: u64,
+    ) -> Result<()> {
+        instructions::create_session(ctx, session_key, realm, allowed_actions, expiry)
+    }
+
+    pub fn revoke_session(ctx: Context<RevokeSession>) -> Result<()> {
+        instructions::revoke_session(ctx)
+    }
+
+    pub fn delegate_with_signature(
+        ctx: Context<DelegateWithSignature>,
+        message: DelegationMessage,
+    ) -> Result<()> {
+        instructions::delegate_with_signature(ctx, message)
+    }
+
     pub fn revoke_vote(ctx: Context<RevokeVote>) -> Result<()> {
         instructions::revoke_vote(ctx)
     }
 
+    pub fn revoke_delegation(ctx: Context<RevokeDelegation>) -> Result<()> {
+        instructions::revoke_delegation(ctx)
+    }
+
+    pub fn revoke_partial(ctx: Context<RevokePartial>, amount: u64) -> Result<()> {
+        instructions::revoke_partial(ctx, amount)
+    }
+
+    pub fn revoke_stale_delegation(ctx: Context<RevokeStaleDelegation>) -> Result<()> {
+        instructions::revoke_stale_delegation(ctx)
+    }
+
+    pub fn revoke_votes<'info>(ctx: Context<'_, '_, '_, 'info, RevokeVotes<'info>>) -> Result<u64> {
+        instructions::revoke_votes(ctx)
+    }
+
+    pub fn release_delegation(ctx: Context<ReleaseDelegation>) -> Result<()> {
+        instructions::release_delegation(ctx)
+    }
+
+    pub fn refresh_delegation(ctx: Context<RefreshDelegation>) -> Result<()> {
+        instructions::refresh_delegation(ctx)
+    }
+
+    pub fn override_vote(ctx: Context<OverrideVote>, vote: Vote) -> Result<()> {
+        instructions::override_vote(ctx, vote)
+    }
+
     pub fn set_precursor(
         ctx: Context<SetPrecursor>,
         mint: Pubkey,
@@ -34,19 +103,75 @@ pub mod vote_delegation {
         instructions::set_precursor(ctx, mint, voter_weight_source)
     }
 
+    pub fn set_delegation_store(
+        ctx: Context<SetDelegationStore>,
+        mint: Pubkey,
+        delegation_store: DelegationStore,
+    ) -> Result<()> {
+        instructions::set_delegation_store(ctx, mint, delegation_store)
+    }
+
     pub fn update_voter_weight_record<'info>(
         ctx: Context<'_, '_, '_, 'info, UpdateVoterWeightRecord<'info>>,
         voter_weight_action: VoterWeightAction,
         target: Option<Pubkey>,
+        delegation_bumps: Vec<u8>,
+    ) -> Result<()> {
+        instructions::update_voter_weight_record(ctx, voter_weight_action, target, delegation_bumps)
+    }
+
+    pub fn aggregate_and_cast_vote<'info>(
+        ctx: Context<'_, '_, '_, 'info, AggregateAndCastVote<'info>>,
+        vote: Vote,
     ) -> Result<()> {
-        instructions::update_voter_weight_record(ctx, voter_weight_action, target)
+        instructions::aggregate_and_cast_vote(ctx, vote)
+    }
+
+    pub fn aggregate_and_create_proposal<'info>(
+        ctx: Context<'_, '_, '_, 'info, AggregateAndCreateProposal<'info>>,
+        name: String,
+        description_link: String,
+        vote_type: VoteType,
+        options: Vec<String>,
+        use_deny_option: bool,
+    ) -> Result<()> {
+        instructions::aggregate_and_create_proposal(
+            ctx,
+            name,
+            description_link,
+            vote_type,
+            options,
+            use_deny_option,
+        )
+    }
+
+    pub fn crank_voter_weight_record<'info>(
+        ctx: Context<'_, '_, '_, 'info, CrankVoterWeightRecord<'info>>,
+    ) -> Result<()> {
+        instructions::crank_voter_weight_record(ctx)
     }
 
     pub fn reclaim_voter_weight_record(ctx: Context<ReclaimVoterWeightRecord>) -> Result<()> {
         instructions::reclaim_voter_weight_record(ctx)
     }
 
+    pub fn reclaim_voter_weight_records<'info>(
+        ctx: Context<'_, '_, '_, 'info, ReclaimVoterWeightRecords<'info>>,
+    ) -> Result<u32> {
+        instructions::reclaim_voter_weight_records(ctx)
+    }
+
     pub fn reclaim_delegation(ctx: Context<ReclaimDelegation>) -> Result<()> {
         instructions::reclaim_delegation(ctx)
     }
+
+    pub fn reclaim_delegations<'info>(
+        ctx: Context<'_, '_, '_, 'info, ReclaimDelegations<'info>>,
+    ) -> Result<u32> {
+        instructions::reclaim_delegations(ctx)
+    }
+
+    pub fn refund_voter_weight_record(ctx: Context<RefundVoterWeightRecord>) -> Result<()> {
+        instructions::refund_voter_weight_record(ctx)
+    }
 }
@@ -1,18 +1,164 @@
+use std::io;
+
 use anchor_lang::prelude::*;
 use anchor_lang::Discriminator;
 use solana_program::{
     program::{invoke, invoke_signed},
     system_instruction::{self, create_account},
 };
+use spl_governance::state::token_owner_record::TokenOwnerRecordV2;
 
-use super::voter_weight_record::VoterWeightAction;
+use super::{settings::Settings, voter_weight_record::VoterWeightAction};
+use crate::error::DelegationError;
 
-#[derive(Debug)]
-#[account]
+/// Delegation records voter weight delegated by one delegator for a target and action
+///
+/// Records are stored in the layout of their version, see DelegationV1 and DelegationV2, and
+/// are written back in the version they were read in. Only override_vote upgrades them.
+#[derive(Debug, Clone)]
 pub struct Delegation {
+    pub version: DelegationVersion,
     pub delegate: Pubkey,
     pub voter_weight_record: Pubkey,
     pub voter_weight: u64,
+
+    /// Account which paid the rent of this record, and is refunded when it is reclaimed
+    ///
+    /// V1 records were paid for by their delegate.
+    pub rent_payer: Pubkey,
+
+    /// Settings whose open_delegations count this record, V1 records are not part of the count
+    pub settings: Pubkey,
+
+    /// Whether the voter weight was delegated with a signed message, see SignedDelegation
+    pub signed: bool,
+
+    /// Canonical bump of the record's address, V1 records do not store it
+    pub bump: Option<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DelegationVersion {
+    V1,
+    V2,
+}
+
+/// Layout of the records created before their rent payer and settings were stored
+#[derive(AnchorSerialize, AnchorDeserialize, Debug)]
+pub struct DelegationV1 {
+    pub delegate: Pubkey,
+    pub voter_weight_record: Pubkey,
+    pub voter_weight: u64,
+}
+
+impl DelegationV1 {
+    /// The discriminator Anchor gave these records, `sha256("account:Delegation")[..8]`
+    pub const DISCRIMINATOR: [u8; 8] = [237, 90, 140, 159, 124, 255, 243, 80];
+}
+
+/// Layout of the records created since they are counted in their settings
+#[account]
+#[derive(Debug)]
+pub struct DelegationV2 {
+    pub delegate: Pubkey,
+    pub voter_weight_record: Pubkey,
+    pub voter_weight: u64,
+    pub rent_payer: Pubkey,
+    pub settings: Pubkey,
+    pub signed: bool,
+    pub bump: u8,
+}
+
+impl From<DelegationV1> for Delegation {
+    fn from(record: DelegationV1) -> Self {
+        Self {
+            version: DelegationVersion::V1,
+            delegate: record.delegate,
+            voter_weight_record: record.voter_weight_record,
+            voter_weight: record.voter_weight,
+            rent_payer: record.delegate,
+            settings: Pubkey::default(),
+            signed: false,
+            bump: None,
+        }
+    }
+}
+
+impl From<DelegationV2> for Delegation {
+    fn from(record: DelegationV2) -> Self {
+        Self {
+            version: DelegationVersion::V2,
+            delegate: record.delegate,
+            voter_weight_record: record.voter_weight_record,
+            voter_weight: record.voter_weight,
+            rent_payer: record.rent_payer,
+            settings: record.settings,
+            signed: record.signed,
+            bump: Some(record.bump),
+        }
+    }
+}
+
+impl AccountDeserialize for Delegation {
+    fn try_deserialize(buf: &mut &[u8]) -> Result<Self> {
+        if buf.len() < 8 {
+            return Err(ErrorCode::AccountDiscriminatorNotFound.into());
+        }
+
+        if buf[..8] == DelegationV1::DISCRIMINATOR || buf[..8] == DelegationV2::discriminator() {
+            Self::try_deserialize_unchecked(buf)
+        } else {
+            Err(ErrorCode::AccountDiscriminatorMismatch.into())
+        }
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self> {
+        if buf[..8] == DelegationV1::DISCRIMINATOR {
+            let mut data: &[u8] = &buf[8..];
+            return DelegationV1::deserialize(&mut data)
+                .map(Delegation::from)
+                .map_err(|_| ErrorCode::AccountDidNotDeserialize.into());
+        }
+
+        DelegationV2::try_deserialize_unchecked(buf).map(Delegation::from)
+    }
+}
+
+impl AccountSerialize for Delegation {
+    fn try_serialize<W: io::Write>(&self, writer: &mut W) -> Result<()> {
+        match self.version {
+            DelegationVersion::V1 => {
+                let record = DelegationV1 {
+                    delegate: self.delegate,
+                    voter_weight_record: self.voter_weight_record,
+                    voter_weight: self.voter_weight,
+                };
+                if writer.write_all(&DelegationV1::DISCRIMINATOR).is_err()
+                    || record.serialize(writer).is_err()
+                {
+                    return Err(ErrorCode::AccountDidNotSerialize.into());
+                }
+
+                Ok(())
+            }
+            DelegationVersion::V2 => DelegationV2 {
+                delegate: self.delegate,
+                voter_weight_record: self.voter_weight_record,
+                voter_weight: self.voter_weight,
+                rent_payer: self.rent_payer,
+                settings: self.settings,
+                signed: self.signed,
+                bump: self.bump.ok_or(ErrorCode::AccountDidNotSerialize)?,
+            }
+            .try_serialize(writer),
+        }
+    }
+}
+
+impl Owner for Delegation {
+    fn owner() -> Pubkey {
+        crate::id()
+    }
 }
 
 impl Delegation {
@@ -54,8 +200,85 @@ impl Delegation {
         ]
     }
 
+    /// Address of the record with the given seeds and bump, if the bump yields one
+    pub fn create_pda_address(seeds: &[&[u8]; 6], bump: u8) -> Option<Pubkey> {
+        let [prefix, realm, mint, owner, target, action] = *seeds;
+        Pubkey::create_program_address(
+            &[prefix, realm, mint, owner, target, action, &[bump]],
+            &crate::id(),
+        )
+        .ok()
+    }
+
+    /// Checks that bump is the canonical bump of address, so the same voter weight cannot be
+    /// delegated through several addresses
+    pub fn assert_canonical_bump(address: &Pubkey, seeds: &[&[u8]; 6], bump: u8) -> Result<()> {
+        require!(
+            Self::create_pda_address(seeds, bump) == Some(*address),
+            DelegationError::IncorrectDelegationAddress
+        );
+        require!(
+            (bump..u8::MAX)
+                .map(|lower| lower + 1)
+                .all(|higher| Self::create_pda_address(seeds, higher).is_none()),
+            DelegationError::NonCanonicalDelegationBump
+        );
+
+        Ok(())
+    }
+
+    /// Checks that address is the address of this record for the given seeds, using its stored
+    /// bump when it has one
+    pub fn assert_address(&self, address: &Pubkey, seeds: &[&[u8]; 6]) -> Result<()> {
+        let expected = match self.bump {
+            Some(bump) => Self::create_pda_address(seeds, bump),
+            None => Pubkey::try_find_program_address(seeds, &crate::id()).map(|(key, _)| key),
+        };
+        require!(
+            expected == Some(*address),
+            DelegationError::IncorrectDelegationAddress
+        );
+
+        Ok(())
+    }
+
+    /// A delegation is stale once its delegate is neither the owner of the voter weight nor
+    /// the current governance delegate of their TokenOwnerRecord
+    ///
+    /// Delegations made with a signed message do not depend on the TokenOwnerRecord, so they
+    /// are never stale and only their delegator can revoke them.
+    pub fn is_stale(&self, token_owner_record: &TokenOwnerRecordV2) -> bool {
+        !self.signed
+            && token_owner_record.governing_token_owner != self.delegate
+            && token_owner_record.governance_delegate != Some(self.delegate)
+    }
+
+    /// Takes this record out of the open delegations of the settings it is counted in, before
+    /// it is closed
+    pub fn close_in(&self, settings_info: &AccountInfo) -> Result<()> {
+        if self.version == DelegationVersion::V2 {
+            require_keys_eq!(
+                self.settings,
+                settings_info.key(),
+                DelegationError::IncorrectSettings
+            );
+            Settings::close_delegations(settings_info, 1)?;
+        }
+
+        Ok(())
+    }
+
+    /// Upgrades a V1 record, whose account must have been grown to `Delegation::size()`
+    pub fn upgrade(&mut self, rent_payer: Pubkey, settings: Pubkey, bump: u8) {
+        self.version = DelegationVersion::V2;
+        self.rent_payer = rent_payer;
+        self.settings = settings;
+        self.bump = Some(bump);
+    }
+
+    /// Size of a V2 record
     pub fn size() -> usize {
-        8 + std::mem::size_of::<Self>()
+        8 + 32 + 32 + 8 + 32 + 32 + 1 + 1
     }
 
     pub fn try_create<'a>(
@@ -64,9 +287,10 @@ impl Delegation {
         system_info: &AccountInfo<'a>,
         seeds: &[&[u8]],
         bump: &[u8],
-        data: &Self,
+        data: &DelegationV2,
     ) -> Result<()> {
-        let serialized_data = [&Self::discriminator()[..], &data.try_to_vec()?].concat();
+        let mut serialized_data = Vec::with_capacity(Self::size());
+        data.try_serialize(&mut serialized_data)?;
 
         let rent_exempt_lamports = Rent::get()?.minimum_balance(serialized_data.len()).max(1);
 
@@ -93,7 +317,7 @@ impl Delegation {
             }
 
             invoke_signed(
-                &system_instruction::allocate(account_info.key, Self::size() as u64),
+                &system_instruction::allocate(account_info.key, serialized_data.len() as u64),
                 &[account_info.clone(), system_info.clone()],
                 &[signers_seeds],
             )?;
@@ -109,7 +333,7 @@ impl Delegation {
                 payer_info.key,
                 account_info.key,
                 rent_exempt_lamports,
-                Self::size() as u64,
+                serialized_data.len() as u64,
                 &crate::id(),
             );
 
@@ -0,0 +1,214 @@
+use anchor_lang::prelude::*;
+
+use super::voter_weight_record::VoterWeightAction;
+use crate::{
+    error::DelegationError,
+    tools::{grow_account, shrink_account},
+};
+
+/// DelegationLedger records every delegation of voter weight for one target and action
+///
+/// The account holds this header followed by `entry_count` LedgerEntries sorted by
+/// delegator. It is shared by all delegates of the target, which is what allows it to
+/// detect voter weight that has already been delegated. Each entry adds `LedgerEntry::SIZE`
+/// bytes of rent, paid on insertion and returned to the entry's delegate on removal. The rent
+/// of the header is returned to the creator of the ledger once its last entry is reclaimed.
+#[account]
+#[derive(Debug)]
+pub struct DelegationLedger {
+    pub realm: Pubkey,
+    pub governing_token_mint: Pubkey,
+    pub target: Pubkey,
+    pub action: VoterWeightAction,
+    pub creator: Pubkey,
+    pub entry_count: u32,
+}
+
+/// Voter weight delegated by one delegator, as stored in a DelegationLedger
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LedgerEntry {
+    pub delegator: Pubkey,
+    pub delegate: Pubkey,
+    pub voter_weight: u64,
+}
+
+impl LedgerEntry {
+    pub const SIZE: usize = 32 + 32 + 8;
+
+    fn read(data: &[u8]) -> Self {
+        Self {
+            delegator: Pubkey::new_from_array(data[0..32].try_into().unwrap()),
+            delegate: Pubkey::new_from_array(data[32..64].try_into().unwrap()),
+            voter_weight: u64::from_le_bytes(data[64..72].try_into().unwrap()),
+        }
+    }
+
+    fn write(&self, data: &mut [u8]) {
+        data[0..32].copy_from_slice(self.delegator.as_ref());
+        data[32..64].copy_from_slice(self.delegate.as_ref());
+        data[64..72].copy_from_slice(&self.voter_weight.to_le_bytes());
+    }
+}
+
+impl DelegationLedger {
+    pub const HEADER_SIZE: usize = 8 + 32 + 32 + 32 + 1 + 32 + 4;
+
+    pub fn get_pda_address(
+        realm: &Pubkey,
+        governing_token_mint: &Pubkey,
+        target: &Pubkey,
+        action: Option<VoterWeightAction>,
+    ) -> Pubkey {
+        Pubkey::try_find_program_address(
+            &DelegationLedger::get_pda_seeds(
+                realm,
+                governing_token_mint,
+                target,
+                &borsh::to_vec(&action).unwrap(),
+            ),
+            &crate::id(),
+        )
+        .unwrap()
+        .0
+    }
+
+    pub fn get_pda_seeds<'a>(
+        realm: &'a Pubkey,
+        governing_token_mint: &'a Pubkey,
+        target: &'a Pubkey,
+        action: &'a [u8],
+    ) -> [&'a [u8]; 5] {
+        [
+            b"delegation-ledger".as_ref(),
+            realm.as_ref(),
+            governing_token_mint.as_ref(),
+            target.as_ref(),
+            action,
+        ]
+    }
+
+    fn entry_offset(index: usize) -> usize {
+        DelegationLedger::HEADER_SIZE + index * LedgerEntry::SIZE
+    }
+
+    fn data_len(&self) -> usize {
+        DelegationLedger::entry_offset(self.entry_count as usize)
+    }
+
+    /// Binary searches the ledger for the delegator's entry
+    ///
+    /// Returns the index of the entry if found, otherwise the index where it would be
+    /// inserted.
+    pub fn find_entry(
+        &self,
+        ledger_info: &AccountInfo,
+        delegator: &Pubkey,
+    ) -> std::result::Result<usize, usize> {
+        let data = ledger_info.data.borrow();
+        let (mut low, mut high) = (0, self.entry_count as usize);
+
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let offset = DelegationLedger::entry_offset(mid);
+
+            match data[offset..offset + 32].cmp(delegator.as_ref()) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => return Ok(mid),
+            }
+        }
+
+        Err(low)
+    }
+
+    pub fn get_entry(&self, ledger_info: &AccountInfo, index: usize) -> LedgerEntry {
+        let offset = DelegationLedger::entry_offset(index);
+
+        LedgerEntry::read(&ledger_info.data.borrow()[offset..offset + LedgerEntry::SIZE])
+    }
+
+    /// Inserts an entry at the index returned by `find_entry`, growing the account by one entry
+    pub fn insert_entry<'info>(
+        &mut self,
+        ledger_info: &AccountInfo<'info>,
+        payer_info: &AccountInfo<'info>,
+        system_info: &AccountInfo<'info>,
+        index: usize,
+        entry: &LedgerEntry,
+    ) -> Result<()> {
+        let end = self.data_len();
+        grow_account(
+            ledger_info,
+            payer_info,
+            system_info,
+            end + LedgerEntry::SIZE,
+        )?;
+
+        let offset = DelegationLedger::entry_offset(index);
+        let mut data = ledger_info.data.borrow_mut();
+        data.copy_within(offset..end, offset + LedgerEntry::SIZE);
+        entry.write(&mut data[offset..offset + LedgerEntry::SIZE]);
+
+        self.entry_count += 1;
+
+        Ok(())
+    }
+
+    /// Removes the entry at index, returning its rent to the entry's delegate
+    pub fn remove_entry(
+        &mut self,
+        ledger_info: &AccountInfo,
+        delegate_info: &AccountInfo,
+        index: usize,
+    ) -> Result<LedgerEntry> {
+        let entry = self.get_entry(ledger_info, index);
+        require_keys_eq!(
+            entry.delegate,
+            delegate_info.key(),
+            DelegationError::NonMatchingDelegationRecordProvided
+        );
+
+        let end = self.data_len();
+        let offset = DelegationLedger::entry_offset(index);
+        ledger_info
+            .data
+            .borrow_mut()
+            .copy_within(offset + LedgerEntry::SIZE..end, offset);
+
+        shrink_account(ledger_info, delegate_info, end - LedgerEntry::SIZE)?;
+
+        self.entry_count -= 1;
+
+        Ok(entry)
+    }
+
+    /// Removes every entry delegated to delegate, returning their rent to the delegate
+    pub fn remove_entries_of(
+        &mut self,
+        ledger_info: &AccountInfo,
+        delegate_info: &AccountInfo,
+    ) -> Result<u32> {
+        let mut kept = 0;
+
+        {
+            let mut data = ledger_info.data.borrow_mut();
+
+            for index in 0..self.entry_count as usize {
+                let offset = DelegationLedger::entry_offset(index);
+                if data[offset + 32..offset + 64] == delegate_info.key.as_ref()[..] {
+                    continue;
+                }
+
+                let kept_offset = DelegationLedger::entry_offset(kept);
+                data.copy_within(offset..offset + LedgerEntry::SIZE, kept_offset);
+                kept += 1;
+            }
+        }
+
+        let removed = self.entry_count - kept as u32;
+        self.entry_count = kept as u32;
+        shrink_account(ledger_info, delegate_info, self.data_len())?;
+
+        Ok(removed)
+    }
+}
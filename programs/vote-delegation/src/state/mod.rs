@@ -1,3 +1,8 @@
 pub mod delegation;
+pub mod delegation_ledger;
+pub mod rent_payer;
+pub mod session;
 pub mod settings;
+pub mod signed_delegation;
+pub mod vote;
 pub mod voter_weight_record;
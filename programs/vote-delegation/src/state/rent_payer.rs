@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::DelegationError, tools::close_account};
+
+/// RentPayer records which account paid the rent of a VoterWeightRecord
+///
+/// The layout of VoterWeightRecord is defined by spl-governance-addin-api, so the payer is
+/// kept in this companion account instead. Both are refunded to the payer when the record
+/// is refunded, and the companion alone when the owner reclaims the record.
+#[account]
+#[derive(Debug)]
+pub struct RentPayer {
+    pub payer: Pubkey,
+}
+
+impl RentPayer {
+    pub fn get_pda_address(record: &Pubkey) -> Pubkey {
+        Pubkey::try_find_program_address(&RentPayer::get_pda_seeds(record), &crate::id())
+            .unwrap()
+            .0
+    }
+
+    /// Closes the RentPayer of a record which is being closed, refunding whoever paid for it
+    ///
+    /// Records created before the payer was stored have no RentPayer, so there is nothing to
+    /// close. The caller checks the address of rent_payer_info.
+    pub fn try_close(rent_payer_info: &AccountInfo, payer_info: &AccountInfo) -> Result<()> {
+        if rent_payer_info.data_is_empty() {
+            return Ok(());
+        }
+
+        let rent_payer = Account::<RentPayer>::try_from(rent_payer_info)?;
+        require_keys_eq!(
+            rent_payer.payer,
+            payer_info.key(),
+            DelegationError::IncorrectRentPayer
+        );
+
+        close_account(rent_payer_info, payer_info)
+    }
+
+    pub fn get_pda_seeds(record: &Pubkey) -> [&[u8]; 2] {
+        [b"rent-payer".as_ref(), record.as_ref()]
+    }
+}
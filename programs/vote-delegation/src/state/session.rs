@@ -0,0 +1,68 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::DelegationError, state::voter_weight_record::VoterWeightAction};
+
+/// Session lets a session key sign update_voter_weight_record on behalf of a delegate
+///
+/// Delegates can keep their own key in cold storage and aggregate with a key which can only
+/// be used for a limited time, for some actions and, optionally, in a single realm.
+#[account]
+#[derive(Debug)]
+pub struct Session {
+    /// The delegate the session key signs for
+    pub delegate: Pubkey,
+
+    pub session_key: Pubkey,
+
+    /// The only realm the session can be used in, or any realm if None
+    pub realm: Option<Pubkey>,
+
+    /// Actions the session key can aggregate voter weight for
+    pub allowed_actions: Vec<VoterWeightAction>,
+
+    /// The last slot the session can be used in
+    pub expiry: u64,
+}
+
+impl Session {
+    /// Every action except RevokeVote, for which voter weight is never aggregated
+    pub const MAX_ACTIONS: usize = 5;
+
+    pub fn size() -> usize {
+        8 + 32 + 32 + (1 + 32) + (4 + Session::MAX_ACTIONS) + 8
+    }
+
+    pub fn get_pda_address(delegate: &Pubkey, session_key: &Pubkey) -> Pubkey {
+        Pubkey::try_find_program_address(
+            &Session::get_pda_seeds(delegate, session_key),
+            &crate::id(),
+        )
+        .unwrap()
+        .0
+    }
+
+    pub fn get_pda_seeds<'a>(delegate: &'a Pubkey, session_key: &'a Pubkey) -> [&'a [u8]; 3] {
+        [b"session".as_ref(), delegate.as_ref(), session_key.as_ref()]
+    }
+
+    /// Checks that signer can use this session to aggregate for action in realm
+    pub fn assert_can_sign(
+        &self,
+        signer: &Pubkey,
+        realm: &Pubkey,
+        action: VoterWeightAction,
+    ) -> Result<()> {
+        require_keys_eq!(self.session_key, *signer, DelegationError::InvalidSession);
+        require!(
+            self.expiry >= Clock::get()?.slot,
+            DelegationError::SessionExpired
+        );
+        require!(
+            self.realm.map_or(true, |scope| scope == *realm)
+                && self.allowed_actions.contains(&action),
+            DelegationError::SessionScopeExceeded
+        );
+
+        Ok(())
+    }
+}
@@ -1,11 +1,176 @@
+use std::io;
+
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
 
-#[account]
+use crate::tools::grow_account;
+
+/// Settings of the delegation middleware for one realm and governing token mint
+///
+/// Settings are stored in the layout of their version, see SettingsV1 and SettingsV2, and are
+/// written back in the version they were read in. `Settings::migrate` upgrades them.
+#[derive(Debug, Clone)]
 pub struct Settings {
+    pub version: SettingsVersion,
     pub voter_weight_source: Pubkey,
+
+    /// Where delegations of voter weight are recorded, always Accounts for V1 settings
+    pub delegation_store: DelegationStore,
+
+    /// Delegations recorded in delegation_store which have not been closed yet
+    // V1 delegation records are not part of it, see Delegation.
+    pub open_delegations: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SettingsVersion {
+    V1,
+    V2,
+}
+
+/// DelegationStore decides how update_voter_weight_record records delegated voter weight
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub enum DelegationStore {
+    /// One Delegation account per delegator, target and action
+    #[default]
+    Accounts,
+
+    /// One DelegationLedger account per target and action, shared by every delegate
+    Ledger,
+}
+
+/// Layout of the settings created before delegation stores were added
+#[derive(AnchorSerialize, AnchorDeserialize, Debug)]
+pub struct SettingsV1 {
+    pub voter_weight_source: Pubkey,
+}
+
+impl SettingsV1 {
+    /// The discriminator Anchor gave these settings, `sha256("account:Settings")[..8]`
+    pub const DISCRIMINATOR: [u8; 8] = [223, 179, 163, 190, 177, 224, 67, 173];
+}
+
+/// Layout of the settings created since delegation stores were added
+#[account]
+#[derive(Debug)]
+pub struct SettingsV2 {
+    pub voter_weight_source: Pubkey,
+    pub delegation_store: DelegationStore,
+    pub open_delegations: u64,
+}
+
+impl From<SettingsV1> for Settings {
+    fn from(settings: SettingsV1) -> Self {
+        Self {
+            version: SettingsVersion::V1,
+            voter_weight_source: settings.voter_weight_source,
+            delegation_store: DelegationStore::Accounts,
+            open_delegations: 0,
+        }
+    }
+}
+
+impl From<SettingsV2> for Settings {
+    fn from(settings: SettingsV2) -> Self {
+        Self {
+            version: SettingsVersion::V2,
+            voter_weight_source: settings.voter_weight_source,
+            delegation_store: settings.delegation_store,
+            open_delegations: settings.open_delegations,
+        }
+    }
+}
+
+impl AccountDeserialize for Settings {
+    fn try_deserialize(buf: &mut &[u8]) -> Result<Self> {
+        if buf.len() < 8 {
+            return Err(ErrorCode::AccountDiscriminatorNotFound.into());
+        }
+
+        if buf[..8] == SettingsV1::DISCRIMINATOR || buf[..8] == SettingsV2::discriminator() {
+            Self::try_deserialize_unchecked(buf)
+        } else {
+            Err(ErrorCode::AccountDiscriminatorMismatch.into())
+        }
+    }
+
+    /// Reads anything but V1 settings as V2, so that set_precursor creates V2 settings
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self> {
+        if buf[..8] == SettingsV1::DISCRIMINATOR {
+            let mut data: &[u8] = &buf[8..];
+            return SettingsV1::deserialize(&mut data)
+                .map(Settings::from)
+                .map_err(|_| ErrorCode::AccountDidNotDeserialize.into());
+        }
+
+        SettingsV2::try_deserialize_unchecked(buf).map(Settings::from)
+    }
+}
+
+impl AccountSerialize for Settings {
+    fn try_serialize<W: io::Write>(&self, writer: &mut W) -> Result<()> {
+        match self.version {
+            SettingsVersion::V1 => {
+                let settings = SettingsV1 {
+                    voter_weight_source: self.voter_weight_source,
+                };
+                if writer.write_all(&SettingsV1::DISCRIMINATOR).is_err()
+                    || settings.serialize(writer).is_err()
+                {
+                    return Err(ErrorCode::AccountDidNotSerialize.into());
+                }
+
+                Ok(())
+            }
+            SettingsVersion::V2 => SettingsV2 {
+                voter_weight_source: self.voter_weight_source,
+                delegation_store: self.delegation_store,
+                open_delegations: self.open_delegations,
+            }
+            .try_serialize(writer),
+        }
+    }
+}
+
+impl Owner for Settings {
+    fn owner() -> Pubkey {
+        crate::id()
+    }
 }
 
 impl Settings {
+    /// Size of V2 settings
+    pub fn size() -> usize {
+        8 + 32 + 1 + 8
+    }
+
+    /// Upgrades V1 settings to V2, growing their account, before delegation_store or
+    /// open_delegations are written
+    pub fn migrate<'info>(
+        settings: &mut Account<'info, Settings>,
+        payer_info: &AccountInfo<'info>,
+        system_info: &AccountInfo<'info>,
+    ) -> Result<()> {
+        if settings.version == SettingsVersion::V1 {
+            grow_account(
+                &settings.to_account_info(),
+                payer_info,
+                system_info,
+                Settings::size(),
+            )?;
+            settings.version = SettingsVersion::V2;
+        }
+
+        Ok(())
+    }
+
+    /// Records that `count` of the delegations in open_delegations were closed
+    pub fn close_delegations(settings_info: &AccountInfo, count: u64) -> Result<()> {
+        let mut settings = Account::<Settings>::try_from(settings_info)?;
+        settings.open_delegations = settings.open_delegations.checked_sub(count).unwrap();
+        settings.exit(&crate::id())
+    }
+
     pub fn get_pda_address(realm: &Pubkey, governing_token_mint: &Pubkey) -> Pubkey {
         Pubkey::try_find_program_address(
             &Settings::get_pda_seeds(realm, governing_token_mint),
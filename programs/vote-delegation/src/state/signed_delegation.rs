@@ -0,0 +1,92 @@
+use anchor_lang::prelude::*;
+
+use crate::state::voter_weight_record::VoterWeightAction;
+
+/// SignedDelegation records voter weight delegated with an ed25519-signed message
+///
+/// It lets delegators without SOL delegate: they sign a DelegationMessage off-chain, and any
+/// relayer submits it with `delegate_with_signature`. The record takes the place of the
+/// delegator's TokenOwnerRecord when their voter weight is aggregated.
+#[account]
+#[derive(Debug)]
+pub struct SignedDelegation {
+    pub realm: Pubkey,
+    pub governing_token_mint: Pubkey,
+    pub delegator: Pubkey,
+
+    /// None once the delegator has ended the delegation
+    pub delegate: Option<Pubkey>,
+
+    /// The only action the voter weight can be aggregated for, or any action if None
+    pub scope: Option<VoterWeightAction>,
+
+    /// The last slot the voter weight can be aggregated in
+    pub expiry: u64,
+
+    /// The nonce the next message must carry, so that messages cannot be replayed
+    pub next_nonce: u64,
+}
+
+/// The message a delegator signs to update their SignedDelegation
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, PartialEq)]
+pub struct DelegationMessage {
+    pub realm: Pubkey,
+    pub governing_token_mint: Pubkey,
+    /// None ends the delegation
+    pub delegate: Option<Pubkey>,
+    pub scope: Option<VoterWeightAction>,
+    pub expiry: u64,
+    pub nonce: u64,
+}
+
+impl DelegationMessage {
+    /// The bytes signed by the delegator
+    // The program id is included so that a message cannot be replayed on another deployment.
+    pub fn to_signed_bytes(&self) -> Vec<u8> {
+        [
+            b"vote-delegation:delegate".as_ref(),
+            crate::id().as_ref(),
+            &self.try_to_vec().unwrap(),
+        ]
+        .concat()
+    }
+}
+
+impl SignedDelegation {
+    pub fn size() -> usize {
+        8 + 32 + 32 + 32 + (1 + 32) + (1 + 1) + 8 + 8
+    }
+
+    pub fn get_pda_address(
+        realm: &Pubkey,
+        governing_token_mint: &Pubkey,
+        delegator: &Pubkey,
+    ) -> Pubkey {
+        Pubkey::try_find_program_address(
+            &SignedDelegation::get_pda_seeds(realm, governing_token_mint, delegator),
+            &crate::id(),
+        )
+        .unwrap()
+        .0
+    }
+
+    pub fn get_pda_seeds<'a>(
+        realm: &'a Pubkey,
+        governing_token_mint: &'a Pubkey,
+        delegator: &'a Pubkey,
+    ) -> [&'a [u8]; 4] {
+        [
+            b"signed-delegation".as_ref(),
+            realm.as_ref(),
+            governing_token_mint.as_ref(),
+            delegator.as_ref(),
+        ]
+    }
+
+    /// Whether the voter weight can currently be aggregated by delegate for action
+    pub fn delegates_to(&self, delegate: &Pubkey, action: VoterWeightAction, slot: u64) -> bool {
+        self.delegate == Some(*delegate)
+            && self.scope.map_or(true, |scope| scope == action)
+            && self.expiry >= slot
+    }
+}
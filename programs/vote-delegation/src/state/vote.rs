@@ -0,0 +1,85 @@
+use anchor_lang::prelude::*;
+use spl_governance::state::{proposal, vote_record};
+
+/// VoteChoice struct as defined in spl-governance
+/// It's redefined here for Anchor to export it to IDL
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, PartialEq)]
+pub struct VoteChoice {
+    /// The rank given to the choice by voter
+    /// Note: The field is not used in the current version
+    pub rank: u8,
+
+    /// The voter's weight percentage given by the voter to the choice
+    pub weight_percentage: u8,
+}
+
+/// Vote enum as defined in spl-governance
+/// It's redefined here for Anchor to export it to IDL
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, PartialEq)]
+pub enum Vote {
+    /// Vote approving choices
+    Approve(Vec<VoteChoice>),
+
+    /// Vote rejecting proposal
+    Deny,
+
+    /// Declare indifference to proposal
+    Abstain,
+
+    /// Veto proposal
+    Veto,
+}
+
+impl From<VoteChoice> for vote_record::VoteChoice {
+    fn from(choice: VoteChoice) -> Self {
+        vote_record::VoteChoice {
+            rank: choice.rank,
+            weight_percentage: choice.weight_percentage,
+        }
+    }
+}
+
+impl From<Vote> for vote_record::Vote {
+    fn from(vote: Vote) -> Self {
+        match vote {
+            Vote::Approve(choices) => {
+                vote_record::Vote::Approve(choices.into_iter().map(Into::into).collect())
+            }
+            Vote::Deny => vote_record::Vote::Deny,
+            Vote::Abstain => vote_record::Vote::Abstain,
+            Vote::Veto => vote_record::Vote::Veto,
+        }
+    }
+}
+
+/// VoteType enum as defined in spl-governance
+/// It's redefined here for Anchor to export it to IDL
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, PartialEq)]
+pub enum VoteType {
+    /// Single choice vote with mutually exclusive choices
+    SingleChoice,
+
+    /// Multiple options can be selected with up to max_voter_options per voter
+    MultiChoice {
+        /// The max number of options a voter can choose
+        max_voter_options: u8,
+
+        /// The max number of wining options
+        max_winning_options: u8,
+    },
+}
+
+impl From<VoteType> for proposal::VoteType {
+    fn from(vote_type: VoteType) -> Self {
+        match vote_type {
+            VoteType::SingleChoice => proposal::VoteType::SingleChoice,
+            VoteType::MultiChoice {
+                max_voter_options,
+                max_winning_options,
+            } => proposal::VoteType::MultiChoice {
+                max_voter_options,
+                max_winning_options,
+            },
+        }
+    }
+}
@@ -1,7 +1,8 @@
 use anchor_lang::{prelude::*, solana_program::program_pack::IsInitialized};
 use num_derive::FromPrimitive;
 use spl_governance::state::{
-    enums::ProposalState, governance::get_governance_data, proposal::get_proposal_data,
+    enums::ProposalState, governance::get_governance_data_for_realm, proposal::get_proposal_data,
+    realm::get_realm_data, vote_record::get_vote_record_data,
 };
 
 use crate::error::DelegationError;
@@ -147,6 +148,51 @@ impl VoterWeightRecord {
         ]
     }
 
+    /// Checks that target is the target this record was created for, and that the record
+    /// stored at address can be reclaimed
+    // The address is checked rather than weight_action_target, as the action and target
+    // stored in the record can be changed by update_voter_weight_record.
+    pub fn assert_can_reclaim_target(
+        &self,
+        address: &Pubkey,
+        target: &AccountInfo,
+        governance_program_id: &Pubkey,
+    ) -> Result<()> {
+        require_keys_eq!(
+            VoterWeightRecord::get_pda_address(
+                &self.realm,
+                &self.governing_token_mint,
+                &self.governing_token_owner,
+                target.key,
+                self.weight_action,
+            ),
+            *address,
+            DelegationError::IncorrectTarget
+        );
+        require_keys_eq!(
+            *target.owner,
+            *governance_program_id,
+            ErrorCode::ConstraintOwner
+        );
+
+        self.assert_can_reclaim(target, governance_program_id)
+    }
+
+    /// Checks that the record can be reclaimed, given the current state of its target
+    ///
+    /// - CastVote and CommentProposal: once the proposal is no longer in Draft, SigningOff or
+    /// Voting.
+    /// - SignOffProposal: once the proposal can no longer be signed off.
+    /// - CreateGovernance and CreateProposal: once the record has expired, as spl-governance
+    /// only accepts unexpired records for these actions and any number of governances or
+    /// proposals can be created while it is valid.
+    /// - RevokeVote: once the record has expired or the vote record has been relinquished.
+    ///
+    /// Records whose target is not of the expected type can never be used, and can be
+    /// reclaimed at any time.
+    ///
+    /// Targets in their V1 layouts are recognised too, as the spl-governance getters convert
+    /// legacy proposals, governances and vote records to their current versions.
     pub fn assert_can_reclaim(
         &self,
         target: &AccountInfo,
@@ -172,15 +218,42 @@ impl VoterWeightRecord {
                 }
             }
             VoterWeightAction::CreateGovernance => {
-                let governance = get_governance_data(governance_program_id, target);
-                require!(governance.is_ok(), DelegationError::ReclaimTargetWrongState);
+                if *target.key == self.realm
+                    && get_realm_data(governance_program_id, target).is_ok()
+                {
+                    require!(
+                        self.has_expired()?,
+                        DelegationError::ReclaimTargetWrongState
+                    );
+                }
+            }
+            VoterWeightAction::CreateProposal => {
+                if get_governance_data_for_realm(governance_program_id, target, &self.realm).is_ok()
+                {
+                    require!(
+                        self.has_expired()?,
+                        DelegationError::ReclaimTargetWrongState
+                    );
+                }
+            }
+            VoterWeightAction::RevokeVote => {
+                if let Ok(vote_record) = get_vote_record_data(governance_program_id, target) {
+                    require!(
+                        vote_record.is_relinquished || self.has_expired()?,
+                        DelegationError::ReclaimTargetWrongState
+                    );
+                }
             }
-            VoterWeightAction::CreateProposal => {}
-            VoterWeightAction::RevokeVote => {}
         };
 
         Ok(())
     }
+
+    /// Whether spl-governance would reject the record as expired
+    fn has_expired(&self) -> Result<bool> {
+        let slot = Clock::get()?.slot;
+        Ok(matches!(self.voter_weight_expiry, Some(expiry) if expiry < slot))
+    }
 }
 
 impl Default for VoterWeightRecord {
@@ -1,4 +1,32 @@
-use anchor_lang::prelude::*;
+#[cfg(not(feature = "upstream-governance"))]
+use anchor_lang::solana_program::{instruction::Instruction, program::invoke_signed};
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        ed25519_program,
+        program::invoke,
+        system_instruction, system_program,
+        sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+    },
+};
+
+use spl_governance::{
+    error::GovernanceError,
+    state::{
+        governance::get_governance_data_for_realm,
+        proposal::get_proposal_data_for_governance,
+        realm::get_realm_data_for_governing_token_mint,
+        token_owner_record::get_token_owner_record_data_for_realm_and_governing_mint,
+        vote_record::{
+            get_vote_record_address, get_vote_record_data_for_proposal_and_token_owner_record,
+        },
+    },
+};
+
+use crate::{
+    error::DelegationError,
+    state::voter_weight_record::{VoterWeightAction, VoterWeightRecord},
+};
 
 /// Disposes account by transferring its lamports to the beneficiary account and zeros its data
 // After transaction completes the runtime would remove the account with no lamports
@@ -15,3 +43,293 @@ pub fn dispose_account(account_info: &AccountInfo, beneficiary_info: &AccountInf
 
     account_data.fill(0);
 }
+
+/// Disposes account and hands it back to the system program
+// Unlike dispose_account this allows the address to be created again within the same transaction
+pub fn close_account(account_info: &AccountInfo, beneficiary_info: &AccountInfo) -> Result<()> {
+    dispose_account(account_info, beneficiary_info);
+
+    account_info.realloc(0, false)?;
+    account_info.assign(&system_program::ID);
+
+    Ok(())
+}
+
+/// The Token-2022 program, which anchor-spl 0.25 does not know about
+pub mod spl_token_2022 {
+    anchor_lang::declare_id!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+}
+
+/// Returns true if an account with this owner can be a governing token mint
+// spl-governance checks the mint address against the realm, so the owner is all that is left to
+// check. Token-2022 mints share the base Mint layout, whatever extensions follow it.
+pub fn is_token_mint_owner(owner: &Pubkey) -> bool {
+    *owner == anchor_spl::token::ID || *owner == spl_token_2022::ID
+}
+
+/// Grows account data to new_len, topping up its rent exemption from the payer
+pub fn grow_account<'info>(
+    account_info: &AccountInfo<'info>,
+    payer_info: &AccountInfo<'info>,
+    system_info: &AccountInfo<'info>,
+    new_len: usize,
+) -> Result<()> {
+    let top_up_lamports = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(account_info.lamports());
+
+    if top_up_lamports > 0 {
+        invoke(
+            &system_instruction::transfer(payer_info.key, account_info.key, top_up_lamports),
+            &[
+                payer_info.clone(),
+                account_info.clone(),
+                system_info.clone(),
+            ],
+        )?;
+    }
+
+    account_info.realloc(new_len, false)?;
+
+    Ok(())
+}
+
+/// Shrinks account data to new_len and transfers the rent no longer needed to the beneficiary
+pub fn shrink_account(
+    account_info: &AccountInfo,
+    beneficiary_info: &AccountInfo,
+    new_len: usize,
+) -> Result<()> {
+    account_info.realloc(new_len, false)?;
+
+    let excess_lamports = account_info
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(new_len));
+
+    **account_info.lamports.borrow_mut() -= excess_lamports;
+    **beneficiary_info.lamports.borrow_mut() = beneficiary_info
+        .lamports()
+        .checked_add(excess_lamports)
+        .unwrap();
+
+    Ok(())
+}
+
+/// Checks that the instruction before the current one verifies the signature of signer over
+/// message with the ed25519 program
+// The precompile only fails the transaction on invalid signatures, so the signer and message
+// it verified have to be read back from its instruction data.
+pub fn assert_ed25519_signature(
+    instructions_info: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> Result<()> {
+    let current_index = load_current_index_checked(instructions_info)?;
+    require!(
+        current_index > 0,
+        DelegationError::MissingSignatureVerification
+    );
+    let instruction = load_instruction_at_checked(current_index as usize - 1, instructions_info)?;
+    require_keys_eq!(
+        instruction.program_id,
+        ed25519_program::ID,
+        DelegationError::MissingSignatureVerification
+    );
+
+    // One signature: a count and padding byte, then offsets of the signature, key and message.
+    let data = &instruction.data;
+    require!(
+        data.len() >= 16 && data[0] == 1,
+        DelegationError::InvalidSignatureVerification
+    );
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]) as usize;
+    let in_same_instruction = [4, 8, 14]
+        .iter()
+        .all(|&at| read_u16(at) == u16::MAX as usize);
+    let public_key_offset = read_u16(6);
+    let message_offset = read_u16(10);
+    let message_size = read_u16(12);
+
+    require!(
+        in_same_instruction
+            && data.get(public_key_offset..public_key_offset + 32) == Some(signer.as_ref())
+            && data.get(message_offset..message_offset + message_size) == Some(message),
+        DelegationError::InvalidSignatureVerification
+    );
+
+    Ok(())
+}
+
+/// Accounts needed to take revoked voter weight out of a vote cast by a delegate
+///
+/// The amount relinquished is read by spl-governance from `revoke_weight_record`, which
+/// must already hold a RevokeVote record targeting `vote_record`.
+#[cfg_attr(feature = "upstream-governance", allow(dead_code))]
+pub struct RelinquishAccounts<'a, 'info> {
+    pub governance_program_id: &'a AccountInfo<'info>,
+    pub realm: &'a AccountInfo<'info>,
+    pub realm_config: &'a AccountInfo<'info>,
+    pub governance: &'a AccountInfo<'info>,
+    pub proposal: &'a AccountInfo<'info>,
+    pub delegate_token_owner_record: &'a AccountInfo<'info>,
+    pub vote_record: &'a AccountInfo<'info>,
+    pub governing_token_mint: &'a AccountInfo<'info>,
+    pub governance_authority: &'a AccountInfo<'info>,
+    pub beneficiary: &'a AccountInfo<'info>,
+    pub revoke_weight_record: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> RelinquishAccounts<'a, 'info> {
+    /// Checks the realm, governance, proposal and delegate Token Owner Record belong together,
+    /// and so does the vote record if the delegate has voted. Returns whether they have voted.
+    ///
+    /// The proposal must be for governing_token_mint, unless that is the council mint and the
+    /// proposal is a community proposal, which council members can veto.
+    pub fn load_vote(&self) -> Result<bool> {
+        let realm_data = get_realm_data_for_governing_token_mint(
+            self.governance_program_id.key,
+            self.realm,
+            self.governing_token_mint.key,
+        )?;
+        get_governance_data_for_realm(
+            self.governance_program_id.key,
+            self.governance,
+            self.realm.key,
+        )?;
+        let proposal_data = get_proposal_data_for_governance(
+            self.governance_program_id.key,
+            self.proposal,
+            self.governance.key,
+        )?;
+        let is_veto = realm_data.config.council_mint == Some(self.governing_token_mint.key())
+            && proposal_data.governing_token_mint == realm_data.community_mint;
+        if proposal_data.governing_token_mint != self.governing_token_mint.key() && !is_veto {
+            return Err(
+                ProgramError::from(GovernanceError::InvalidGoverningMintForProposal).into(),
+            );
+        }
+        let delegate_token_owner_record_data =
+            get_token_owner_record_data_for_realm_and_governing_mint(
+                self.governance_program_id.key,
+                self.delegate_token_owner_record,
+                self.realm.key,
+                self.governing_token_mint.key,
+            )?;
+
+        // An empty account only shows the delegate has not voted if it is their vote record.
+        require_keys_eq!(
+            self.vote_record.key(),
+            get_vote_record_address(
+                self.governance_program_id.key,
+                self.proposal.key,
+                self.delegate_token_owner_record.key,
+            ),
+            DelegationError::IncorrectVoteRecord
+        );
+
+        // We only need to unvote if a vote has actually been cast.
+        if self.vote_record.data_is_empty() {
+            return Ok(false);
+        }
+
+        require_keys_eq!(
+            *self.vote_record.owner,
+            self.governance_program_id.key(),
+            ErrorCode::ConstraintOwner
+        );
+        get_vote_record_data_for_proposal_and_token_owner_record(
+            self.governance_program_id.key,
+            self.vote_record,
+            &realm_data,
+            self.proposal.key,
+            &proposal_data,
+            &delegate_token_owner_record_data,
+        )?;
+
+        Ok(true)
+    }
+
+    /// Takes voter_weight delegated by governing_token_owner out of the vote cast by the
+    /// delegate
+    pub fn relinquish_weight(
+        &self,
+        governing_token_owner: &Pubkey,
+        voter_weight: u64,
+    ) -> Result<()> {
+        self.relinquish_weight_signed(governing_token_owner, voter_weight, &[])
+    }
+
+    /// Like relinquish_weight, with governance_authority being a PDA of this program signed
+    /// for with signer_seeds
+    pub fn relinquish_weight_signed(
+        &self,
+        governing_token_owner: &Pubkey,
+        voter_weight: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        {
+            let mut data = self.revoke_weight_record.try_borrow_mut_data()?;
+            VoterWeightRecord {
+                realm: self.realm.key(),
+                governing_token_mint: self.governing_token_mint.key(),
+                governing_token_owner: *governing_token_owner,
+                voter_weight,
+                voter_weight_expiry: None,
+                weight_action: Some(VoterWeightAction::RevokeVote),
+                weight_action_target: Some(self.vote_record.key()),
+                reserved: Default::default(),
+            }
+            .try_serialize(&mut &mut data[..])?;
+        }
+
+        self.relinquish_signed(signer_seeds)
+    }
+
+    pub fn relinquish(&self) -> Result<()> {
+        self.relinquish_signed(&[])
+    }
+
+    #[cfg(not(feature = "upstream-governance"))]
+    pub fn get_relinquish_instruction(&self) -> Instruction {
+        spl_governance::instruction::relinquish_vote(
+            self.governance_program_id.key,
+            self.realm.key,
+            self.governance.key,
+            self.proposal.key,
+            self.delegate_token_owner_record.key,
+            self.governing_token_mint.key,
+            Some(*self.governance_authority.key),
+            Some(*self.beneficiary.key),
+            Some(*self.revoke_weight_record.key),
+        )
+    }
+
+    #[cfg(not(feature = "upstream-governance"))]
+    pub fn relinquish_signed(&self, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+        invoke_signed(
+            &self.get_relinquish_instruction(),
+            &[
+                self.realm.clone(),
+                self.governance.clone(),
+                self.proposal.clone(),
+                self.delegate_token_owner_record.clone(),
+                self.vote_record.clone(),
+                self.governing_token_mint.clone(),
+                self.governance_authority.clone(),
+                self.beneficiary.clone(),
+                self.revoke_weight_record.clone(),
+                self.realm_config.clone(),
+            ],
+            signer_seeds,
+        )?;
+
+        Ok(())
+    }
+
+    /// Upstream spl-governance can only relinquish a whole vote, so revoked voter weight
+    /// cannot be taken out of a cast vote.
+    #[cfg(feature = "upstream-governance")]
+    pub fn relinquish_signed(&self, _signer_seeds: &[&[&[u8]]]) -> Result<()> {
+        err!(DelegationError::CannotRevokeFromCastVote)
+    }
+}
@@ -5,11 +5,17 @@ use solana_program::instruction::Instruction;
 use solana_program_test::{processor, ProgramTest};
 use solana_sdk::{signature::Keypair, signer::Signer, transport::TransportError};
 use spl_governance::state::{
-    realm_config::get_realm_config_address, vote_record::get_vote_record_address,
+    proposal::get_proposal_address, realm_config::get_realm_config_address,
+    token_owner_record::get_token_owner_record_address, vote_record::get_vote_record_address,
 };
 use vote_delegation::state::{
-    delegation::Delegation,
-    settings::Settings,
+    delegation::{Delegation, DelegationVersion},
+    delegation_ledger::DelegationLedger,
+    rent_payer::RentPayer,
+    session::Session,
+    settings::{DelegationStore, Settings, SettingsVersion},
+    signed_delegation::{DelegationMessage, SignedDelegation},
+    vote::{Vote, VoteType},
     voter_weight_record::{VoterWeightAction, VoterWeightRecord},
 };
 
@@ -27,6 +33,7 @@ pub struct DelegationTest {
 
 pub struct VoterWeightRecordCookie {
     pub address: Pubkey,
+    pub governing_token_mint: Pubkey,
     pub owner: Pubkey,
     pub action: VoterWeightAction,
     pub target: Pubkey,
@@ -52,10 +59,16 @@ impl DelegationTest {
     }
 
     pub async fn start_new() -> Self {
+        Self::start_with_programs(|_| {}).await
+    }
+
+    /// Starts a new test with additional programs, such as ones acting as delegates
+    pub async fn start_with_programs<F: FnOnce(&mut ProgramTest)>(add_programs: F) -> Self {
         let mut program_test = ProgramTest::default();
 
         DelegationTest::add_program(&mut program_test);
         GovernanceTest::add_program(&mut program_test);
+        add_programs(&mut program_test);
 
         let program_id = vote_delegation::id();
         let bench_rc = Arc::new(ProgramTestBench::start_new(program_test).await);
@@ -92,6 +105,7 @@ impl DelegationTest {
     ) -> Result<VoterWeightRecordCookie, TransportError> {
         let vwr_cookie = VoterWeightRecordCookie {
             address: Keypair::new().pubkey(),
+            governing_token_mint: realm.account.community_mint,
             owner: wallet.address,
             action,
             target,
@@ -131,12 +145,73 @@ impl DelegationTest {
             .governance
             .with_token_owner_record(realm, &wallet)
             .await?;
+
+        self.delegate_token_owner_record(
+            realm,
+            predecessor,
+            wallet,
+            token_owner_record,
+            delegate,
+            weight,
+            expiry,
+            action,
+            target,
+        )
+        .await
+    }
+
+    /// Like with_delegator, for a council member delegating their council voter weight
+    #[allow(dead_code)]
+    pub async fn with_council_delegator(
+        &mut self,
+        realm: &RealmCookie,
+        predecessor: &PrecursorProgramCookie,
+        delegate: Pubkey,
+        weight: u64,
+        expiry: Option<u64>,
+        action: VoterWeightAction,
+        target: Pubkey,
+    ) -> Result<DelegatorCookie, TransportError> {
+        let wallet = self.bench.with_wallet().await;
+        let token_owner_record = self
+            .governance
+            .with_council_token_owner_record(realm, &wallet, weight)
+            .await?;
+
+        self.delegate_token_owner_record(
+            realm,
+            predecessor,
+            wallet,
+            token_owner_record,
+            delegate,
+            weight,
+            expiry,
+            action,
+            target,
+        )
+        .await
+    }
+
+    async fn delegate_token_owner_record(
+        &mut self,
+        realm: &RealmCookie,
+        predecessor: &PrecursorProgramCookie,
+        wallet: WalletCookie,
+        token_owner_record: TokenOwnerRecordCookie,
+        delegate: Pubkey,
+        weight: u64,
+        expiry: Option<u64>,
+        action: VoterWeightAction,
+        target: Pubkey,
+    ) -> Result<DelegatorCookie, TransportError> {
         self.governance
             .set_delegate(&wallet, &token_owner_record, Some(delegate))
             .await?;
 
+        let governing_token_mint = token_owner_record.account.governing_token_mint;
         let vwr_cookie = VoterWeightRecordCookie {
             address: Keypair::new().pubkey(),
+            governing_token_mint,
             owner: wallet.address,
             action,
             target,
@@ -145,7 +220,7 @@ impl DelegationTest {
             .set_anchor_account(
                 &VoterWeightRecord {
                     realm: realm.address,
-                    governing_token_mint: realm.account.community_mint,
+                    governing_token_mint,
                     governing_token_owner: wallet.address,
                     voter_weight: weight,
                     voter_weight_expiry: expiry,
@@ -165,9 +240,45 @@ impl DelegationTest {
         })
     }
 
+    #[allow(dead_code)]
+    pub async fn set_source_voter_weight(
+        &mut self,
+        realm: &RealmCookie,
+        predecessor: &PrecursorProgramCookie,
+        delegator: &DelegatorCookie,
+        weight: u64,
+    ) -> Result<(), TransportError> {
+        self.bench
+            .set_anchor_account(
+                &VoterWeightRecord {
+                    realm: realm.address,
+                    governing_token_mint: realm.account.community_mint,
+                    governing_token_owner: delegator.wallet.address,
+                    voter_weight: weight,
+                    voter_weight_expiry: None,
+                    weight_action: Some(delegator.source_vwr.action),
+                    weight_action_target: Some(delegator.source_vwr.target),
+                    reserved: Default::default(),
+                },
+                delegator.source_vwr.address,
+                predecessor.address,
+            )
+            .await
+    }
+
     pub async fn with_precursor_program(
         &mut self,
         realm: &RealmCookie,
+    ) -> Result<PrecursorProgramCookie, TransportError> {
+        self.with_precursor_program_for_mint(realm, &realm.account.community_mint)
+            .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn with_precursor_program_for_mint(
+        &mut self,
+        realm: &RealmCookie,
+        governing_token_mint: &Pubkey,
     ) -> Result<PrecursorProgramCookie, TransportError> {
         let cookie = PrecursorProgramCookie {
             address: Keypair::new().pubkey(),
@@ -179,7 +290,7 @@ impl DelegationTest {
 
         let data =
             anchor_lang::InstructionData::data(&vote_delegation::instruction::SetPrecursor {
-                mint: realm.community_mint_cookie.address,
+                mint: *governing_token_mint,
                 voter_weight_source: cookie.address,
             });
 
@@ -187,7 +298,7 @@ impl DelegationTest {
             &vote_delegation::accounts::SetPrecursor {
                 signer: realm.realm_authority.pubkey(),
                 payer: self.bench.payer.pubkey(),
-                settings: Settings::get_pda_address(&realm.address, &realm.account.community_mint),
+                settings: Settings::get_pda_address(&realm.address, governing_token_mint),
                 governance_program_id: self.governance.program_id,
                 realm_info: realm.address,
                 system_program: solana_sdk::system_program::id(),
@@ -208,12 +319,97 @@ impl DelegationTest {
         Ok(cookie)
     }
 
+    #[allow(dead_code)]
+    pub async fn with_revoke_vote_record(
+        &mut self,
+        realm: &RealmCookie,
+        owner: &WalletCookie,
+        vote_record: Pubkey,
+    ) -> Result<VoterWeightRecordCookie, TransportError> {
+        let vwr_cookie = VoterWeightRecordCookie {
+            address: VoterWeightRecord::get_pda_address(
+                &realm.address,
+                &realm.community_mint_cookie.address,
+                &owner.address,
+                &vote_record,
+                Some(VoterWeightAction::RevokeVote),
+            ),
+            governing_token_mint: realm.account.community_mint,
+            owner: owner.address,
+            action: VoterWeightAction::RevokeVote,
+            target: vote_record,
+        };
+        self.bench
+            .set_anchor_account(
+                &VoterWeightRecord {
+                    realm: realm.address,
+                    governing_token_mint: realm.account.community_mint,
+                    governing_token_owner: owner.address,
+                    voter_weight: 10,
+                    voter_weight_expiry: Some(u64::max_value()),
+                    weight_action: Some(VoterWeightAction::RevokeVote),
+                    weight_action_target: Some(vote_record),
+                    reserved: Default::default(),
+                },
+                vwr_cookie.address,
+                vote_delegation::id(),
+            )
+            .await?;
+
+        Ok(vwr_cookie)
+    }
+
+    /// Rewrites a delegation record as a V1 record, which lacks its rent payer and settings
+    #[allow(dead_code)]
+    pub async fn downgrade_delegation_record(
+        &mut self,
+        delegation_record: Pubkey,
+    ) -> Result<(), TransportError> {
+        let mut record = self
+            .bench
+            .get_anchor_account::<Delegation>(delegation_record)
+            .await;
+        record.version = DelegationVersion::V1;
+        self.bench
+            .set_anchor_account(&record, delegation_record, vote_delegation::id())
+            .await
+    }
+
+    /// Rewrites settings as V1 settings, which lack a delegation store
+    #[allow(dead_code)]
+    pub async fn downgrade_settings(&mut self, settings: Pubkey) -> Result<(), TransportError> {
+        let mut record = self.bench.get_anchor_account::<Settings>(settings).await;
+        record.version = SettingsVersion::V1;
+        self.bench
+            .set_anchor_account(&record, settings, vote_delegation::id())
+            .await
+    }
+
     pub async fn with_vwr(
         &mut self,
         realm: &RealmCookie,
         owner: &WalletCookie,
         target: Pubkey,
         action: VoterWeightAction,
+    ) -> Result<VoterWeightRecordCookie, TransportError> {
+        self.with_vwr_for_mint(
+            realm,
+            owner,
+            &realm.community_mint_cookie.address,
+            target,
+            action,
+        )
+        .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn with_vwr_for_mint(
+        &mut self,
+        realm: &RealmCookie,
+        owner: &WalletCookie,
+        governing_token_mint: &Pubkey,
+        target: Pubkey,
+        action: VoterWeightAction,
     ) -> Result<VoterWeightRecordCookie, TransportError> {
         let data = anchor_lang::InstructionData::data(
             &vote_delegation::instruction::CreateVoterWeightRecord {
@@ -225,7 +421,7 @@ impl DelegationTest {
 
         let address = VoterWeightRecord::get_pda_address(
             &realm.address,
-            &realm.community_mint_cookie.address,
+            governing_token_mint,
             &owner.address,
             &target,
             Some(action),
@@ -234,9 +430,10 @@ impl DelegationTest {
             &vote_delegation::accounts::CreateVoterWeightRecord {
                 payer: self.bench.payer.pubkey(),
                 voter_weight_record: address,
+                rent_payer: RentPayer::get_pda_address(&address),
                 governance_program_id: self.governance.program_id,
                 realm: realm.address,
-                realm_governing_token_mint: realm.community_mint_cookie.address,
+                realm_governing_token_mint: *governing_token_mint,
                 system_program: solana_sdk::system_program::id(),
             },
             None,
@@ -256,6 +453,7 @@ impl DelegationTest {
 
         Ok(VoterWeightRecordCookie {
             address,
+            governing_token_mint: *governing_token_mint,
             action,
             target,
             owner: owner.address,
@@ -299,7 +497,7 @@ impl DelegationTest {
                 payer: self.bench.payer.pubkey(),
                 revoke_weight_record: VoterWeightRecord::get_revocation_address(
                     &realm.address,
-                    &realm.community_mint_cookie.address,
+                    &to_revoke.governing_token_mint,
                     &delegator.wallet.address,
                     &to_revoke.target,
                     Some(to_revoke.action),
@@ -307,7 +505,7 @@ impl DelegationTest {
                 delegate: to_revoke.owner,
                 delegation_record: Delegation::get_pda_address(
                     &realm.address,
-                    &realm.community_mint_cookie.address,
+                    &to_revoke.governing_token_mint,
                     &delegator.wallet.address,
                     &to_revoke.target,
                     Some(to_revoke.action),
@@ -327,9 +525,14 @@ impl DelegationTest {
                 governance_info: proposal.account.governance,
                 proposal_info: proposal.address,
                 delegate_token_owner_record_info: to_revoke_token_owner_record.address,
-                realm_governing_token_mint: realm.community_mint_cookie.address,
+                realm_governing_token_mint: to_revoke.governing_token_mint,
                 governing_token_owner: delegator.wallet.address,
                 system_program: solana_sdk::system_program::id(),
+                settings: Settings::get_pda_address(
+                    &realm.address,
+                    &to_revoke.governing_token_mint,
+                ),
+                rent_payer: self.bench.payer.pubkey(),
             },
             None,
         );
@@ -352,174 +555,1336 @@ impl DelegationTest {
         Ok(())
     }
 
-    pub async fn aggregate_delegation(
-        &mut self,
-        realm: &RealmCookie,
-        owner: &WalletCookie,
-        vwr: &VoterWeightRecordCookie,
-        delegator_accounts: &[&DelegatorCookie],
-    ) -> Result<(), TransportError> {
-        self.aggregate_delegation_using_ix(realm, owner, vwr, delegator_accounts, NopOverride)
-            .await
-    }
-
-    pub async fn aggregate_delegation_using_ix<F: Fn(&mut Instruction)>(
+    #[allow(dead_code)]
+    pub async fn revoke_partial(
         &mut self,
         realm: &RealmCookie,
-        owner: &WalletCookie,
-        vwr: &VoterWeightRecordCookie,
-        delegator_accounts: &[&DelegatorCookie],
-        instruction_override: F,
+        delegator: &DelegatorCookie,
+        to_revoke: &VoterWeightRecordCookie,
+        proposal: &ProposalCookie,
+        to_revoke_token_owner_record: &TokenOwnerRecordCookie,
+        amount: u64,
     ) -> Result<(), TransportError> {
-        let data = anchor_lang::InstructionData::data(
-            &vote_delegation::instruction::UpdateVoterWeightRecord {
-                voter_weight_action: vwr.action,
-                target: Some(vwr.target),
-            },
-        );
+        let data =
+            anchor_lang::InstructionData::data(&vote_delegation::instruction::RevokePartial {
+                amount,
+            });
 
-        let mut accounts = anchor_lang::ToAccountMetas::to_account_metas(
-            &vote_delegation::accounts::UpdateVoterWeightRecord {
-                delegate: vwr.owner,
+        let accounts = anchor_lang::ToAccountMetas::to_account_metas(
+            &vote_delegation::accounts::RevokePartial {
                 payer: self.bench.payer.pubkey(),
-                settings: Settings::get_pda_address(
+                revoke_weight_record: VoterWeightRecord::get_revocation_address(
                     &realm.address,
                     &realm.community_mint_cookie.address,
+                    &delegator.wallet.address,
+                    &to_revoke.target,
+                    Some(to_revoke.action),
                 ),
-                voter_weight_record: vwr.address,
-                system_program: solana_sdk::system_program::id(),
-                governance_program_id: self.governance.program_id,
-                realm: realm.address,
-            },
-            None,
-        );
-
-        for delegator in delegator_accounts {
-            accounts.push(AccountMeta {
-                pubkey: delegator.source_vwr.address,
-                is_signer: false,
-                is_writable: false,
-            });
-            accounts.push(AccountMeta {
-                pubkey: delegator.token_owner_record.address,
-                is_signer: false,
-                is_writable: false,
-            });
-            accounts.push(AccountMeta {
-                pubkey: Delegation::get_pda_address(
+                delegate: to_revoke.owner,
+                delegation_record: Delegation::get_pda_address(
                     &realm.address,
                     &realm.community_mint_cookie.address,
                     &delegator.wallet.address,
-                    &delegator.source_vwr.target,
-                    Some(delegator.source_vwr.action),
+                    &to_revoke.target,
+                    Some(to_revoke.action),
                 ),
-                is_signer: false,
-                is_writable: true,
-            });
-        }
+                delegated_voter_weight_record: to_revoke.address,
+                governance_program_id: self.governance.program_id,
+                vote_record_info: get_vote_record_address(
+                    &self.governance.program_id,
+                    &to_revoke.target,
+                    &to_revoke_token_owner_record.address,
+                ),
+                realm_info: realm.address,
+                realm_config_info: get_realm_config_address(
+                    &self.governance.program_id,
+                    &realm.address,
+                ),
+                governance_info: proposal.account.governance,
+                proposal_info: proposal.address,
+                delegate_token_owner_record_info: to_revoke_token_owner_record.address,
+                realm_governing_token_mint: realm.community_mint_cookie.address,
+                governing_token_owner: delegator.wallet.address,
+                system_program: solana_sdk::system_program::id(),
+            },
+            None,
+        );
 
-        let mut update_voter_weight_record_ix = Instruction {
+        let revoke_partial_ix = Instruction {
             program_id: vote_delegation::id(),
             accounts,
             data,
         };
 
-        instruction_override(&mut update_voter_weight_record_ix);
-
         self.bench
             .process_transaction(
-                &[update_voter_weight_record_ix],
-                Some(&[&self.bench.payer, &owner.signer]),
+                &[revoke_partial_ix],
+                Some(&[&self.bench.payer, &delegator.wallet.signer]),
             )
             .await?;
 
         Ok(())
     }
 
-    pub async fn aggregate_delegation_for_self(
+    #[allow(dead_code)]
+    pub async fn revoke_delegation(
         &mut self,
         realm: &RealmCookie,
-        owner: &WalletCookie,
-        vwr: &VoterWeightRecordCookie,
-        own_vwr: &VoterWeightRecordCookie,
-        own_token_owner_record: &TokenOwnerRecordCookie,
+        delegator: &DelegatorCookie,
+        to_revoke: &VoterWeightRecordCookie,
     ) -> Result<(), TransportError> {
-        let data = anchor_lang::InstructionData::data(
-            &vote_delegation::instruction::UpdateVoterWeightRecord {
-                voter_weight_action: vwr.action,
-                target: Some(vwr.target),
-            },
-        );
+        let revoke_delegation_ix =
+            self.get_revoke_delegation_instruction(realm, delegator, to_revoke);
 
-        let mut accounts = anchor_lang::ToAccountMetas::to_account_metas(
-            &vote_delegation::accounts::UpdateVoterWeightRecord {
-                delegate: vwr.owner,
-                payer: self.bench.payer.pubkey(),
-                settings: Settings::get_pda_address(
-                    &realm.address,
-                    &realm.community_mint_cookie.address,
-                ),
-                voter_weight_record: vwr.address,
-                system_program: solana_sdk::system_program::id(),
-                governance_program_id: self.governance.program_id,
-                realm: realm.address,
+        self.bench
+            .process_transaction(
+                &[revoke_delegation_ix],
+                Some(&[&self.bench.payer, &delegator.wallet.signer]),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub async fn revoke_delegation_after_voting(
+        &mut self,
+        realm: &RealmCookie,
+        delegator: &DelegatorCookie,
+        to_revoke: &VoterWeightRecordCookie,
+    ) -> Result<(), TransportError> {
+        let mut revoke_delegation_ix =
+            self.get_revoke_delegation_instruction(realm, delegator, to_revoke);
+
+        revoke_delegation_ix.accounts.push(AccountMeta {
+            pubkey: to_revoke.target,
+            is_signer: false,
+            is_writable: false,
+        });
+        revoke_delegation_ix.accounts.push(AccountMeta {
+            pubkey: self.governance.program_id,
+            is_signer: false,
+            is_writable: false,
+        });
+
+        self.bench
+            .process_transaction(
+                &[revoke_delegation_ix],
+                Some(&[&self.bench.payer, &delegator.wallet.signer]),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    pub fn get_revoke_delegation_instruction(
+        &self,
+        realm: &RealmCookie,
+        delegator: &DelegatorCookie,
+        to_revoke: &VoterWeightRecordCookie,
+    ) -> Instruction {
+        let data =
+            anchor_lang::InstructionData::data(&vote_delegation::instruction::RevokeDelegation {});
+
+        let accounts = anchor_lang::ToAccountMetas::to_account_metas(
+            &vote_delegation::accounts::RevokeDelegation {
+                delegate: to_revoke.owner,
+                delegation_record: Delegation::get_pda_address(
+                    &realm.address,
+                    &realm.community_mint_cookie.address,
+                    &delegator.wallet.address,
+                    &to_revoke.target,
+                    Some(to_revoke.action),
+                ),
+                delegated_voter_weight_record: to_revoke.address,
+                governing_token_owner: delegator.wallet.address,
+                settings: Settings::get_pda_address(
+                    &realm.address,
+                    &realm.community_mint_cookie.address,
+                ),
+                rent_payer: self.bench.payer.pubkey(),
+            },
+            None,
+        );
+
+        Instruction {
+            program_id: vote_delegation::id(),
+            accounts,
+            data,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub async fn revoke_votes(
+        &mut self,
+        realm: &RealmCookie,
+        delegators: &[&DelegatorCookie],
+        to_revoke: &VoterWeightRecordCookie,
+        proposal: &ProposalCookie,
+        to_revoke_token_owner_record: &TokenOwnerRecordCookie,
+    ) -> Result<(), TransportError> {
+        let bench = self.bench.clone();
+        let signers = delegators
+            .iter()
+            .map(|delegator| &delegator.wallet.signer)
+            .chain(std::iter::once(&bench.payer))
+            .collect::<Vec<_>>();
+
+        self.revoke_votes_using_ix(
+            realm,
+            delegators,
+            to_revoke,
+            proposal,
+            to_revoke_token_owner_record,
+            NopOverride,
+            &signers,
+        )
+        .await
+    }
+
+    #[allow(dead_code)]
+    #[allow(clippy::too_many_arguments)]
+    pub async fn revoke_votes_using_ix<F: Fn(&mut Instruction)>(
+        &mut self,
+        realm: &RealmCookie,
+        delegators: &[&DelegatorCookie],
+        to_revoke: &VoterWeightRecordCookie,
+        proposal: &ProposalCookie,
+        to_revoke_token_owner_record: &TokenOwnerRecordCookie,
+        instruction_override: F,
+        signers: &[&Keypair],
+    ) -> Result<(), TransportError> {
+        let data =
+            anchor_lang::InstructionData::data(&vote_delegation::instruction::RevokeVotes {});
+
+        let mut accounts = anchor_lang::ToAccountMetas::to_account_metas(
+            &vote_delegation::accounts::RevokeVotes {
+                payer: self.bench.payer.pubkey(),
+                revoke_weight_record: VoterWeightRecord::get_revocation_address(
+                    &realm.address,
+                    &realm.community_mint_cookie.address,
+                    &to_revoke.owner,
+                    &to_revoke.target,
+                    Some(to_revoke.action),
+                ),
+                delegate: to_revoke.owner,
+                delegated_voter_weight_record: to_revoke.address,
+                governance_program_id: self.governance.program_id,
+                vote_record_info: get_vote_record_address(
+                    &self.governance.program_id,
+                    &to_revoke.target,
+                    &to_revoke_token_owner_record.address,
+                ),
+                realm_info: realm.address,
+                realm_config_info: get_realm_config_address(
+                    &self.governance.program_id,
+                    &realm.address,
+                ),
+                governance_info: proposal.account.governance,
+                proposal_info: proposal.address,
+                delegate_token_owner_record_info: to_revoke_token_owner_record.address,
+                realm_governing_token_mint: realm.community_mint_cookie.address,
+                system_program: solana_sdk::system_program::id(),
+                settings: Settings::get_pda_address(
+                    &realm.address,
+                    &realm.community_mint_cookie.address,
+                ),
+            },
+            None,
+        );
+
+        for delegator in delegators {
+            accounts.push(AccountMeta {
+                pubkey: delegator.token_owner_record.address,
+                is_signer: false,
+                is_writable: false,
+            });
+            accounts.push(AccountMeta {
+                pubkey: Delegation::get_pda_address(
+                    &realm.address,
+                    &realm.community_mint_cookie.address,
+                    &delegator.wallet.address,
+                    &to_revoke.target,
+                    Some(to_revoke.action),
+                ),
+                is_signer: false,
+                is_writable: true,
+            });
+            accounts.push(AccountMeta {
+                pubkey: delegator.wallet.address,
+                is_signer: true,
+                is_writable: false,
+            });
+            accounts.push(AccountMeta {
+                pubkey: self.bench.payer.pubkey(),
+                is_signer: false,
+                is_writable: true,
+            });
+        }
+
+        let mut revoke_ix = Instruction {
+            program_id: vote_delegation::id(),
+            accounts,
+            data,
+        };
+
+        instruction_override(&mut revoke_ix);
+
+        self.bench
+            .process_transaction(&[revoke_ix], Some(signers))
+            .await?;
+
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub async fn revoke_stale_delegation(
+        &mut self,
+        realm: &RealmCookie,
+        delegator: &DelegatorCookie,
+        to_revoke: &VoterWeightRecordCookie,
+        proposal: &ProposalCookie,
+        to_revoke_token_owner_record: &TokenOwnerRecordCookie,
+    ) -> Result<(), TransportError> {
+        let data = anchor_lang::InstructionData::data(
+            &vote_delegation::instruction::RevokeStaleDelegation {},
+        );
+
+        let accounts = anchor_lang::ToAccountMetas::to_account_metas(
+            &vote_delegation::accounts::RevokeStaleDelegation {
+                payer: self.bench.payer.pubkey(),
+                revoke_weight_record: VoterWeightRecord::get_revocation_address(
+                    &realm.address,
+                    &realm.community_mint_cookie.address,
+                    &delegator.wallet.address,
+                    &to_revoke.target,
+                    Some(to_revoke.action),
+                ),
+                delegate: to_revoke.owner,
+                delegation_record: Delegation::get_pda_address(
+                    &realm.address,
+                    &realm.community_mint_cookie.address,
+                    &delegator.wallet.address,
+                    &to_revoke.target,
+                    Some(to_revoke.action),
+                ),
+                delegated_voter_weight_record: to_revoke.address,
+                governance_program_id: self.governance.program_id,
+                vote_record_info: get_vote_record_address(
+                    &self.governance.program_id,
+                    &to_revoke.target,
+                    &to_revoke_token_owner_record.address,
+                ),
+                realm_info: realm.address,
+                realm_config_info: get_realm_config_address(
+                    &self.governance.program_id,
+                    &realm.address,
+                ),
+                governance_info: proposal.account.governance,
+                proposal_info: proposal.address,
+                delegate_token_owner_record_info: to_revoke_token_owner_record.address,
+                realm_governing_token_mint: realm.community_mint_cookie.address,
+                governing_token_owner_record_info: delegator.token_owner_record.address,
+                governing_token_owner: delegator.wallet.address,
+                system_program: solana_sdk::system_program::id(),
+                settings: Settings::get_pda_address(
+                    &realm.address,
+                    &realm.community_mint_cookie.address,
+                ),
+                rent_payer: self.bench.payer.pubkey(),
+            },
+            None,
+        );
+
+        let revoke_ix = Instruction {
+            program_id: vote_delegation::id(),
+            accounts,
+            data,
+        };
+
+        self.bench
+            .process_transaction(&[revoke_ix], Some(&[&self.bench.payer]))
+            .await?;
+
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub async fn release_delegation(
+        &mut self,
+        realm: &RealmCookie,
+        delegate: &WalletCookie,
+        delegator: &DelegatorCookie,
+        to_release: &VoterWeightRecordCookie,
+        proposal: &ProposalCookie,
+        delegate_token_owner_record: &TokenOwnerRecordCookie,
+    ) -> Result<(), TransportError> {
+        let data =
+            anchor_lang::InstructionData::data(&vote_delegation::instruction::ReleaseDelegation {});
+
+        let accounts = anchor_lang::ToAccountMetas::to_account_metas(
+            &vote_delegation::accounts::ReleaseDelegation {
+                payer: self.bench.payer.pubkey(),
+                revoke_weight_record: VoterWeightRecord::get_revocation_address(
+                    &realm.address,
+                    &realm.community_mint_cookie.address,
+                    &delegator.wallet.address,
+                    &to_release.target,
+                    Some(to_release.action),
+                ),
+                delegate: delegate.address,
+                delegation_record: Delegation::get_pda_address(
+                    &realm.address,
+                    &realm.community_mint_cookie.address,
+                    &delegator.wallet.address,
+                    &to_release.target,
+                    Some(to_release.action),
+                ),
+                delegated_voter_weight_record: to_release.address,
+                governance_program_id: self.governance.program_id,
+                vote_record_info: get_vote_record_address(
+                    &self.governance.program_id,
+                    &to_release.target,
+                    &delegate_token_owner_record.address,
+                ),
+                realm_info: realm.address,
+                realm_config_info: get_realm_config_address(
+                    &self.governance.program_id,
+                    &realm.address,
+                ),
+                governance_info: proposal.account.governance,
+                proposal_info: proposal.address,
+                delegate_token_owner_record_info: delegate_token_owner_record.address,
+                realm_governing_token_mint: realm.community_mint_cookie.address,
+                governing_token_owner: delegator.wallet.address,
+                system_program: solana_sdk::system_program::id(),
+                settings: Settings::get_pda_address(
+                    &realm.address,
+                    &realm.community_mint_cookie.address,
+                ),
+                rent_payer: self.bench.payer.pubkey(),
+            },
+            None,
+        );
+
+        let release_ix = Instruction {
+            program_id: vote_delegation::id(),
+            accounts,
+            data,
+        };
+
+        self.bench
+            .process_transaction(&[release_ix], Some(&[&self.bench.payer, &delegate.signer]))
+            .await?;
+
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub async fn refresh_delegation(
+        &mut self,
+        realm: &RealmCookie,
+        delegator: &DelegatorCookie,
+        to_refresh: &VoterWeightRecordCookie,
+        proposal: &ProposalCookie,
+        to_refresh_token_owner_record: &TokenOwnerRecordCookie,
+    ) -> Result<(), TransportError> {
+        self.refresh_delegation_using_ix(
+            realm,
+            delegator,
+            to_refresh,
+            proposal,
+            to_refresh_token_owner_record,
+            NopOverride,
+        )
+        .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn refresh_delegation_using_ix<F: Fn(&mut Instruction)>(
+        &mut self,
+        realm: &RealmCookie,
+        delegator: &DelegatorCookie,
+        to_refresh: &VoterWeightRecordCookie,
+        proposal: &ProposalCookie,
+        to_refresh_token_owner_record: &TokenOwnerRecordCookie,
+        instruction_override: F,
+    ) -> Result<(), TransportError> {
+        let data =
+            anchor_lang::InstructionData::data(&vote_delegation::instruction::RefreshDelegation {});
+
+        let accounts = anchor_lang::ToAccountMetas::to_account_metas(
+            &vote_delegation::accounts::RefreshDelegation {
+                payer: self.bench.payer.pubkey(),
+                revoke_weight_record: VoterWeightRecord::get_revocation_address(
+                    &realm.address,
+                    &realm.community_mint_cookie.address,
+                    &delegator.wallet.address,
+                    &to_refresh.target,
+                    Some(to_refresh.action),
+                ),
+                delegate: to_refresh.owner,
+                delegation_record: Delegation::get_pda_address(
+                    &realm.address,
+                    &realm.community_mint_cookie.address,
+                    &delegator.wallet.address,
+                    &to_refresh.target,
+                    Some(to_refresh.action),
+                ),
+                delegated_voter_weight_record: to_refresh.address,
+                settings: Settings::get_pda_address(
+                    &realm.address,
+                    &realm.community_mint_cookie.address,
+                ),
+                voter_weight_source_record: delegator.source_vwr.address,
+                governance_program_id: self.governance.program_id,
+                vote_record_info: get_vote_record_address(
+                    &self.governance.program_id,
+                    &to_refresh.target,
+                    &to_refresh_token_owner_record.address,
+                ),
+                realm_info: realm.address,
+                realm_config_info: get_realm_config_address(
+                    &self.governance.program_id,
+                    &realm.address,
+                ),
+                governance_info: proposal.account.governance,
+                proposal_info: proposal.address,
+                delegate_token_owner_record_info: to_refresh_token_owner_record.address,
+                realm_governing_token_mint: realm.community_mint_cookie.address,
+                governing_token_owner: delegator.wallet.address,
+                system_program: solana_sdk::system_program::id(),
+            },
+            None,
+        );
+
+        let mut refresh_ix = Instruction {
+            program_id: vote_delegation::id(),
+            accounts,
+            data,
+        };
+        instruction_override(&mut refresh_ix);
+
+        self.bench
+            .process_transaction(&[refresh_ix], Some(&[&self.bench.payer]))
+            .await?;
+
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub async fn override_vote(
+        &mut self,
+        realm: &RealmCookie,
+        delegator: &DelegatorCookie,
+        to_revoke: &VoterWeightRecordCookie,
+        proposal: &ProposalCookie,
+        to_revoke_token_owner_record: &TokenOwnerRecordCookie,
+        vote: Vote,
+    ) -> Result<(), TransportError> {
+        let data =
+            anchor_lang::InstructionData::data(&vote_delegation::instruction::OverrideVote {
+                vote,
+            });
+
+        let accounts = anchor_lang::ToAccountMetas::to_account_metas(
+            &vote_delegation::accounts::OverrideVote {
+                payer: self.bench.payer.pubkey(),
+                revoke_weight_record: VoterWeightRecord::get_revocation_address(
+                    &realm.address,
+                    &realm.community_mint_cookie.address,
+                    &delegator.wallet.address,
+                    &to_revoke.target,
+                    Some(to_revoke.action),
+                ),
+                delegate: to_revoke.owner,
+                delegation_record: Delegation::get_pda_address(
+                    &realm.address,
+                    &realm.community_mint_cookie.address,
+                    &delegator.wallet.address,
+                    &to_revoke.target,
+                    Some(to_revoke.action),
+                ),
+                delegated_voter_weight_record: to_revoke.address,
+                voter_weight_record: VoterWeightRecord::get_pda_address(
+                    &realm.address,
+                    &realm.community_mint_cookie.address,
+                    &delegator.wallet.address,
+                    &proposal.address,
+                    Some(VoterWeightAction::CastVote),
+                ),
+                governance_program_id: self.governance.program_id,
+                vote_record_info: get_vote_record_address(
+                    &self.governance.program_id,
+                    &to_revoke.target,
+                    &to_revoke_token_owner_record.address,
+                ),
+                governing_token_owner_vote_record_info: get_vote_record_address(
+                    &self.governance.program_id,
+                    &proposal.address,
+                    &delegator.token_owner_record.address,
+                ),
+                realm_info: realm.address,
+                realm_config_info: get_realm_config_address(
+                    &self.governance.program_id,
+                    &realm.address,
+                ),
+                governance_info: proposal.account.governance,
+                proposal_info: proposal.address,
+                proposal_owner_record_info: proposal.account.token_owner_record,
+                delegate_token_owner_record_info: to_revoke_token_owner_record.address,
+                governing_token_owner_record_info: delegator.token_owner_record.address,
+                realm_governing_token_mint: realm.community_mint_cookie.address,
+                governing_token_owner: delegator.wallet.address,
+                system_program: solana_sdk::system_program::id(),
+                settings: Settings::get_pda_address(
+                    &realm.address,
+                    &realm.community_mint_cookie.address,
+                ),
+            },
+            None,
+        );
+
+        let override_ix = Instruction {
+            program_id: vote_delegation::id(),
+            accounts,
+            data,
+        };
+
+        self.bench
+            .process_transaction(
+                &[override_ix],
+                Some(&[&self.bench.payer, &delegator.wallet.signer]),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn aggregate_delegation(
+        &mut self,
+        realm: &RealmCookie,
+        owner: &WalletCookie,
+        vwr: &VoterWeightRecordCookie,
+        delegator_accounts: &[&DelegatorCookie],
+    ) -> Result<(), TransportError> {
+        self.aggregate_delegation_using_ix(realm, owner, vwr, delegator_accounts, NopOverride)
+            .await
+    }
+
+    pub async fn aggregate_delegation_using_ix<F: Fn(&mut Instruction)>(
+        &mut self,
+        realm: &RealmCookie,
+        owner: &WalletCookie,
+        vwr: &VoterWeightRecordCookie,
+        delegator_accounts: &[&DelegatorCookie],
+        instruction_override: F,
+    ) -> Result<(), TransportError> {
+        let mut update_voter_weight_record_ix =
+            self.get_aggregate_delegation_instruction(realm, vwr, delegator_accounts);
+
+        instruction_override(&mut update_voter_weight_record_ix);
+
+        self.bench
+            .process_transaction(
+                &[update_voter_weight_record_ix],
+                Some(&[&self.bench.payer, &owner.signer]),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub async fn simulate_aggregate_delegation(
+        &mut self,
+        realm: &RealmCookie,
+        owner: &WalletCookie,
+        vwr: &VoterWeightRecordCookie,
+        delegator_accounts: &[&DelegatorCookie],
+    ) -> Result<u64, TransportError> {
+        let update_voter_weight_record_ix =
+            self.get_aggregate_delegation_instruction(realm, vwr, delegator_accounts);
+
+        self.bench
+            .simulate_transaction_units(
+                &[update_voter_weight_record_ix],
+                Some(&[&self.bench.payer, &owner.signer]),
+            )
+            .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn with_session(
+        &mut self,
+        delegate: &WalletCookie,
+        session_key: &Pubkey,
+        realm: Option<Pubkey>,
+        allowed_actions: Vec<VoterWeightAction>,
+        expiry: u64,
+    ) -> Result<Pubkey, TransportError> {
+        let data =
+            anchor_lang::InstructionData::data(&vote_delegation::instruction::CreateSession {
+                session_key: *session_key,
+                realm,
+                allowed_actions,
+                expiry,
+            });
+
+        let session = Session::get_pda_address(&delegate.address, session_key);
+        let accounts = anchor_lang::ToAccountMetas::to_account_metas(
+            &vote_delegation::accounts::CreateSession {
+                payer: self.bench.payer.pubkey(),
+                delegate: delegate.address,
+                session,
+                system_program: solana_sdk::system_program::id(),
+            },
+            None,
+        );
+
+        let create_session_ix = Instruction {
+            program_id: vote_delegation::id(),
+            accounts,
+            data,
+        };
+
+        self.bench
+            .process_transaction(
+                &[create_session_ix],
+                Some(&[&self.bench.payer, &delegate.signer]),
+            )
+            .await?;
+
+        Ok(session)
+    }
+
+    #[allow(dead_code)]
+    pub async fn revoke_session(
+        &mut self,
+        delegate: &WalletCookie,
+        session: Pubkey,
+    ) -> Result<(), TransportError> {
+        let data =
+            anchor_lang::InstructionData::data(&vote_delegation::instruction::RevokeSession {});
+
+        let accounts = anchor_lang::ToAccountMetas::to_account_metas(
+            &vote_delegation::accounts::RevokeSession {
+                delegate: delegate.address,
+                session,
+            },
+            None,
+        );
+
+        let revoke_session_ix = Instruction {
+            program_id: vote_delegation::id(),
+            accounts,
+            data,
+        };
+
+        self.bench
+            .process_transaction(&[revoke_session_ix], Some(&[&delegate.signer]))
+            .await
+    }
+
+    /// Aggregates for the owner of vwr, signed by a session key instead of the owner
+    #[allow(dead_code)]
+    pub async fn aggregate_delegation_with_session(
+        &mut self,
+        realm: &RealmCookie,
+        session_key: &WalletCookie,
+        session: Pubkey,
+        vwr: &VoterWeightRecordCookie,
+        delegator_accounts: &[&DelegatorCookie],
+    ) -> Result<(), TransportError> {
+        let mut update_voter_weight_record_ix =
+            self.get_aggregate_delegation_instruction(realm, vwr, delegator_accounts);
+
+        // The session goes before the delegator accounts, after the 7 named accounts.
+        update_voter_weight_record_ix.accounts[0] =
+            AccountMeta::new_readonly(session_key.address, true);
+        update_voter_weight_record_ix
+            .accounts
+            .insert(7, AccountMeta::new_readonly(session, false));
+
+        self.bench
+            .process_transaction(
+                &[update_voter_weight_record_ix],
+                Some(&[&self.bench.payer, &session_key.signer]),
+            )
+            .await
+    }
+
+    /// Relays message for delegator, with the signature of signer over it
+    #[allow(dead_code)]
+    pub async fn delegate_with_signature(
+        &mut self,
+        delegator: &Pubkey,
+        signer: &Keypair,
+        message: &DelegationMessage,
+    ) -> Result<Pubkey, TransportError> {
+        let data = anchor_lang::InstructionData::data(
+            &vote_delegation::instruction::DelegateWithSignature {
+                message: message.clone(),
+            },
+        );
+
+        let signed_delegation = SignedDelegation::get_pda_address(
+            &message.realm,
+            &message.governing_token_mint,
+            delegator,
+        );
+        let accounts = anchor_lang::ToAccountMetas::to_account_metas(
+            &vote_delegation::accounts::DelegateWithSignature {
+                payer: self.bench.payer.pubkey(),
+                delegator: *delegator,
+                signed_delegation,
+                instructions: solana_program::sysvar::instructions::id(),
+                system_program: solana_sdk::system_program::id(),
+            },
+            None,
+        );
+
+        let delegate_with_signature_ix = Instruction {
+            program_id: vote_delegation::id(),
+            accounts,
+            data,
+        };
+
+        self.bench
+            .process_transaction(
+                &[
+                    get_ed25519_instruction(signer, &message.to_signed_bytes()),
+                    delegate_with_signature_ix,
+                ],
+                Some(&[&self.bench.payer]),
+            )
+            .await?;
+
+        Ok(signed_delegation)
+    }
+
+    #[allow(dead_code)]
+    pub async fn aggregate_and_cast_vote(
+        &mut self,
+        realm: &RealmCookie,
+        delegate: &WalletCookie,
+        vwr: &VoterWeightRecordCookie,
+        proposal: &ProposalCookie,
+        delegate_token_owner_record: &TokenOwnerRecordCookie,
+        delegator_accounts: &[&DelegatorCookie],
+        vote: Vote,
+    ) -> Result<(), TransportError> {
+        let data = anchor_lang::InstructionData::data(
+            &vote_delegation::instruction::AggregateAndCastVote { vote },
+        );
+
+        let mut accounts = anchor_lang::ToAccountMetas::to_account_metas(
+            &vote_delegation::accounts::AggregateAndCastVote {
+                payer: self.bench.payer.pubkey(),
+                delegate: delegate.address,
+                settings: Settings::get_pda_address(
+                    &realm.address,
+                    &realm.community_mint_cookie.address,
+                ),
+                voter_weight_record: vwr.address,
+                governance_program_id: self.governance.program_id,
+                vote_record_info: get_vote_record_address(
+                    &self.governance.program_id,
+                    &proposal.address,
+                    &delegate_token_owner_record.address,
+                ),
+                realm_info: realm.address,
+                realm_config_info: get_realm_config_address(
+                    &self.governance.program_id,
+                    &realm.address,
+                ),
+                governance_info: proposal.account.governance,
+                proposal_info: proposal.address,
+                proposal_owner_record_info: proposal.account.token_owner_record,
+                delegate_token_owner_record_info: delegate_token_owner_record.address,
+                realm_governing_token_mint: realm.community_mint_cookie.address,
+                system_program: solana_sdk::system_program::id(),
+            },
+            None,
+        );
+        // The delegator accounts are the same as for update_voter_weight_record.
+        let update_voter_weight_record_ix =
+            self.get_aggregate_delegation_instruction(realm, vwr, delegator_accounts);
+        accounts.extend_from_slice(&update_voter_weight_record_ix.accounts[7..]);
+
+        let aggregate_and_cast_vote_ix = Instruction {
+            program_id: vote_delegation::id(),
+            accounts,
+            data,
+        };
+
+        self.bench
+            .process_transaction(
+                &[aggregate_and_cast_vote_ix],
+                Some(&[&self.bench.payer, &delegate.signer]),
+            )
+            .await
+    }
+
+    /// Creates a proposal with the given index in the governance targeted by vwr
+    #[allow(dead_code, clippy::too_many_arguments)]
+    pub async fn aggregate_and_create_proposal(
+        &mut self,
+        realm: &RealmCookie,
+        delegate: &WalletCookie,
+        vwr: &VoterWeightRecordCookie,
+        delegate_token_owner_record: &TokenOwnerRecordCookie,
+        delegator_accounts: &[&DelegatorCookie],
+        proposal_index: u32,
+        vote_type: VoteType,
+        options: Vec<String>,
+    ) -> Result<Pubkey, TransportError> {
+        let data = anchor_lang::InstructionData::data(
+            &vote_delegation::instruction::AggregateAndCreateProposal {
+                name: String::from("Delegated proposal"),
+                description_link: String::from("Delegated proposal link"),
+                vote_type,
+                options,
+                use_deny_option: true,
+            },
+        );
+
+        let proposal = get_proposal_address(
+            &self.governance.program_id,
+            &vwr.target,
+            &realm.community_mint_cookie.address,
+            &proposal_index.to_le_bytes(),
+        );
+        let mut accounts = anchor_lang::ToAccountMetas::to_account_metas(
+            &vote_delegation::accounts::AggregateAndCreateProposal {
+                payer: self.bench.payer.pubkey(),
+                delegate: delegate.address,
+                settings: Settings::get_pda_address(
+                    &realm.address,
+                    &realm.community_mint_cookie.address,
+                ),
+                voter_weight_record: vwr.address,
+                governance_program_id: self.governance.program_id,
+                proposal_info: proposal,
+                realm_info: realm.address,
+                realm_config_info: get_realm_config_address(
+                    &self.governance.program_id,
+                    &realm.address,
+                ),
+                governance_info: vwr.target,
+                delegate_token_owner_record_info: delegate_token_owner_record.address,
+                realm_governing_token_mint: realm.community_mint_cookie.address,
+                system_program: solana_sdk::system_program::id(),
+            },
+            None,
+        );
+        // The delegator accounts are the same as for update_voter_weight_record.
+        let update_voter_weight_record_ix =
+            self.get_aggregate_delegation_instruction(realm, vwr, delegator_accounts);
+        accounts.extend_from_slice(&update_voter_weight_record_ix.accounts[7..]);
+
+        let aggregate_and_create_proposal_ix = Instruction {
+            program_id: vote_delegation::id(),
+            accounts,
+            data,
+        };
+
+        self.bench
+            .process_transaction(
+                &[aggregate_and_create_proposal_ix],
+                Some(&[&self.bench.payer, &delegate.signer]),
+            )
+            .await?;
+
+        Ok(proposal)
+    }
+
+    #[allow(dead_code)]
+    pub async fn crank_voter_weight_record(
+        &mut self,
+        realm: &RealmCookie,
+        vwr: &VoterWeightRecordCookie,
+        delegator_accounts: &[&DelegatorCookie],
+    ) -> Result<(), TransportError> {
+        let data = anchor_lang::InstructionData::data(
+            &vote_delegation::instruction::CrankVoterWeightRecord {},
+        );
+
+        let mut accounts = anchor_lang::ToAccountMetas::to_account_metas(
+            &vote_delegation::accounts::CrankVoterWeightRecord {
+                payer: self.bench.payer.pubkey(),
+                settings: Settings::get_pda_address(
+                    &realm.address,
+                    &realm.community_mint_cookie.address,
+                ),
+                voter_weight_record: vwr.address,
+                system_program: solana_sdk::system_program::id(),
+                governance_program_id: self.governance.program_id,
+                realm: realm.address,
+                vote_record: get_vote_record_address(
+                    &self.governance.program_id,
+                    &vwr.target,
+                    &get_token_owner_record_address(
+                        &self.governance.program_id,
+                        &realm.address,
+                        &realm.community_mint_cookie.address,
+                        &vwr.owner,
+                    ),
+                ),
+            },
+            None,
+        );
+        // The delegator accounts are the same as for update_voter_weight_record.
+        let update_voter_weight_record_ix =
+            self.get_aggregate_delegation_instruction(realm, vwr, delegator_accounts);
+        accounts.extend_from_slice(&update_voter_weight_record_ix.accounts[7..]);
+
+        let crank_voter_weight_record_ix = Instruction {
+            program_id: vote_delegation::id(),
+            accounts,
+            data,
+        };
+
+        self.bench
+            .process_transaction(&[crank_voter_weight_record_ix], Some(&[&self.bench.payer]))
+            .await
+    }
+
+    pub fn get_aggregate_delegation_instruction(
+        &self,
+        realm: &RealmCookie,
+        vwr: &VoterWeightRecordCookie,
+        delegator_accounts: &[&DelegatorCookie],
+    ) -> Instruction {
+        let data = anchor_lang::InstructionData::data(
+            &vote_delegation::instruction::UpdateVoterWeightRecord {
+                voter_weight_action: vwr.action,
+                target: Some(vwr.target),
+                delegation_bumps: delegator_accounts
+                    .iter()
+                    .map(|delegator| {
+                        get_delegation_bump(
+                            &realm.address,
+                            &vwr.governing_token_mint,
+                            &delegator.wallet.address,
+                            &delegator.source_vwr.target,
+                            Some(delegator.source_vwr.action),
+                        )
+                    })
+                    .collect(),
+            },
+        );
+
+        let mut accounts = anchor_lang::ToAccountMetas::to_account_metas(
+            &vote_delegation::accounts::UpdateVoterWeightRecord {
+                delegate: vwr.owner,
+                payer: self.bench.payer.pubkey(),
+                settings: Settings::get_pda_address(&realm.address, &vwr.governing_token_mint),
+                voter_weight_record: vwr.address,
+                system_program: solana_sdk::system_program::id(),
+                governance_program_id: self.governance.program_id,
+                realm: realm.address,
+            },
+            None,
+        );
+
+        for delegator in delegator_accounts {
+            accounts.push(AccountMeta {
+                pubkey: delegator.source_vwr.address,
+                is_signer: false,
+                is_writable: false,
+            });
+            accounts.push(AccountMeta {
+                pubkey: delegator.token_owner_record.address,
+                is_signer: false,
+                is_writable: false,
+            });
+            accounts.push(AccountMeta {
+                pubkey: Delegation::get_pda_address(
+                    &realm.address,
+                    &vwr.governing_token_mint,
+                    &delegator.wallet.address,
+                    &delegator.source_vwr.target,
+                    Some(delegator.source_vwr.action),
+                ),
+                is_signer: false,
+                is_writable: true,
+            });
+        }
+
+        Instruction {
+            program_id: vote_delegation::id(),
+            accounts,
+            data,
+        }
+    }
+
+    pub async fn aggregate_delegation_for_self(
+        &mut self,
+        realm: &RealmCookie,
+        owner: &WalletCookie,
+        vwr: &VoterWeightRecordCookie,
+        own_vwr: &VoterWeightRecordCookie,
+        own_token_owner_record: &TokenOwnerRecordCookie,
+    ) -> Result<(), TransportError> {
+        let data = anchor_lang::InstructionData::data(
+            &vote_delegation::instruction::UpdateVoterWeightRecord {
+                voter_weight_action: vwr.action,
+                target: Some(vwr.target),
+                delegation_bumps: vec![get_delegation_bump(
+                    &realm.address,
+                    &realm.community_mint_cookie.address,
+                    &owner.address,
+                    &own_vwr.target,
+                    Some(own_vwr.action),
+                )],
+            },
+        );
+
+        let mut accounts = anchor_lang::ToAccountMetas::to_account_metas(
+            &vote_delegation::accounts::UpdateVoterWeightRecord {
+                delegate: vwr.owner,
+                payer: self.bench.payer.pubkey(),
+                settings: Settings::get_pda_address(
+                    &realm.address,
+                    &realm.community_mint_cookie.address,
+                ),
+                voter_weight_record: vwr.address,
+                system_program: solana_sdk::system_program::id(),
+                governance_program_id: self.governance.program_id,
+                realm: realm.address,
+            },
+            None,
+        );
+
+        accounts.push(AccountMeta {
+            pubkey: own_vwr.address,
+            is_signer: false,
+            is_writable: false,
+        });
+        accounts.push(AccountMeta {
+            pubkey: own_token_owner_record.address,
+            is_signer: false,
+            is_writable: false,
+        });
+        accounts.push(AccountMeta {
+            pubkey: Delegation::get_pda_address(
+                &realm.address,
+                &realm.community_mint_cookie.address,
+                &owner.address,
+                &own_vwr.target,
+                Some(own_vwr.action),
+            ),
+            is_signer: false,
+            is_writable: true,
+        });
+
+        let update_voter_weight_record_ix = Instruction {
+            program_id: vote_delegation::id(),
+            accounts,
+            data,
+        };
+
+        self.bench
+            .process_transaction(
+                &[update_voter_weight_record_ix],
+                Some(&[&self.bench.payer, &owner.signer]),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub async fn reclaim_voter_weight_record(
+        &mut self,
+        token_owner_cookie: &WalletCookie,
+        proposal_cookie: &ProposalCookie,
+        vwr_cookie: &VoterWeightRecordCookie,
+    ) -> Result<(), TransportError> {
+        let data = anchor_lang::InstructionData::data(
+            &vote_delegation::instruction::ReclaimVoterWeightRecord {},
+        );
+
+        let accounts = anchor_lang::ToAccountMetas::to_account_metas(
+            &vote_delegation::accounts::ReclaimVoterWeightRecord {
+                payer: self.bench.payer.pubkey(),
+                caller: token_owner_cookie.address,
+                voter_weight_record: vwr_cookie.address,
+                rent_payer: RentPayer::get_pda_address(&vwr_cookie.address),
+                record_payer: self.bench.payer.pubkey(),
+                target: vwr_cookie.target,
+                governance_program_id: self.governance.program_id,
+            },
+            None,
+        );
+
+        let reclaim_voter_weight_record_ix = Instruction {
+            program_id: vote_delegation::id(),
+            accounts,
+            data,
+        };
+
+        self.bench
+            .process_transaction(
+                &[reclaim_voter_weight_record_ix],
+                Some(&[&self.bench.payer, &token_owner_cookie.signer]),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub async fn reclaim_voter_weight_records(
+        &mut self,
+        token_owner_cookie: &WalletCookie,
+        vwr_cookies: &[&VoterWeightRecordCookie],
+    ) -> Result<(), TransportError> {
+        let data = anchor_lang::InstructionData::data(
+            &vote_delegation::instruction::ReclaimVoterWeightRecords {},
+        );
+
+        let mut accounts = anchor_lang::ToAccountMetas::to_account_metas(
+            &vote_delegation::accounts::ReclaimVoterWeightRecords {
+                payer: self.bench.payer.pubkey(),
+                caller: token_owner_cookie.address,
+                governance_program_id: self.governance.program_id,
+            },
+            None,
+        );
+
+        for vwr_cookie in vwr_cookies {
+            accounts.push(AccountMeta {
+                pubkey: vwr_cookie.address,
+                is_signer: false,
+                is_writable: true,
+            });
+            accounts.push(AccountMeta {
+                pubkey: vwr_cookie.target,
+                is_signer: false,
+                is_writable: false,
+            });
+            accounts.push(AccountMeta {
+                pubkey: RentPayer::get_pda_address(&vwr_cookie.address),
+                is_signer: false,
+                is_writable: true,
+            });
+            accounts.push(AccountMeta {
+                pubkey: self.bench.payer.pubkey(),
+                is_signer: false,
+                is_writable: true,
+            });
+        }
+
+        let reclaim_voter_weight_records_ix = Instruction {
+            program_id: vote_delegation::id(),
+            accounts,
+            data,
+        };
+
+        self.bench
+            .process_transaction(
+                &[reclaim_voter_weight_records_ix],
+                Some(&[&self.bench.payer, &token_owner_cookie.signer]),
+            )
+            .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn reclaim_delegation_records(
+        &mut self,
+        realm: &RealmCookie,
+        delegate: &WalletCookie,
+        vwr_cookie: &VoterWeightRecordCookie,
+        delegators: &[&DelegatorCookie],
+    ) -> Result<(), TransportError> {
+        self.reclaim_delegation_records_using_ix(
+            realm,
+            delegate,
+            vwr_cookie,
+            delegators,
+            NopOverride,
+        )
+        .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn reclaim_delegation_records_using_ix<F: Fn(&mut Instruction)>(
+        &mut self,
+        realm: &RealmCookie,
+        delegate: &WalletCookie,
+        vwr_cookie: &VoterWeightRecordCookie,
+        delegators: &[&DelegatorCookie],
+        instruction_override: F,
+    ) -> Result<(), TransportError> {
+        let data = anchor_lang::InstructionData::data(
+            &vote_delegation::instruction::ReclaimDelegations {},
+        );
+
+        let mut accounts = anchor_lang::ToAccountMetas::to_account_metas(
+            &vote_delegation::accounts::ReclaimDelegations {
+                payer: self.bench.payer.pubkey(),
+                delegate: delegate.address,
+                settings: Settings::get_pda_address(
+                    &realm.address,
+                    &realm.community_mint_cookie.address,
+                ),
             },
             None,
         );
 
-        accounts.push(AccountMeta {
-            pubkey: own_vwr.address,
-            is_signer: false,
-            is_writable: false,
-        });
-        accounts.push(AccountMeta {
-            pubkey: own_token_owner_record.address,
-            is_signer: false,
-            is_writable: false,
-        });
-        accounts.push(AccountMeta {
-            pubkey: Delegation::get_pda_address(
-                &realm.address,
-                &realm.community_mint_cookie.address,
-                &owner.address,
-                &own_vwr.target,
-                Some(own_vwr.action),
-            ),
-            is_signer: false,
-            is_writable: true,
-        });
+        for delegator in delegators {
+            accounts.push(AccountMeta {
+                pubkey: vwr_cookie.address,
+                is_signer: false,
+                is_writable: false,
+            });
+            accounts.push(AccountMeta {
+                pubkey: Delegation::get_pda_address(
+                    &realm.address,
+                    &realm.community_mint_cookie.address,
+                    &delegator.wallet.address,
+                    &delegator.source_vwr.target,
+                    Some(delegator.source_vwr.action),
+                ),
+                is_signer: false,
+                is_writable: true,
+            });
+            accounts.push(AccountMeta {
+                pubkey: self.bench.payer.pubkey(),
+                is_signer: false,
+                is_writable: true,
+            });
+        }
 
-        let update_voter_weight_record_ix = Instruction {
+        let mut reclaim_delegations_ix = Instruction {
             program_id: vote_delegation::id(),
             accounts,
             data,
         };
 
-        self.bench
-            .process_transaction(
-                &[update_voter_weight_record_ix],
-                Some(&[&self.bench.payer, &owner.signer]),
-            )
-            .await?;
+        instruction_override(&mut reclaim_delegations_ix);
 
-        Ok(())
+        self.bench
+            .process_transaction(&[reclaim_delegations_ix], Some(&[&self.bench.payer]))
+            .await
     }
 
     #[allow(dead_code)]
-    pub async fn reclaim_voter_weight_record(
+    pub async fn refund_voter_weight_record(
         &mut self,
-        token_owner_cookie: &WalletCookie,
-        proposal_cookie: &ProposalCookie,
+        rent_payer: &Pubkey,
         vwr_cookie: &VoterWeightRecordCookie,
     ) -> Result<(), TransportError> {
         let data = anchor_lang::InstructionData::data(
-            &vote_delegation::instruction::ReclaimVoterWeightRecord {},
+            &vote_delegation::instruction::RefundVoterWeightRecord {},
         );
 
         let accounts = anchor_lang::ToAccountMetas::to_account_metas(
-            &vote_delegation::accounts::ReclaimVoterWeightRecord {
-                payer: self.bench.payer.pubkey(),
-                caller: token_owner_cookie.address,
+            &vote_delegation::accounts::RefundVoterWeightRecord {
+                payer: *rent_payer,
+                rent_payer: RentPayer::get_pda_address(&vwr_cookie.address),
                 voter_weight_record: vwr_cookie.address,
                 target: vwr_cookie.target,
                 governance_program_id: self.governance.program_id,
@@ -527,30 +1892,71 @@ impl DelegationTest {
             None,
         );
 
-        let reclaim_voter_weight_record_ix = Instruction {
+        let refund_voter_weight_record_ix = Instruction {
             program_id: vote_delegation::id(),
             accounts,
             data,
         };
 
         self.bench
-            .process_transaction(
-                &[reclaim_voter_weight_record_ix],
-                Some(&[&self.bench.payer, &token_owner_cookie.signer]),
-            )
+            .process_transaction(&[refund_voter_weight_record_ix], None)
+            .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn reclaim_delegation_record(
+        &mut self,
+        realm: &RealmCookie,
+        delegate: &WalletCookie,
+        vwr_cookie: &VoterWeightRecordCookie,
+        delegator: &DelegatorCookie,
+    ) -> Result<(), TransportError> {
+        let reclaim_delegation_ix =
+            self.get_reclaim_delegation_instruction(realm, delegate, vwr_cookie, delegator);
+
+        self.bench
+            .process_transaction(&[reclaim_delegation_ix], Some(&[&self.bench.payer]))
             .await?;
 
         Ok(())
     }
 
     #[allow(dead_code)]
-    pub async fn reclaim_delegation_record(
+    pub async fn reclaim_delegation_record_with_target(
         &mut self,
         realm: &RealmCookie,
         delegate: &WalletCookie,
         vwr_cookie: &VoterWeightRecordCookie,
         delegator: &DelegatorCookie,
     ) -> Result<(), TransportError> {
+        let mut reclaim_delegation_ix =
+            self.get_reclaim_delegation_instruction(realm, delegate, vwr_cookie, delegator);
+
+        reclaim_delegation_ix.accounts.push(AccountMeta {
+            pubkey: vwr_cookie.target,
+            is_signer: false,
+            is_writable: false,
+        });
+        reclaim_delegation_ix.accounts.push(AccountMeta {
+            pubkey: self.governance.program_id,
+            is_signer: false,
+            is_writable: false,
+        });
+
+        self.bench
+            .process_transaction(&[reclaim_delegation_ix], Some(&[&self.bench.payer]))
+            .await?;
+
+        Ok(())
+    }
+
+    pub fn get_reclaim_delegation_instruction(
+        &self,
+        realm: &RealmCookie,
+        delegate: &WalletCookie,
+        vwr_cookie: &VoterWeightRecordCookie,
+        delegator: &DelegatorCookie,
+    ) -> Instruction {
         let data =
             anchor_lang::InstructionData::data(&vote_delegation::instruction::ReclaimDelegation {});
 
@@ -566,6 +1972,206 @@ impl DelegationTest {
                     &delegator.source_vwr.target,
                     Some(delegator.source_vwr.action),
                 ),
+                settings: Settings::get_pda_address(
+                    &realm.address,
+                    &realm.community_mint_cookie.address,
+                ),
+                rent_payer: self.bench.payer.pubkey(),
+            },
+            None,
+        );
+
+        Instruction {
+            program_id: vote_delegation::id(),
+            accounts,
+            data,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub async fn set_delegation_store(
+        &mut self,
+        realm: &RealmCookie,
+        delegation_store: DelegationStore,
+    ) -> Result<(), TransportError> {
+        let data =
+            anchor_lang::InstructionData::data(&vote_delegation::instruction::SetDelegationStore {
+                mint: realm.community_mint_cookie.address,
+                delegation_store,
+            });
+
+        let accounts = anchor_lang::ToAccountMetas::to_account_metas(
+            &vote_delegation::accounts::SetDelegationStore {
+                signer: realm.realm_authority.pubkey(),
+                payer: self.bench.payer.pubkey(),
+                settings: Settings::get_pda_address(&realm.address, &realm.account.community_mint),
+                governance_program_id: self.governance.program_id,
+                realm_info: realm.address,
+                system_program: solana_sdk::system_program::id(),
+            },
+            None,
+        );
+
+        let set_delegation_store_ix = Instruction {
+            program_id: vote_delegation::id(),
+            accounts,
+            data,
+        };
+
+        self.bench
+            .process_transaction(
+                &[set_delegation_store_ix],
+                Some(&[&self.bench.payer, &realm.realm_authority]),
+            )
+            .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn with_delegation_ledger(
+        &mut self,
+        realm: &RealmCookie,
+        target: Pubkey,
+        action: VoterWeightAction,
+    ) -> Result<Pubkey, TransportError> {
+        let data = anchor_lang::InstructionData::data(
+            &vote_delegation::instruction::CreateDelegationLedger { target, action },
+        );
+
+        let address = DelegationLedger::get_pda_address(
+            &realm.address,
+            &realm.community_mint_cookie.address,
+            &target,
+            Some(action),
+        );
+        let accounts = anchor_lang::ToAccountMetas::to_account_metas(
+            &vote_delegation::accounts::CreateDelegationLedger {
+                payer: self.bench.payer.pubkey(),
+                delegation_ledger: address,
+                governance_program_id: self.governance.program_id,
+                realm: realm.address,
+                realm_governing_token_mint: realm.community_mint_cookie.address,
+                system_program: solana_sdk::system_program::id(),
+            },
+            None,
+        );
+
+        let create_delegation_ledger_ix = Instruction {
+            program_id: vote_delegation::id(),
+            accounts,
+            data,
+        };
+
+        self.bench
+            .process_transaction(&[create_delegation_ledger_ix], Some(&[&self.bench.payer]))
+            .await?;
+
+        Ok(address)
+    }
+
+    #[allow(dead_code)]
+    pub async fn aggregate_delegation_with_ledger(
+        &mut self,
+        realm: &RealmCookie,
+        owner: &WalletCookie,
+        vwr: &VoterWeightRecordCookie,
+        delegator_accounts: &[&DelegatorCookie],
+    ) -> Result<(), TransportError> {
+        self.aggregate_delegation_with_ledger_using_ix(
+            realm,
+            owner,
+            vwr,
+            delegator_accounts,
+            NopOverride,
+        )
+        .await
+    }
+
+    /// Aggregates with the ledger of the target in place of each delegation record
+    #[allow(dead_code)]
+    pub async fn aggregate_delegation_with_ledger_using_ix<F: Fn(&mut Instruction)>(
+        &mut self,
+        realm: &RealmCookie,
+        owner: &WalletCookie,
+        vwr: &VoterWeightRecordCookie,
+        delegator_accounts: &[&DelegatorCookie],
+        instruction_override: F,
+    ) -> Result<(), TransportError> {
+        let ledger = DelegationLedger::get_pda_address(
+            &realm.address,
+            &vwr.governing_token_mint,
+            &vwr.target,
+            Some(vwr.action),
+        );
+
+        self.aggregate_delegation_using_ix(realm, owner, vwr, delegator_accounts, |ix| {
+            for accounts in ix.accounts[7..].chunks_exact_mut(3) {
+                accounts[2].pubkey = ledger;
+            }
+            instruction_override(ix);
+        })
+        .await
+    }
+
+    /// Revokes with the ledger of the target in place of the delegation record
+    #[allow(dead_code)]
+    pub async fn revoke_vote_from_ledger(
+        &mut self,
+        realm: &RealmCookie,
+        delegator: &DelegatorCookie,
+        to_revoke: &VoterWeightRecordCookie,
+        proposal: &ProposalCookie,
+        to_revoke_token_owner_record: &TokenOwnerRecordCookie,
+    ) -> Result<(), TransportError> {
+        let ledger = DelegationLedger::get_pda_address(
+            &realm.address,
+            &to_revoke.governing_token_mint,
+            &to_revoke.target,
+            Some(to_revoke.action),
+        );
+        let delegate = to_revoke.owner;
+
+        self.revoke_vote_using_ix(
+            realm,
+            delegator,
+            to_revoke,
+            proposal,
+            to_revoke_token_owner_record,
+            move |ix| {
+                ix.accounts[3].pubkey = ledger;
+                ix.accounts.last_mut().unwrap().pubkey = delegate;
+            },
+            None,
+        )
+        .await
+    }
+
+    /// Reclaims with the ledger of the target in place of the delegation record
+    #[allow(dead_code)]
+    pub async fn reclaim_delegation_from_ledger(
+        &mut self,
+        realm: &RealmCookie,
+        delegate: &WalletCookie,
+        vwr_cookie: &VoterWeightRecordCookie,
+    ) -> Result<(), TransportError> {
+        let data =
+            anchor_lang::InstructionData::data(&vote_delegation::instruction::ReclaimDelegation {});
+
+        let accounts = anchor_lang::ToAccountMetas::to_account_metas(
+            &vote_delegation::accounts::ReclaimDelegation {
+                payer: self.bench.payer.pubkey(),
+                voter_weight_record: vwr_cookie.address,
+                delegate: delegate.address,
+                delegation: DelegationLedger::get_pda_address(
+                    &realm.address,
+                    &vwr_cookie.governing_token_mint,
+                    &vwr_cookie.target,
+                    Some(vwr_cookie.action),
+                ),
+                settings: Settings::get_pda_address(
+                    &realm.address,
+                    &vwr_cookie.governing_token_mint,
+                ),
+                rent_payer: self.bench.payer.pubkey(),
             },
             None,
         );
@@ -578,8 +2184,58 @@ impl DelegationTest {
 
         self.bench
             .process_transaction(&[reclaim_delegation_ix], Some(&[&self.bench.payer]))
-            .await?;
+            .await
+    }
+}
 
-        Ok(())
+/// Builds an ed25519 program instruction verifying the signature of signer over message
+#[allow(dead_code)]
+pub fn get_ed25519_instruction(signer: &Keypair, message: &[u8]) -> Instruction {
+    // Offsets of the key, signature and message in this instruction's data, as laid out by
+    // solana_sdk::ed25519_instruction, which needs an ed25519_dalek keypair.
+    let public_key_offset: u16 = 16;
+    let signature_offset = public_key_offset + 32;
+    let message_offset = signature_offset + 64;
+
+    let mut data = vec![1, 0];
+    for value in [
+        signature_offset,
+        u16::MAX,
+        public_key_offset,
+        u16::MAX,
+        message_offset,
+        message.len() as u16,
+        u16::MAX,
+    ] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(signer.pubkey().as_ref());
+    data.extend_from_slice(signer.sign_message(message).as_ref());
+    data.extend_from_slice(message);
+
+    Instruction {
+        program_id: solana_sdk::ed25519_program::id(),
+        accounts: vec![],
+        data,
     }
 }
+
+/// Returns the canonical bump of the Delegation record of governing_token_owner
+#[allow(dead_code)]
+pub fn get_delegation_bump(
+    realm: &Pubkey,
+    governing_token_mint: &Pubkey,
+    governing_token_owner: &Pubkey,
+    target: &Pubkey,
+    action: Option<VoterWeightAction>,
+) -> u8 {
+    let encoded_action = borsh::to_vec(&action).unwrap();
+    let seeds = Delegation::get_pda_seeds(
+        realm,
+        governing_token_mint,
+        governing_token_owner,
+        target,
+        &encoded_action,
+    );
+    Pubkey::find_program_address(&seeds, &vote_delegation::id()).1
+}
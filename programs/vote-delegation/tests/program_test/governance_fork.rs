@@ -0,0 +1,126 @@
+//! Realm fixtures for the relinquish-partial spl-governance fork, which configures voter
+//! weight addins on the realm itself.
+use solana_sdk::{signature::Keypair, signer::Signer, transport::TransportError};
+use spl_governance::{
+    instruction::create_realm,
+    state::{
+        enums::{GovernanceAccountType, MintMaxVoteWeightSource},
+        realm::{get_realm_address, RealmConfig, RealmV2},
+    },
+};
+
+use crate::program_test::{
+    governance_test::{GovernanceTest, RealmCookie},
+    program_test_bench::MintCookie,
+};
+
+impl GovernanceTest {
+    #[allow(dead_code)]
+    pub async fn with_realm(&mut self) -> Result<RealmCookie, TransportError> {
+        let realm_authority = Keypair::new();
+
+        let community_mint_cookie = self.bench.with_mint().await?;
+        let council_mint_cookie = self.bench.with_mint().await?;
+
+        self.next_id += 1;
+        let realm_name = format!("Realm #{}", self.next_id).to_string();
+
+        let min_community_weight_to_create_governance = 1;
+        let community_mint_max_vote_weight_source = MintMaxVoteWeightSource::FULL_SUPPLY_FRACTION;
+
+        let realm_key = get_realm_address(&self.program_id, &realm_name);
+
+        let create_realm_ix = create_realm(
+            &self.program_id,
+            &realm_authority.pubkey(),
+            &community_mint_cookie.address,
+            &self.bench.payer.pubkey(),
+            Some(council_mint_cookie.address),
+            self.community_voter_weight_addin,
+            self.max_community_voter_weight_addin,
+            realm_name.clone(),
+            min_community_weight_to_create_governance,
+            community_mint_max_vote_weight_source.clone(),
+        );
+
+        self.bench
+            .process_transaction(&[create_realm_ix], None)
+            .await?;
+
+        let account = RealmV2 {
+            account_type: GovernanceAccountType::RealmV2,
+            community_mint: community_mint_cookie.address,
+
+            name: realm_name,
+            reserved: [0; 6],
+            authority: Some(realm_authority.pubkey()),
+            config: RealmConfig {
+                council_mint: Some(council_mint_cookie.address),
+                reserved: [0; 6],
+                min_community_weight_to_create_governance,
+                community_mint_max_vote_weight_source,
+                use_community_voter_weight_addin: false,
+                use_max_community_voter_weight_addin: false,
+            },
+            voting_proposal_count: 0,
+            reserved_v2: [0; 128],
+        };
+
+        Ok(RealmCookie {
+            address: realm_key,
+            account,
+            realm_authority,
+            community_mint_cookie,
+            council_mint_cookie: Some(council_mint_cookie),
+        })
+    }
+
+    /// Plants a realm for a community mint spl-governance cannot create realms for itself.
+    /// Its council mint is an SPL Token mint, so proposals can be created in it
+    #[allow(dead_code)]
+    pub async fn with_realm_for_mint(
+        &mut self,
+        community_mint_cookie: MintCookie,
+    ) -> Result<RealmCookie, TransportError> {
+        let realm_authority = Keypair::new();
+        let council_mint_cookie = self.bench.with_mint().await?;
+
+        self.next_id += 1;
+        let realm_name = format!("Realm #{}", self.next_id).to_string();
+        let realm_key = get_realm_address(&self.program_id, &realm_name);
+
+        let account = RealmV2 {
+            account_type: GovernanceAccountType::RealmV2,
+            community_mint: community_mint_cookie.address,
+
+            name: realm_name,
+            reserved: [0; 6],
+            authority: Some(realm_authority.pubkey()),
+            config: RealmConfig {
+                council_mint: Some(council_mint_cookie.address),
+                reserved: [0; 6],
+                min_community_weight_to_create_governance: 1,
+                community_mint_max_vote_weight_source:
+                    MintMaxVoteWeightSource::FULL_SUPPLY_FRACTION,
+                use_community_voter_weight_addin: self.community_voter_weight_addin.is_some(),
+                use_max_community_voter_weight_addin: self
+                    .max_community_voter_weight_addin
+                    .is_some(),
+            },
+            voting_proposal_count: 0,
+            reserved_v2: [0; 128],
+        };
+
+        self.bench
+            .set_borsht_account(&account, realm_key, self.program_id)
+            .await?;
+
+        Ok(RealmCookie {
+            address: realm_key,
+            account,
+            realm_authority,
+            community_mint_cookie,
+            council_mint_cookie: Some(council_mint_cookie),
+        })
+    }
+}
@@ -5,16 +5,16 @@ use solana_program_test::ProgramTest;
 use solana_sdk::{signature::Keypair, signer::Signer, transport::TransportError};
 use spl_governance::{
     instruction::{
-        cast_vote, create_governance, create_proposal, create_realm, create_token_owner_record,
+        cast_vote, create_governance, create_proposal, create_token_owner_record,
         deposit_governing_tokens, relinquish_vote, set_governance_delegate, sign_off_proposal,
     },
     state::{
-        enums::{GovernanceAccountType, MintMaxVoteWeightSource, ProposalState, VoteTipping},
-        governance::get_governance_address,
-        proposal::{get_proposal_address, ProposalV2},
-        realm::{get_realm_address, RealmConfig, RealmV2},
+        enums::{GovernanceAccountType, ProposalState, VoteTipping},
+        governance::{get_governance_address, GovernanceV2},
+        proposal::{get_proposal_address, ProposalV2, VoteType},
+        realm::RealmV2,
         token_owner_record::{get_token_owner_record_address, TokenOwnerRecordV2},
-        vote_record::VoteChoice,
+        vote_record::{Vote, VoteChoice, VoteRecordV2},
     },
 };
 
@@ -64,7 +64,12 @@ impl GovernanceTest {
 
     #[allow(dead_code)]
     pub fn add_program(program_test: &mut ProgramTest) {
-        program_test.add_program("spl_governance", Self::program_id(), None);
+        let program_name = if cfg!(feature = "upstream-governance") {
+            "spl_governance_upstream"
+        } else {
+            "spl_governance"
+        };
+        program_test.add_program(program_name, Self::program_id(), None);
     }
 
     #[allow(dead_code)]
@@ -83,77 +88,50 @@ impl GovernanceTest {
     }
 
     #[allow(dead_code)]
-    pub async fn with_realm(&mut self) -> Result<RealmCookie, TransportError> {
-        let realm_authority = Keypair::new();
-
-        let community_mint_cookie = self.bench.with_mint().await?;
-        let council_mint_cookie = self.bench.with_mint().await?;
-
-        self.next_id += 1;
-        let realm_name = format!("Realm #{}", self.next_id).to_string();
-
-        let min_community_weight_to_create_governance = 1;
-        let community_mint_max_vote_weight_source = MintMaxVoteWeightSource::FULL_SUPPLY_FRACTION;
-
-        let realm_key = get_realm_address(&self.program_id, &realm_name);
-
-        let create_realm_ix = create_realm(
-            &self.program_id,
-            &realm_authority.pubkey(),
-            &community_mint_cookie.address,
-            &self.bench.payer.pubkey(),
-            Some(council_mint_cookie.address),
-            self.community_voter_weight_addin,
-            self.max_community_voter_weight_addin,
-            realm_name.clone(),
-            min_community_weight_to_create_governance,
-            community_mint_max_vote_weight_source.clone(),
-        );
-
-        self.bench
-            .process_transaction(&[create_realm_ix], None)
-            .await?;
+    pub async fn with_proposal(
+        &mut self,
+        realm_cookie: &RealmCookie,
+    ) -> Result<ProposalCookie, TransportError> {
+        self.with_proposal_using_options(
+            realm_cookie,
+            VoteType::SingleChoice,
+            vec!["Yes".to_string()],
+        )
+        .await
+    }
 
-        let account = RealmV2 {
-            account_type: GovernanceAccountType::RealmV2,
-            community_mint: community_mint_cookie.address,
-
-            name: realm_name,
-            reserved: [0; 6],
-            authority: Some(realm_authority.pubkey()),
-            config: RealmConfig {
-                council_mint: Some(council_mint_cookie.address),
-                reserved: [0; 6],
-                min_community_weight_to_create_governance,
-                community_mint_max_vote_weight_source,
-                use_community_voter_weight_addin: false,
-                use_max_community_voter_weight_addin: false,
+    #[allow(dead_code)]
+    pub async fn with_multi_choice_proposal(
+        &mut self,
+        realm_cookie: &RealmCookie,
+    ) -> Result<ProposalCookie, TransportError> {
+        self.with_proposal_using_options(
+            realm_cookie,
+            VoteType::MultiChoice {
+                max_voter_options: 3,
+                max_winning_options: 3,
             },
-            voting_proposal_count: 0,
-            reserved_v2: [0; 128],
-        };
-
-        Ok(RealmCookie {
-            address: realm_key,
-            account,
-            realm_authority,
-            community_mint_cookie,
-            council_mint_cookie: Some(council_mint_cookie),
-        })
+            vec!["A".to_string(), "B".to_string(), "C".to_string()],
+        )
+        .await
     }
 
     #[allow(dead_code)]
-    pub async fn with_proposal(
+    pub async fn with_proposal_using_options(
         &mut self,
         realm_cookie: &RealmCookie,
+        vote_type: VoteType,
+        options: Vec<String>,
     ) -> Result<ProposalCookie, TransportError> {
+        let token_owner = self.bench.payer.pubkey();
+        let council_mint_cookie = realm_cookie.council_mint_cookie.as_ref().unwrap();
+
+        // The governed account is a council token account, as the community mint may belong to
+        // a token program the bench cannot create accounts for.
         let token_account_cookie = self
             .bench
-            .with_token_account(&realm_cookie.account.community_mint)
+            .with_token_account(&council_mint_cookie.address)
             .await?;
-
-        let token_owner = self.bench.payer.pubkey();
-        let council_mint_cookie = realm_cookie.council_mint_cookie.as_ref().unwrap();
         let governing_token_mint = council_mint_cookie.address;
 
         let governing_token_account_cookie = self
@@ -250,8 +228,8 @@ impl GovernanceTest {
             String::from("Proposal #1"),
             String::from("Proposal #1 link"),
             &proposal_governing_token_mint,
-            spl_governance::state::proposal::VoteType::SingleChoice,
-            vec!["Yes".to_string()],
+            vote_type.clone(),
+            options,
             true,
             0_u32,
         );
@@ -277,7 +255,7 @@ impl GovernanceTest {
             token_owner_record: proposal_owner_record_key,
             signatories_count: 1,
             signatories_signed_off_count: 1,
-            vote_type: spl_governance::state::proposal::VoteType::SingleChoice,
+            vote_type,
             options: vec![],
             deny_vote_weight: Some(1),
             veto_vote_weight: 0,
@@ -351,6 +329,63 @@ impl GovernanceTest {
         })
     }
 
+    #[allow(dead_code)]
+    pub async fn with_council_token_owner_record(
+        &mut self,
+        realm_cookie: &RealmCookie,
+        token_owner_cookie: &WalletCookie,
+        amount: u64,
+    ) -> Result<TokenOwnerRecordCookie, TransportError> {
+        let council_mint_cookie = realm_cookie.council_mint_cookie.as_ref().unwrap();
+        let governing_token_mint = council_mint_cookie.address;
+
+        let governing_token_account_cookie = self
+            .bench
+            .with_tokens(council_mint_cookie, &token_owner_cookie.address, amount)
+            .await?;
+
+        let token_owner_record_key = get_token_owner_record_address(
+            &self.program_id,
+            &realm_cookie.address,
+            &governing_token_mint,
+            &token_owner_cookie.address,
+        );
+
+        let deposit_ix = deposit_governing_tokens(
+            &self.program_id,
+            &realm_cookie.address,
+            &governing_token_account_cookie.address,
+            &token_owner_cookie.address,
+            &token_owner_cookie.address,
+            &self.bench.payer.pubkey(),
+            amount,
+            &governing_token_mint,
+        );
+
+        self.bench
+            .process_transaction(&[deposit_ix], Some(&[&token_owner_cookie.signer]))
+            .await?;
+
+        let account = TokenOwnerRecordV2 {
+            account_type: GovernanceAccountType::TokenOwnerRecordV2,
+            realm: realm_cookie.address,
+            governing_token_mint,
+            governing_token_owner: token_owner_cookie.address,
+            governing_token_deposit_amount: amount,
+            unrelinquished_votes_count: 0,
+            total_votes_count: 0,
+            outstanding_proposal_count: 0,
+            reserved: [0; 7],
+            governance_delegate: None,
+            reserved_v2: [0; 128],
+        };
+
+        Ok(TokenOwnerRecordCookie {
+            address: token_owner_record_key,
+            account,
+        })
+    }
+
     pub async fn set_delegate(
         &mut self,
         owner: &WalletCookie,
@@ -380,6 +415,30 @@ impl GovernanceTest {
         wallet: &WalletCookie,
         voter_owner_record_cookie: &TokenOwnerRecordCookie,
         vwr: &VoterWeightRecordCookie,
+    ) -> Result<(), TransportError> {
+        self.cast_vote_using(
+            realm,
+            proposal,
+            wallet,
+            voter_owner_record_cookie,
+            vwr,
+            Vote::Approve(vec![VoteChoice {
+                rank: 0,
+                weight_percentage: 100,
+            }]),
+        )
+        .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn cast_vote_using(
+        &mut self,
+        realm: &RealmCookie,
+        proposal: &ProposalCookie,
+        wallet: &WalletCookie,
+        voter_owner_record_cookie: &TokenOwnerRecordCookie,
+        vwr: &VoterWeightRecordCookie,
+        vote: Vote,
     ) -> Result<(), TransportError> {
         let cast_vote_ix = cast_vote(
             &self.program_id,
@@ -393,10 +452,7 @@ impl GovernanceTest {
             &self.bench.payer.pubkey(),
             Some(vwr.address),
             None,
-            spl_governance::state::vote_record::Vote::Approve(vec![VoteChoice {
-                rank: 0,
-                weight_percentage: 100,
-            }]),
+            vote,
         );
 
         self.bench
@@ -407,22 +463,35 @@ impl GovernanceTest {
     }
 
     #[allow(dead_code)]
+    #[cfg_attr(feature = "upstream-governance", allow(unused_variables))]
     pub async fn relinquish_vote(
         &mut self,
+        realm_cookie: &RealmCookie,
         proposal_cookie: &ProposalCookie,
         token_owner_cookie: &WalletCookie,
         token_owner_record_cookie: &TokenOwnerRecordCookie,
     ) -> Result<(), TransportError> {
+        #[cfg(not(feature = "upstream-governance"))]
         let relinquish_vote_ix = relinquish_vote(
             &self.program_id,
+            &realm_cookie.address,
             &proposal_cookie.account.governance,
             &proposal_cookie.address,
             &token_owner_record_cookie.address,
             &proposal_cookie.account.governing_token_mint,
-            &token_owner_record_cookie.account.governing_token_owner,
+            Some(token_owner_record_cookie.account.governing_token_owner),
             Some(self.bench.payer.pubkey()),
             None,
-            None,
+        );
+        #[cfg(feature = "upstream-governance")]
+        let relinquish_vote_ix = relinquish_vote(
+            &self.program_id,
+            &proposal_cookie.account.governance,
+            &proposal_cookie.address,
+            &token_owner_record_cookie.address,
+            &proposal_cookie.account.governing_token_mint,
+            Some(token_owner_record_cookie.account.governing_token_owner),
+            Some(self.bench.payer.pubkey()),
         );
 
         self.bench
@@ -432,6 +501,50 @@ impl GovernanceTest {
         Ok(())
     }
 
+    /// Rewrites the proposal and its governance in their V1 layouts, as found in older realms
+    #[allow(dead_code)]
+    pub async fn downgrade_proposal(
+        &mut self,
+        proposal_cookie: &ProposalCookie,
+    ) -> Result<(), TransportError> {
+        let mut governance = self
+            .bench
+            .get_borsh_account::<GovernanceV2>(&proposal_cookie.account.governance)
+            .await;
+        governance.account_type = GovernanceAccountType::GovernanceV1;
+        let mut data = vec![];
+        governance.serialize(&mut data).unwrap();
+        self.bench
+            .set_account(data, proposal_cookie.account.governance, self.program_id)
+            .await?;
+
+        let mut proposal = self.get_proposal(&proposal_cookie.address).await;
+        proposal.account_type = GovernanceAccountType::ProposalV1;
+        let mut data = vec![];
+        proposal.serialize(&mut data).unwrap();
+        self.bench
+            .set_account(data, proposal_cookie.address, self.program_id)
+            .await
+    }
+
+    /// Rewrites the vote record in its V1 layout, as found in older realms
+    #[allow(dead_code)]
+    pub async fn downgrade_vote_record(
+        &mut self,
+        vote_record_address: &Pubkey,
+    ) -> Result<(), TransportError> {
+        let mut vote_record = self
+            .bench
+            .get_borsh_account::<VoteRecordV2>(vote_record_address)
+            .await;
+        vote_record.account_type = GovernanceAccountType::VoteRecordV1;
+        let mut data = vec![];
+        vote_record.serialize(&mut data).unwrap();
+        self.bench
+            .set_account(data, *vote_record_address, self.program_id)
+            .await
+    }
+
     #[allow(dead_code)]
     pub async fn get_proposal(&mut self, proposal_key: &Pubkey) -> ProposalV2 {
         self.bench
@@ -0,0 +1,175 @@
+//! Realm fixtures for upstream spl-governance 3.0, which configures voter weight addins in
+//! a separate RealmConfigAccount and can set them for the council mint too.
+use anchor_lang::prelude::Pubkey;
+use solana_sdk::{signature::Keypair, signer::Signer, transport::TransportError};
+use spl_governance::{
+    instruction::{create_realm, set_realm_config},
+    state::{
+        enums::{GovernanceAccountType, MintMaxVoteWeightSource},
+        realm::{get_realm_address, GoverningTokenConfigAccountArgs, RealmConfig, RealmV2},
+        realm_config::GoverningTokenType,
+    },
+};
+
+use crate::program_test::{
+    governance_test::{GovernanceTest, RealmCookie},
+    program_test_bench::MintCookie,
+};
+
+impl GovernanceTest {
+    #[allow(dead_code)]
+    pub async fn with_realm(&mut self) -> Result<RealmCookie, TransportError> {
+        let realm_authority = Keypair::new();
+
+        let community_mint_cookie = self.bench.with_mint().await?;
+        let council_mint_cookie = self.bench.with_mint().await?;
+
+        self.next_id += 1;
+        let realm_name = format!("Realm #{}", self.next_id).to_string();
+
+        let min_community_weight_to_create_governance = 1;
+        let community_mint_max_vote_weight_source = MintMaxVoteWeightSource::FULL_SUPPLY_FRACTION;
+
+        let realm_key = get_realm_address(&self.program_id, &realm_name);
+
+        let create_realm_ix = create_realm(
+            &self.program_id,
+            &realm_authority.pubkey(),
+            &community_mint_cookie.address,
+            &self.bench.payer.pubkey(),
+            Some(council_mint_cookie.address),
+            Some(GoverningTokenConfigAccountArgs {
+                voter_weight_addin: self.community_voter_weight_addin,
+                max_voter_weight_addin: self.max_community_voter_weight_addin,
+                token_type: GoverningTokenType::Liquid,
+            }),
+            None,
+            realm_name.clone(),
+            min_community_weight_to_create_governance,
+            community_mint_max_vote_weight_source.clone(),
+        );
+
+        self.bench
+            .process_transaction(&[create_realm_ix], None)
+            .await?;
+
+        let account = RealmV2 {
+            account_type: GovernanceAccountType::RealmV2,
+            community_mint: community_mint_cookie.address,
+
+            name: realm_name,
+            reserved: [0; 6],
+            authority: Some(realm_authority.pubkey()),
+            config: RealmConfig {
+                council_mint: Some(council_mint_cookie.address),
+                reserved: [0; 6],
+                min_community_weight_to_create_governance,
+                community_mint_max_vote_weight_source,
+                legacy1: 0,
+                legacy2: 0,
+            },
+            voting_proposal_count: 0,
+            reserved_v2: [0; 128],
+        };
+
+        Ok(RealmCookie {
+            address: realm_key,
+            account,
+            realm_authority,
+            community_mint_cookie,
+            council_mint_cookie: Some(council_mint_cookie),
+        })
+    }
+
+    /// Plants a realm for a community mint spl-governance cannot create realms for itself.
+    /// Its council mint is an SPL Token mint, so proposals can be created in it.
+    /// Its RealmConfigAccount is not planted, so it has no voter weight addins.
+    #[allow(dead_code)]
+    pub async fn with_realm_for_mint(
+        &mut self,
+        community_mint_cookie: MintCookie,
+    ) -> Result<RealmCookie, TransportError> {
+        let realm_authority = Keypair::new();
+        let council_mint_cookie = self.bench.with_mint().await?;
+
+        self.next_id += 1;
+        let realm_name = format!("Realm #{}", self.next_id).to_string();
+        let realm_key = get_realm_address(&self.program_id, &realm_name);
+
+        let account = RealmV2 {
+            account_type: GovernanceAccountType::RealmV2,
+            community_mint: community_mint_cookie.address,
+
+            name: realm_name,
+            reserved: [0; 6],
+            authority: Some(realm_authority.pubkey()),
+            config: RealmConfig {
+                council_mint: Some(council_mint_cookie.address),
+                reserved: [0; 6],
+                min_community_weight_to_create_governance: 1,
+                community_mint_max_vote_weight_source:
+                    MintMaxVoteWeightSource::FULL_SUPPLY_FRACTION,
+                legacy1: 0,
+                legacy2: 0,
+            },
+            voting_proposal_count: 0,
+            reserved_v2: [0; 128],
+        };
+
+        self.bench
+            .set_borsht_account(&account, realm_key, self.program_id)
+            .await?;
+
+        Ok(RealmCookie {
+            address: realm_key,
+            account,
+            realm_authority,
+            community_mint_cookie,
+            council_mint_cookie: Some(council_mint_cookie),
+        })
+    }
+
+    /// Sets the realm's council voter weight addin, which spl-governance then reads for council
+    /// votes and vetoes. Proposals should be created first, as their council owner records
+    /// have no voter weight records.
+    #[allow(dead_code)]
+    pub async fn set_council_voter_weight_addin(
+        &mut self,
+        realm_cookie: &RealmCookie,
+        council_voter_weight_addin: Option<Pubkey>,
+    ) -> Result<(), TransportError> {
+        let set_realm_config_ix = set_realm_config(
+            &self.program_id,
+            &realm_cookie.address,
+            &realm_cookie.realm_authority.pubkey(),
+            realm_cookie.account.config.council_mint,
+            &self.bench.payer.pubkey(),
+            Some(GoverningTokenConfigAccountArgs {
+                voter_weight_addin: self.community_voter_weight_addin,
+                max_voter_weight_addin: self.max_community_voter_weight_addin,
+                token_type: GoverningTokenType::Liquid,
+            }),
+            Some(GoverningTokenConfigAccountArgs {
+                voter_weight_addin: council_voter_weight_addin,
+                max_voter_weight_addin: None,
+                token_type: GoverningTokenType::Liquid,
+            }),
+            realm_cookie
+                .account
+                .config
+                .min_community_weight_to_create_governance,
+            realm_cookie
+                .account
+                .config
+                .community_mint_max_vote_weight_source
+                .clone(),
+        );
+
+        self.bench
+            .process_transaction(
+                &[set_realm_config_ix],
+                Some(&[&realm_cookie.realm_authority]),
+            )
+            .await
+    }
+}
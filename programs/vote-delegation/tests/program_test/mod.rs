@@ -1,4 +1,8 @@
 pub mod delegation_test;
+#[cfg(not(feature = "upstream-governance"))]
+pub mod governance_fork;
 pub mod governance_test;
+#[cfg(feature = "upstream-governance")]
+pub mod governance_upstream;
 pub mod program_test_bench;
 pub mod tools;
@@ -9,7 +9,7 @@ use anchor_spl::{
     associated_token::get_associated_token_address,
     token::{spl_token, Token},
 };
-use solana_program::{borsh::try_from_slice_unchecked, system_program};
+use solana_program::{borsh::try_from_slice_unchecked, program_option::COption, system_program};
 use solana_program_test::{ProgramTest, ProgramTestContext};
 use solana_sdk::{
     account::{Account, AccountSharedData, ReadableAccount, WritableAccount},
@@ -113,6 +113,42 @@ impl ProgramTestBench {
             })
     }
 
+    /// Simulates a transaction and returns the compute units it consumed
+    #[allow(dead_code)]
+    pub async fn simulate_transaction_units(
+        &self,
+        instructions: &[Instruction],
+        signers: Option<&[&Keypair]>,
+    ) -> Result<u64, TransportError> {
+        let mut context = self.context.borrow_mut();
+
+        let mut transaction =
+            Transaction::new_with_payer(instructions, Some(&context.payer.pubkey()));
+
+        let mut all_signers = vec![&context.payer];
+
+        if let Some(signers) = signers {
+            all_signers.extend_from_slice(signers);
+        }
+
+        transaction.sign(&all_signers, context.last_blockhash);
+
+        let simulation = context
+            .banks_client
+            .simulate_transaction(transaction)
+            .await
+            .map_err(|e| TransportError::Custom(e.to_string()))?;
+
+        if let Some(Err(err)) = simulation.result {
+            return Err(TransportError::TransactionError(err));
+        }
+
+        Ok(simulation
+            .simulation_details
+            .map(|details| details.units_consumed)
+            .unwrap_or_default())
+    }
+
     pub async fn get_clock(&self) -> solana_program::clock::Clock {
         self.context
             .borrow_mut()
@@ -172,6 +208,39 @@ impl ProgramTestBench {
         })
     }
 
+    /// Plants a mint laid out as Token-2022 lays out mints with extensions, owned by owner
+    #[allow(dead_code)]
+    pub async fn with_extended_mint(&self, owner: &Pubkey) -> Result<MintCookie, TransportError> {
+        let mint_address = Pubkey::new_unique();
+        let mint_authority = Keypair::new();
+
+        let mint = spl_token::state::Mint {
+            mint_authority: COption::Some(mint_authority.pubkey()),
+            supply: 0,
+            decimals: 0,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        };
+        let mut data = vec![0; spl_token::state::Mint::LEN];
+        spl_token::state::Mint::pack(mint, &mut data).unwrap();
+
+        // Padding up to the size of a token account, the Mint account type and a
+        // MintCloseAuthority extension.
+        data.resize(spl_token::state::Account::LEN, 0);
+        data.push(1);
+        data.extend_from_slice(&3u16.to_le_bytes());
+        data.extend_from_slice(&32u16.to_le_bytes());
+        data.extend_from_slice(mint_authority.pubkey().as_ref());
+
+        self.set_account(data, mint_address, *owner).await?;
+
+        Ok(MintCookie {
+            address: mint_address,
+            mint_authority,
+            freeze_authority: None,
+        })
+    }
+
     #[allow(dead_code)]
     pub async fn create_mint(
         &self,
@@ -0,0 +1,169 @@
+#![cfg(not(feature = "upstream-governance"))]
+
+use crate::program_test::tools::assert_vote_delegation_err;
+
+use anchor_lang::prelude::Pubkey;
+use program_test::delegation_test::DelegationTest;
+
+use solana_program_test::tokio;
+use solana_sdk::transport::TransportError;
+use spl_governance::state::proposal::ProposalV2;
+use vote_delegation::{
+    error::DelegationError,
+    state::{
+        vote::{Vote, VoteChoice},
+        voter_weight_record::{VoterWeightAction, VoterWeightRecord},
+    },
+};
+
+mod program_test;
+
+type TestOutcome = Result<(), TransportError>;
+
+fn approve() -> Vote {
+    Vote::Approve(vec![VoteChoice {
+        rank: 0,
+        weight_percentage: 100,
+    }])
+}
+
+#[tokio::test]
+async fn test_aggregate_and_cast_vote() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .with_unassigned_tokens(&realm_cookie)
+        .await?;
+    let token_owner_record = vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+
+    // Act
+    vote_delegation_test
+        .aggregate_and_cast_vote(
+            &realm_cookie,
+            &wallet,
+            &vwr_cookie,
+            &proposal,
+            &token_owner_record,
+            &[&delegator],
+            approve(),
+        )
+        .await?;
+
+    // Assert
+    let vwr_record = vote_delegation_test
+        .bench
+        .get_anchor_account::<VoterWeightRecord>(vwr_cookie.address)
+        .await;
+
+    assert_eq!(vwr_record.voter_weight, 10);
+
+    let proposal_record = vote_delegation_test
+        .bench
+        .get_borsh_account::<ProposalV2>(&proposal.address)
+        .await;
+
+    assert_eq!(proposal_record.options[0].vote_weight, 10);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_aggregate_and_cast_vote_with_undelegated_weight_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .with_unassigned_tokens(&realm_cookie)
+        .await?;
+    let token_owner_record = vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            Pubkey::new_unique(),
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+
+    // Act
+    let err = vote_delegation_test
+        .aggregate_and_cast_vote(
+            &realm_cookie,
+            &wallet,
+            &vwr_cookie,
+            &proposal,
+            &token_owner_record,
+            &[&delegator],
+            approve(),
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::VoterWeightNotDelegatedToDelegate);
+
+    let proposal_record = vote_delegation_test
+        .bench
+        .get_borsh_account::<ProposalV2>(&proposal.address)
+        .await;
+
+    assert_eq!(proposal_record.options[0].vote_weight, 0);
+
+    Ok(())
+}
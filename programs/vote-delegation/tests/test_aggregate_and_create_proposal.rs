@@ -0,0 +1,169 @@
+#![cfg(not(feature = "upstream-governance"))]
+
+use crate::program_test::tools::assert_vote_delegation_err;
+
+use anchor_lang::prelude::Pubkey;
+use program_test::delegation_test::DelegationTest;
+
+use solana_program_test::tokio;
+use solana_sdk::transport::TransportError;
+use spl_governance::state::proposal::VoteType as GovernanceVoteType;
+use vote_delegation::{
+    error::DelegationError,
+    state::{
+        vote::VoteType,
+        voter_weight_record::{VoterWeightAction, VoterWeightRecord},
+    },
+};
+
+mod program_test;
+
+type TestOutcome = Result<(), TransportError>;
+
+#[tokio::test]
+async fn test_aggregate_and_create_proposal() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    let token_owner_record = vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    // The governance already holds this proposal, so the delegate's is the second.
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.account.governance,
+            VoterWeightAction::CreateProposal,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CreateProposal,
+            proposal.account.governance,
+        )
+        .await?;
+
+    // Act
+    let new_proposal = vote_delegation_test
+        .aggregate_and_create_proposal(
+            &realm_cookie,
+            &wallet,
+            &vwr_cookie,
+            &token_owner_record,
+            &[&delegator],
+            1,
+            VoteType::MultiChoice {
+                max_voter_options: 2,
+                max_winning_options: 2,
+            },
+            vec!["A".to_string(), "B".to_string()],
+        )
+        .await?;
+
+    // Assert
+    let vwr_record = vote_delegation_test
+        .bench
+        .get_anchor_account::<VoterWeightRecord>(vwr_cookie.address)
+        .await;
+
+    assert_eq!(vwr_record.voter_weight, 10);
+
+    let proposal_record = vote_delegation_test
+        .governance
+        .get_proposal(&new_proposal)
+        .await;
+
+    assert_eq!(
+        proposal_record.token_owner_record,
+        token_owner_record.address
+    );
+    assert_eq!(proposal_record.name, "Delegated proposal");
+    assert_eq!(proposal_record.description_link, "Delegated proposal link");
+    assert_eq!(
+        proposal_record.vote_type,
+        GovernanceVoteType::MultiChoice {
+            max_voter_options: 2,
+            max_winning_options: 2,
+        }
+    );
+    assert_eq!(proposal_record.options.len(), 2);
+    assert_eq!(proposal_record.options[1].label, "B");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_aggregate_and_create_proposal_with_undelegated_weight_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    let token_owner_record = vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.account.governance,
+            VoterWeightAction::CreateProposal,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            Pubkey::new_unique(),
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CreateProposal,
+            proposal.account.governance,
+        )
+        .await?;
+
+    // Act
+    let err = vote_delegation_test
+        .aggregate_and_create_proposal(
+            &realm_cookie,
+            &wallet,
+            &vwr_cookie,
+            &token_owner_record,
+            &[&delegator],
+            1,
+            VoteType::SingleChoice,
+            vec!["Yes".to_string()],
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::VoterWeightNotDelegatedToDelegate);
+
+    Ok(())
+}
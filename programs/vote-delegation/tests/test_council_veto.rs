@@ -0,0 +1,149 @@
+#[cfg(feature = "upstream-governance")]
+extern crate spl_governance_upstream as spl_governance;
+
+use program_test::delegation_test::DelegationTest;
+
+use solana_program_test::tokio;
+use solana_sdk::transport::TransportError;
+use vote_delegation::state::{
+    delegation::Delegation,
+    voter_weight_record::{VoterWeightAction, VoterWeightRecord},
+};
+
+mod program_test;
+
+type TestOutcome = Result<(), TransportError>;
+
+#[tokio::test]
+async fn test_aggregate_council_delegation_for_community_proposal() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let council_mint = realm_cookie.council_mint_cookie.as_ref().unwrap().address;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr_for_mint(
+            &realm_cookie,
+            &wallet,
+            &council_mint,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program_for_mint(&realm_cookie, &council_mint)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_council_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+
+    // Act
+    vote_delegation_test
+        .aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &[&delegator])
+        .await?;
+
+    // Assert
+    let vwr = vote_delegation_test
+        .bench
+        .get_anchor_account::<VoterWeightRecord>(vwr_cookie.address)
+        .await;
+
+    assert_eq!(vwr.governing_token_mint, council_mint);
+    assert_eq!(vwr.weight_action_target, Some(proposal.address));
+    assert_eq!(vwr.voter_weight, 10);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_revoke_vote_from_council_veto_before_vote_cast() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let council_mint = realm_cookie.council_mint_cookie.as_ref().unwrap().address;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    let token_owner_record = vote_delegation_test
+        .governance
+        .with_council_token_owner_record(&realm_cookie, &wallet, 1)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr_for_mint(
+            &realm_cookie,
+            &wallet,
+            &council_mint,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program_for_mint(&realm_cookie, &council_mint)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_council_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+    vote_delegation_test
+        .aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &[&delegator])
+        .await?;
+
+    vote_delegation_test.bench.advance_clock_a_lot().await;
+
+    // Act
+    vote_delegation_test
+        .revoke_vote(
+            &realm_cookie,
+            &delegator,
+            &vwr_cookie,
+            &proposal,
+            &token_owner_record,
+        )
+        .await?;
+
+    // Assert
+    let delegation_record = vote_delegation_test
+        .bench
+        .get_account(&Delegation::get_pda_address(
+            &realm_cookie.address,
+            &council_mint,
+            &delegator.wallet.address,
+            &proposal.address,
+            Some(VoterWeightAction::CastVote),
+        ))
+        .await;
+
+    assert!(delegation_record.is_none());
+
+    let vwr = vote_delegation_test
+        .bench
+        .get_anchor_account::<VoterWeightRecord>(vwr_cookie.address)
+        .await;
+
+    assert_eq!(vwr.voter_weight, 0);
+
+    Ok(())
+}
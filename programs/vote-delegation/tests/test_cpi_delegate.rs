@@ -0,0 +1,200 @@
+#![cfg(not(feature = "upstream-governance"))]
+
+use anchor_lang::prelude::{AccountInfo, AccountMeta, Pubkey};
+use program_test::{
+    delegation_test::DelegationTest, governance_test::GovernanceTest,
+    program_test_bench::WalletCookie,
+};
+
+use solana_program::{entrypoint::ProgramResult, instruction::Instruction, program::invoke_signed};
+use solana_program_test::{processor, tokio};
+use solana_sdk::{account::Account, signature::Keypair, signer::Signer, transport::TransportError};
+use spl_governance::{
+    instruction::cast_vote,
+    state::vote_record::{Vote, VoteChoice},
+};
+use vote_delegation::{
+    cpi_helpers::{self, DelegatorAccounts},
+    state::voter_weight_record::{VoterWeightAction, VoterWeightRecord},
+};
+
+mod program_test;
+
+type TestOutcome = Result<(), TransportError>;
+
+fn proxy_program_id() -> Pubkey {
+    Pubkey::new_from_array([7; 32])
+}
+
+fn get_proxy_delegate_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"delegate"], &proxy_program_id())
+}
+
+/// A mock of a program which owns a delegate, such as a multisig or a realm's native
+/// treasury. It forwards the instruction to the program given as the first account, signing
+/// for its delegate PDA.
+fn process_proxy_instruction(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let (delegate, bump) = get_proxy_delegate_address();
+    let instruction = Instruction {
+        program_id: *accounts[0].key,
+        accounts: accounts[1..]
+            .iter()
+            .map(|info| AccountMeta {
+                pubkey: *info.key,
+                is_signer: info.is_signer || *info.key == delegate,
+                is_writable: info.is_writable,
+            })
+            .collect(),
+        data: data.to_vec(),
+    };
+
+    invoke_signed(&instruction, accounts, &[&[b"delegate", &[bump]]])
+}
+
+fn proxy(instruction: Instruction) -> Instruction {
+    let (delegate, _) = get_proxy_delegate_address();
+    let mut accounts = vec![AccountMeta::new_readonly(instruction.program_id, false)];
+    accounts.extend(instruction.accounts.into_iter().map(|meta| AccountMeta {
+        is_signer: meta.is_signer && meta.pubkey != delegate,
+        ..meta
+    }));
+
+    Instruction {
+        program_id: proxy_program_id(),
+        accounts,
+        data: instruction.data,
+    }
+}
+
+#[tokio::test]
+async fn test_program_delegate_aggregates_and_votes() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_with_programs(|program_test| {
+        program_test.add_program(
+            "proxy",
+            proxy_program_id(),
+            processor!(process_proxy_instruction),
+        )
+    })
+    .await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    vote_delegation_test
+        .with_unassigned_tokens(&realm_cookie)
+        .await?;
+    let mint = realm_cookie.community_mint_cookie.address;
+    let payer = vote_delegation_test.bench.payer.pubkey();
+
+    // The delegate can only sign through the proxy program, so this keypair is never used.
+    let delegate = WalletCookie {
+        address: get_proxy_delegate_address().0,
+        account: Account::default(),
+        signer: Keypair::new(),
+    };
+    let token_owner_record = vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &delegate)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            delegate.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+
+    vote_delegation_test
+        .bench
+        .process_transaction(
+            &[cpi_helpers::create_voter_weight_record(
+                &GovernanceTest::program_id(),
+                &realm_cookie.address,
+                &mint,
+                &delegate.address,
+                &payer,
+                VoterWeightAction::CastVote,
+                proposal.address,
+            )],
+            None,
+        )
+        .await?;
+    let vwr_address = VoterWeightRecord::get_pda_address(
+        &realm_cookie.address,
+        &mint,
+        &delegate.address,
+        &proposal.address,
+        Some(VoterWeightAction::CastVote),
+    );
+
+    // Act
+    let aggregate_ix = cpi_helpers::update_voter_weight_record(
+        &GovernanceTest::program_id(),
+        &realm_cookie.address,
+        &mint,
+        &delegate.address,
+        &payer,
+        VoterWeightAction::CastVote,
+        proposal.address,
+        &[DelegatorAccounts {
+            governing_token_owner: delegator.wallet.address,
+            voter_weight_record: delegator.source_vwr.address,
+            token_owner_record: delegator.token_owner_record.address,
+        }],
+    );
+    let cast_vote_ix = cast_vote(
+        &GovernanceTest::program_id(),
+        &realm_cookie.address,
+        &proposal.account.governance,
+        &proposal.address,
+        &proposal.account.token_owner_record,
+        &token_owner_record.address,
+        &delegate.address,
+        &mint,
+        &payer,
+        Some(vwr_address),
+        None,
+        Vote::Approve(vec![VoteChoice {
+            rank: 0,
+            weight_percentage: 100,
+        }]),
+    );
+
+    vote_delegation_test
+        .bench
+        .process_transaction(
+            &[proxy(aggregate_ix), proxy(cast_vote_ix)],
+            Some(&[&vote_delegation_test.bench.payer]),
+        )
+        .await?;
+
+    // Assert
+    let vwr_record = vote_delegation_test
+        .bench
+        .get_anchor_account::<VoterWeightRecord>(vwr_address)
+        .await;
+
+    assert_eq!(vwr_record.voter_weight, 10);
+
+    let proposal_record = vote_delegation_test
+        .governance
+        .get_proposal(&proposal.address)
+        .await;
+
+    assert_eq!(proposal_record.options[0].vote_weight, 10);
+
+    Ok(())
+}
@@ -0,0 +1,357 @@
+#[cfg(feature = "upstream-governance")]
+extern crate spl_governance_upstream as spl_governance;
+
+use crate::program_test::tools::assert_vote_delegation_err;
+
+use program_test::delegation_test::DelegationTest;
+
+use solana_program_test::tokio;
+use solana_sdk::{signature::Keypair, signer::Signer, transport::TransportError};
+use vote_delegation::{
+    error::DelegationError,
+    state::{
+        delegation::Delegation,
+        voter_weight_record::{VoterWeightAction, VoterWeightRecord},
+    },
+};
+
+mod program_test;
+
+type TestOutcome = Result<(), TransportError>;
+
+#[tokio::test]
+async fn test_crank_voter_weight_record() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+
+    vote_delegation_test
+        .aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &[])
+        .await?;
+    let expiry = vote_delegation_test
+        .bench
+        .get_anchor_account::<VoterWeightRecord>(vwr_cookie.address)
+        .await
+        .voter_weight_expiry;
+
+    // Act
+    vote_delegation_test
+        .crank_voter_weight_record(&realm_cookie, &vwr_cookie, &[&delegator])
+        .await?;
+
+    // Assert
+    let vwr_record = vote_delegation_test
+        .bench
+        .get_anchor_account::<VoterWeightRecord>(vwr_cookie.address)
+        .await;
+
+    assert_eq!(vwr_record.voter_weight, 10);
+    // Only the delegate can extend their record.
+    assert_eq!(vwr_record.voter_weight_expiry, expiry);
+
+    let delegation_record = vote_delegation_test
+        .bench
+        .get_anchor_account::<Delegation>(Delegation::get_pda_address(
+            &realm_cookie.address,
+            &realm_cookie.community_mint_cookie.address,
+            &delegator.wallet.address,
+            &proposal.address,
+            Some(VoterWeightAction::CastVote),
+        ))
+        .await;
+
+    assert_eq!(delegation_record.delegate, wallet.address);
+    assert_eq!(
+        delegation_record.rent_payer,
+        vote_delegation_test.bench.payer.pubkey()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_crank_voter_weight_record_then_cast_vote() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .with_unassigned_tokens(&realm_cookie)
+        .await?;
+    let token_owner_record = vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+
+    vote_delegation_test
+        .aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &[])
+        .await?;
+
+    // Act
+    vote_delegation_test
+        .crank_voter_weight_record(&realm_cookie, &vwr_cookie, &[&delegator])
+        .await?;
+    vote_delegation_test
+        .governance
+        .cast_vote(
+            &realm_cookie,
+            &proposal,
+            &wallet,
+            &token_owner_record,
+            &vwr_cookie,
+        )
+        .await?;
+
+    // Assert
+    let proposal_record = vote_delegation_test
+        .governance
+        .get_proposal(&proposal.address)
+        .await;
+
+    assert_eq!(proposal_record.options[0].vote_weight, 10);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_crank_voter_weight_record_not_delegated_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            Keypair::new().pubkey(),
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+    vote_delegation_test
+        .aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &[])
+        .await?;
+
+    // Act
+    let err = vote_delegation_test
+        .crank_voter_weight_record(&realm_cookie, &vwr_cookie, &[&delegator])
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::VoterWeightNotDelegatedToDelegate);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_crank_expired_voter_weight_record_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+
+    // Act
+    let err = vote_delegation_test
+        .crank_voter_weight_record(&realm_cookie, &vwr_cookie, &[&delegator])
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::VoterWeightRecordExpired);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_crank_voter_weight_record_after_vote_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .with_unassigned_tokens(&realm_cookie)
+        .await?;
+    let token_owner_record = vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator1 = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+    let delegator2 = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            20,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+
+    vote_delegation_test
+        .aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &[&delegator1])
+        .await?;
+    vote_delegation_test
+        .governance
+        .cast_vote(
+            &realm_cookie,
+            &proposal,
+            &wallet,
+            &token_owner_record,
+            &vwr_cookie,
+        )
+        .await?;
+
+    // Act
+    let err = vote_delegation_test
+        .crank_voter_weight_record(&realm_cookie, &vwr_cookie, &[&delegator2])
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::CannotIncreaseCastVote);
+
+    Ok(())
+}
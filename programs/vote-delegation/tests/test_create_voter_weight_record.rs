@@ -1,3 +1,9 @@
+#[cfg(feature = "upstream-governance")]
+extern crate spl_governance_upstream as spl_governance;
+
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
 use program_test::{delegation_test::DelegationTest, tools::assert_vote_delegation_err};
 use solana_program::program_pack::IsInitialized;
 use solana_program_test::tokio;
@@ -145,3 +151,78 @@ async fn test_cannot_create_vwr_to_revoke_err() -> TestOutcome {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_create_voter_weight_record_for_token_2022_mint() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let token_2022_program_id =
+        Pubkey::from_str("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb").unwrap();
+    let mint_cookie = vote_delegation_test
+        .bench
+        .with_extended_mint(&token_2022_program_id)
+        .await?;
+    let realm_cookie = vote_delegation_test
+        .governance
+        .with_realm_for_mint(mint_cookie)
+        .await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    let fake_proposal = Keypair::new();
+
+    // Act
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            fake_proposal.pubkey(),
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+
+    // Assert
+    let vwr_record = vote_delegation_test
+        .bench
+        .get_anchor_account::<VoterWeightRecord>(vwr_cookie.address)
+        .await;
+
+    assert!(vwr_record.is_initialized());
+    assert_eq!(
+        vwr_record.governing_token_mint,
+        realm_cookie.community_mint_cookie.address
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_create_voter_weight_record_with_mint_of_other_program_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let mint_cookie = vote_delegation_test
+        .bench
+        .with_extended_mint(&Pubkey::new_unique())
+        .await?;
+    let realm_cookie = vote_delegation_test
+        .governance
+        .with_realm_for_mint(mint_cookie)
+        .await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    let fake_proposal = Keypair::new();
+
+    // Act
+    let err = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            fake_proposal.pubkey(),
+            VoterWeightAction::CastVote,
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::InvalidMintOwner);
+
+    Ok(())
+}
@@ -0,0 +1,658 @@
+#![cfg(not(feature = "upstream-governance"))]
+
+use crate::program_test::tools::assert_vote_delegation_err;
+
+use program_test::delegation_test::DelegationTest;
+
+use solana_program_test::tokio;
+use solana_sdk::{signature::Keypair, signer::Signer, transport::TransportError};
+use spl_governance::state::proposal::ProposalV2;
+use vote_delegation::{
+    error::DelegationError,
+    state::{
+        delegation_ledger::{DelegationLedger, LedgerEntry},
+        settings::{DelegationStore, Settings},
+        voter_weight_record::{VoterWeightAction, VoterWeightRecord},
+    },
+};
+
+mod program_test;
+
+type TestOutcome = Result<(), TransportError>;
+
+#[tokio::test]
+async fn test_update_voter_weight_record_with_ledger() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let fake_proposal = Keypair::new();
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            fake_proposal.pubkey(),
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+    vote_delegation_test
+        .set_delegation_store(&realm_cookie, DelegationStore::Ledger)
+        .await?;
+    let ledger_address = vote_delegation_test
+        .with_delegation_ledger(
+            &realm_cookie,
+            fake_proposal.pubkey(),
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+
+    let delegator1 = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            fake_proposal.pubkey(),
+        )
+        .await?;
+    let delegator2 = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            fake_proposal.pubkey(),
+        )
+        .await?;
+
+    // Act
+    vote_delegation_test
+        .aggregate_delegation_with_ledger(
+            &realm_cookie,
+            &wallet,
+            &vwr_cookie,
+            &[&delegator1, &delegator2],
+        )
+        .await?;
+
+    // Assert
+    let vwr_record = vote_delegation_test
+        .bench
+        .get_anchor_account::<VoterWeightRecord>(vwr_cookie.address)
+        .await;
+    assert_eq!(vwr_record.voter_weight, 20);
+
+    let ledger = vote_delegation_test
+        .bench
+        .get_anchor_account::<DelegationLedger>(ledger_address)
+        .await;
+    let ledger_account = vote_delegation_test
+        .bench
+        .get_account(&ledger_address)
+        .await
+        .unwrap();
+
+    assert_eq!(ledger.entry_count, 2);
+    assert_eq!(
+        ledger_account.data.len(),
+        DelegationLedger::HEADER_SIZE + 2 * LedgerEntry::SIZE
+    );
+    assert!(vote_delegation_test
+        .bench
+        .get_rent()
+        .await
+        .is_exempt(ledger_account.lamports, ledger_account.data.len()));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_update_voter_weight_record_with_ledger_already_delegated_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    let other_wallet = vote_delegation_test.bench.with_wallet().await;
+    let fake_proposal = Keypair::new();
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            fake_proposal.pubkey(),
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let other_vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &other_wallet,
+            fake_proposal.pubkey(),
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+    vote_delegation_test
+        .set_delegation_store(&realm_cookie, DelegationStore::Ledger)
+        .await?;
+    vote_delegation_test
+        .with_delegation_ledger(
+            &realm_cookie,
+            fake_proposal.pubkey(),
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            fake_proposal.pubkey(),
+        )
+        .await?;
+    vote_delegation_test
+        .aggregate_delegation_with_ledger(&realm_cookie, &wallet, &vwr_cookie, &[&delegator])
+        .await?;
+
+    vote_delegation_test
+        .governance
+        .set_delegate(
+            &delegator.wallet,
+            &delegator.token_owner_record,
+            Some(other_wallet.address),
+        )
+        .await?;
+
+    // Act
+    let err = vote_delegation_test
+        .aggregate_delegation_with_ledger(
+            &realm_cookie,
+            &other_wallet,
+            &other_vwr_cookie,
+            &[&delegator],
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::VoterWeightAlreadyDelegated);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_update_voter_weight_record_with_ledger_source_of_other_owner_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    let fake_proposal = Keypair::new();
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            fake_proposal.pubkey(),
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+    vote_delegation_test
+        .set_delegation_store(&realm_cookie, DelegationStore::Ledger)
+        .await?;
+    vote_delegation_test
+        .with_delegation_ledger(
+            &realm_cookie,
+            fake_proposal.pubkey(),
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            fake_proposal.pubkey(),
+        )
+        .await?;
+    let other_delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            Keypair::new().pubkey(),
+            100,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            fake_proposal.pubkey(),
+        )
+        .await?;
+
+    // Act
+    let other_source = other_delegator.source_vwr.address;
+    let err = vote_delegation_test
+        .aggregate_delegation_with_ledger_using_ix(
+            &realm_cookie,
+            &wallet,
+            &vwr_cookie,
+            &[&delegator],
+            move |ix| ix.accounts[7].pubkey = other_source,
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::InvalidVoterWeightRecordSource);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_update_voter_weight_record_with_delegation_record_in_ledger_store_err() -> TestOutcome
+{
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    let fake_proposal = Keypair::new();
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            fake_proposal.pubkey(),
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+    vote_delegation_test
+        .set_delegation_store(&realm_cookie, DelegationStore::Ledger)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            fake_proposal.pubkey(),
+        )
+        .await?;
+
+    // Act
+    let err = vote_delegation_test
+        .aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &[&delegator])
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::IncorrectDelegationAddress);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_set_delegation_store_with_open_delegations_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .with_unassigned_tokens(&realm_cookie)
+        .await?;
+    vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+    vote_delegation_test
+        .aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &[&delegator])
+        .await?;
+
+    // Act
+    let err = vote_delegation_test
+        .set_delegation_store(&realm_cookie, DelegationStore::Ledger)
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::DelegationStoreInUse);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_set_delegation_store_after_delegations_closed() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .with_unassigned_tokens(&realm_cookie)
+        .await?;
+    let token_owner_record = vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+    vote_delegation_test
+        .aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &[&delegator])
+        .await?;
+
+    vote_delegation_test.bench.advance_clock_a_lot().await;
+    vote_delegation_test
+        .revoke_vote(
+            &realm_cookie,
+            &delegator,
+            &vwr_cookie,
+            &proposal,
+            &token_owner_record,
+        )
+        .await?;
+
+    // Act
+    vote_delegation_test
+        .set_delegation_store(&realm_cookie, DelegationStore::Ledger)
+        .await?;
+
+    // Assert
+    let settings = vote_delegation_test
+        .bench
+        .get_anchor_account::<Settings>(Settings::get_pda_address(
+            &realm_cookie.address,
+            &realm_cookie.community_mint_cookie.address,
+        ))
+        .await;
+
+    assert_eq!(settings.open_delegations, 0);
+    assert_eq!(settings.delegation_store, DelegationStore::Ledger);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_revoke_vote_from_ledger() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .with_unassigned_tokens(&realm_cookie)
+        .await?;
+    let token_owner_record = vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+    vote_delegation_test
+        .set_delegation_store(&realm_cookie, DelegationStore::Ledger)
+        .await?;
+    let ledger_address = vote_delegation_test
+        .with_delegation_ledger(&realm_cookie, proposal.address, VoterWeightAction::CastVote)
+        .await?;
+
+    let delegator1 = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+    let delegator2 = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+    vote_delegation_test
+        .aggregate_delegation_with_ledger(
+            &realm_cookie,
+            &wallet,
+            &vwr_cookie,
+            &[&delegator1, &delegator2],
+        )
+        .await?;
+
+    vote_delegation_test
+        .governance
+        .cast_vote(
+            &realm_cookie,
+            &proposal,
+            &wallet,
+            &token_owner_record,
+            &vwr_cookie,
+        )
+        .await?;
+
+    vote_delegation_test.bench.advance_clock_a_lot().await;
+
+    // Act
+    vote_delegation_test
+        .revoke_vote_from_ledger(
+            &realm_cookie,
+            &delegator1,
+            &vwr_cookie,
+            &proposal,
+            &token_owner_record,
+        )
+        .await?;
+
+    // Assert
+    let proposal_record = vote_delegation_test
+        .bench
+        .get_borsh_account::<ProposalV2>(&proposal.address)
+        .await;
+    assert_eq!(proposal_record.options[0].vote_weight, 10);
+
+    let ledger = vote_delegation_test
+        .bench
+        .get_anchor_account::<DelegationLedger>(ledger_address)
+        .await;
+    let ledger_account = vote_delegation_test
+        .bench
+        .get_account(&ledger_address)
+        .await
+        .unwrap();
+
+    assert_eq!(ledger.entry_count, 1);
+    assert_eq!(
+        ledger_account.data.len(),
+        DelegationLedger::HEADER_SIZE + LedgerEntry::SIZE
+    );
+
+    let err = vote_delegation_test
+        .revoke_vote_from_ledger(
+            &realm_cookie,
+            &delegator1,
+            &vwr_cookie,
+            &proposal,
+            &token_owner_record,
+        )
+        .await
+        .err()
+        .unwrap();
+    assert_vote_delegation_err(err, DelegationError::DelegationNotInLedger);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reclaim_delegation_from_ledger() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    let token_owner_record = vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+    vote_delegation_test
+        .set_delegation_store(&realm_cookie, DelegationStore::Ledger)
+        .await?;
+    let ledger_address = vote_delegation_test
+        .with_delegation_ledger(&realm_cookie, proposal.address, VoterWeightAction::CastVote)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+    vote_delegation_test
+        .aggregate_delegation_with_ledger(&realm_cookie, &wallet, &vwr_cookie, &[&delegator])
+        .await?;
+
+    vote_delegation_test
+        .governance
+        .cast_vote(
+            &realm_cookie,
+            &proposal,
+            &wallet,
+            &token_owner_record,
+            &vwr_cookie,
+        )
+        .await?;
+
+    vote_delegation_test.bench.advance_clock_a_lot().await;
+    vote_delegation_test
+        .reclaim_voter_weight_record(&wallet, &proposal, &vwr_cookie)
+        .await?;
+
+    // Act
+    vote_delegation_test
+        .reclaim_delegation_from_ledger(&realm_cookie, &wallet, &vwr_cookie)
+        .await?;
+
+    // Assert
+    let ledger_account = vote_delegation_test
+        .bench
+        .get_account(&ledger_address)
+        .await;
+
+    assert!(ledger_account.is_none());
+
+    Ok(())
+}
@@ -0,0 +1,456 @@
+#![cfg(not(feature = "upstream-governance"))]
+
+use crate::program_test::tools::assert_vote_delegation_err;
+
+use program_test::{
+    delegation_test::{get_delegation_bump, DelegationTest},
+    governance_test::GovernanceTest,
+};
+
+use solana_program_test::tokio;
+use solana_sdk::transport::TransportError;
+use spl_governance::state::{
+    enums::GovernanceAccountType, legacy::ProposalV1, vote_record::get_vote_record_address,
+};
+use vote_delegation::{
+    error::DelegationError,
+    state::{
+        delegation::Delegation,
+        settings::{Settings, SettingsVersion},
+        vote::Vote,
+        voter_weight_record::VoterWeightAction,
+    },
+};
+
+mod program_test;
+
+type TestOutcome = Result<(), TransportError>;
+
+#[tokio::test]
+async fn test_revoke_vote_with_legacy_accounts() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .with_unassigned_tokens(&realm_cookie)
+        .await?;
+    let token_owner_record = vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+    vote_delegation_test
+        .aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &[&delegator])
+        .await?;
+
+    vote_delegation_test
+        .governance
+        .cast_vote(
+            &realm_cookie,
+            &proposal,
+            &wallet,
+            &token_owner_record,
+            &vwr_cookie,
+        )
+        .await?;
+
+    vote_delegation_test
+        .governance
+        .downgrade_proposal(&proposal)
+        .await?;
+    vote_delegation_test
+        .governance
+        .downgrade_vote_record(&get_vote_record_address(
+            &GovernanceTest::program_id(),
+            &proposal.address,
+            &token_owner_record.address,
+        ))
+        .await?;
+
+    vote_delegation_test.bench.advance_clock_a_lot().await;
+
+    // Act
+    vote_delegation_test
+        .revoke_vote(
+            &realm_cookie,
+            &delegator,
+            &vwr_cookie,
+            &proposal,
+            &token_owner_record,
+        )
+        .await?;
+
+    // Assert
+    let proposal_record = vote_delegation_test
+        .bench
+        .get_borsh_account::<ProposalV1>(&proposal.address)
+        .await;
+
+    assert_eq!(
+        proposal_record.account_type,
+        GovernanceAccountType::ProposalV1
+    );
+    assert_eq!(proposal_record.yes_votes_count, 0);
+
+    let delegation_record = vote_delegation_test
+        .bench
+        .get_account(&Delegation::get_pda_address(
+            &realm_cookie.address,
+            &realm_cookie.community_mint_cookie.address,
+            &delegator.wallet.address,
+            &proposal.address,
+            Some(VoterWeightAction::CastVote),
+        ))
+        .await;
+
+    assert!(delegation_record.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_override_vote_with_legacy_delegation_record() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .with_unassigned_tokens(&realm_cookie)
+        .await?;
+    let token_owner_record = vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+    vote_delegation_test
+        .aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &[&delegator])
+        .await?;
+
+    vote_delegation_test
+        .governance
+        .cast_vote(
+            &realm_cookie,
+            &proposal,
+            &wallet,
+            &token_owner_record,
+            &vwr_cookie,
+        )
+        .await?;
+
+    let delegation_address = Delegation::get_pda_address(
+        &realm_cookie.address,
+        &realm_cookie.community_mint_cookie.address,
+        &delegator.wallet.address,
+        &proposal.address,
+        Some(VoterWeightAction::CastVote),
+    );
+    vote_delegation_test
+        .downgrade_delegation_record(delegation_address)
+        .await?;
+
+    vote_delegation_test.bench.advance_clock_a_lot().await;
+
+    // Act
+    vote_delegation_test
+        .override_vote(
+            &realm_cookie,
+            &delegator,
+            &vwr_cookie,
+            &proposal,
+            &token_owner_record,
+            Vote::Deny,
+        )
+        .await?;
+
+    // Assert
+    let delegation_account = vote_delegation_test
+        .bench
+        .get_account(&delegation_address)
+        .await
+        .unwrap();
+    let delegation_record = vote_delegation_test
+        .bench
+        .get_anchor_account::<Delegation>(delegation_address)
+        .await;
+
+    assert_eq!(delegation_account.data.len(), Delegation::size());
+    assert!(vote_delegation_test
+        .bench
+        .get_rent()
+        .await
+        .is_exempt(delegation_account.lamports, delegation_account.data.len()));
+    assert_eq!(delegation_record.delegate, delegator.wallet.address);
+    assert_eq!(delegation_record.rent_payer, wallet.address);
+    assert_eq!(
+        delegation_record.bump,
+        Some(get_delegation_bump(
+            &realm_cookie.address,
+            &realm_cookie.community_mint_cookie.address,
+            &delegator.wallet.address,
+            &proposal.address,
+            Some(VoterWeightAction::CastVote),
+        ))
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_update_voter_weight_record_with_legacy_settings() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let settings_address = Settings::get_pda_address(
+        &realm_cookie.address,
+        &realm_cookie.community_mint_cookie.address,
+    );
+    vote_delegation_test
+        .downgrade_settings(settings_address)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+
+    // Act
+    vote_delegation_test
+        .aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &[&delegator])
+        .await?;
+
+    // Assert
+    let settings_account = vote_delegation_test
+        .bench
+        .get_account(&settings_address)
+        .await
+        .unwrap();
+    let settings = vote_delegation_test
+        .bench
+        .get_anchor_account::<Settings>(settings_address)
+        .await;
+
+    assert_eq!(settings_account.data.len(), Settings::size());
+    assert_eq!(settings.version, SettingsVersion::V2);
+    assert_eq!(settings.voter_weight_source, precursor_cookie.address);
+    assert_eq!(settings.open_delegations, 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reclaim_delegation_record_with_finished_legacy_proposal() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    let token_owner_record = vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+    vote_delegation_test
+        .aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &[&delegator])
+        .await?;
+
+    vote_delegation_test
+        .governance
+        .cast_vote(
+            &realm_cookie,
+            &proposal,
+            &wallet,
+            &token_owner_record,
+            &vwr_cookie,
+        )
+        .await?;
+
+    vote_delegation_test
+        .governance
+        .downgrade_proposal(&proposal)
+        .await?;
+
+    vote_delegation_test.bench.advance_clock_a_lot().await;
+
+    // Act
+    vote_delegation_test
+        .reclaim_delegation_record_with_target(&realm_cookie, &wallet, &vwr_cookie, &delegator)
+        .await?;
+
+    // Assert
+    let delegation_record = vote_delegation_test
+        .bench
+        .get_account(&Delegation::get_pda_address(
+            &realm_cookie.address,
+            &realm_cookie.community_mint_cookie.address,
+            &delegator.wallet.address,
+            &delegator.source_vwr.target,
+            Some(delegator.source_vwr.action),
+        ))
+        .await;
+
+    assert!(delegation_record.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reclaim_create_proposal_record_with_legacy_governance_unexpired_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.account.governance,
+            VoterWeightAction::CreateProposal,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CreateProposal,
+            proposal.account.governance,
+        )
+        .await?;
+    vote_delegation_test
+        .aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &[&delegator])
+        .await?;
+
+    // A legacy governance is still recognised as a governance of the realm.
+    vote_delegation_test
+        .governance
+        .downgrade_proposal(&proposal)
+        .await?;
+
+    // Act
+    let err = vote_delegation_test
+        .reclaim_voter_weight_record(&wallet, &proposal, &vwr_cookie)
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::ReclaimTargetWrongState);
+
+    Ok(())
+}
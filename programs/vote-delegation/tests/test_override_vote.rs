@@ -0,0 +1,361 @@
+#![cfg(not(feature = "upstream-governance"))]
+
+use program_test::delegation_test::DelegationTest;
+
+use solana_program_test::tokio;
+use solana_sdk::transport::TransportError;
+use spl_governance::state::{
+    proposal::ProposalV2,
+    vote_record::{Vote as GovernanceVote, VoteChoice as GovernanceVoteChoice},
+};
+use vote_delegation::state::{
+    delegation::Delegation,
+    vote::{Vote, VoteChoice},
+    voter_weight_record::{VoterWeightAction, VoterWeightRecord},
+};
+
+mod program_test;
+
+type TestOutcome = Result<(), TransportError>;
+
+#[tokio::test]
+async fn test_override_vote_with_approve() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .with_unassigned_tokens(&realm_cookie)
+        .await?;
+    let token_owner_record = vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+    vote_delegation_test
+        .aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &[&delegator])
+        .await?;
+
+    vote_delegation_test
+        .governance
+        .cast_vote_using(
+            &realm_cookie,
+            &proposal,
+            &wallet,
+            &token_owner_record,
+            &vwr_cookie,
+            GovernanceVote::Approve(vec![GovernanceVoteChoice {
+                rank: 0,
+                weight_percentage: 100,
+            }]),
+        )
+        .await?;
+
+    vote_delegation_test.bench.advance_clock_a_lot().await;
+
+    // Act
+    vote_delegation_test
+        .override_vote(
+            &realm_cookie,
+            &delegator,
+            &vwr_cookie,
+            &proposal,
+            &token_owner_record,
+            Vote::Approve(vec![VoteChoice {
+                rank: 0,
+                weight_percentage: 100,
+            }]),
+        )
+        .await?;
+
+    // Assert
+    let proposal_record = vote_delegation_test
+        .bench
+        .get_borsh_account::<ProposalV2>(&proposal.address)
+        .await;
+
+    assert_eq!(proposal_record.options[0].vote_weight, 10);
+    assert_eq!(proposal_record.deny_vote_weight, Some(0));
+
+    let delegation_record = vote_delegation_test
+        .bench
+        .get_anchor_account::<Delegation>(Delegation::get_pda_address(
+            &realm_cookie.address,
+            &realm_cookie.community_mint_cookie.address,
+            &delegator.wallet.address,
+            &proposal.address,
+            Some(VoterWeightAction::CastVote),
+        ))
+        .await;
+
+    assert_eq!(delegation_record.delegate, delegator.wallet.address);
+
+    let delegate_vwr = vote_delegation_test
+        .bench
+        .get_anchor_account::<VoterWeightRecord>(vwr_cookie.address)
+        .await;
+
+    assert_eq!(delegate_vwr.voter_weight, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_override_vote_with_deny() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .with_unassigned_tokens(&realm_cookie)
+        .await?;
+    let token_owner_record = vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+    vote_delegation_test
+        .aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &[&delegator])
+        .await?;
+
+    vote_delegation_test
+        .governance
+        .cast_vote_using(
+            &realm_cookie,
+            &proposal,
+            &wallet,
+            &token_owner_record,
+            &vwr_cookie,
+            GovernanceVote::Approve(vec![GovernanceVoteChoice {
+                rank: 0,
+                weight_percentage: 100,
+            }]),
+        )
+        .await?;
+
+    vote_delegation_test.bench.advance_clock_a_lot().await;
+
+    // Act
+    vote_delegation_test
+        .override_vote(
+            &realm_cookie,
+            &delegator,
+            &vwr_cookie,
+            &proposal,
+            &token_owner_record,
+            Vote::Deny,
+        )
+        .await?;
+
+    // Assert
+    let proposal_record = vote_delegation_test
+        .bench
+        .get_borsh_account::<ProposalV2>(&proposal.address)
+        .await;
+
+    assert_eq!(proposal_record.options[0].vote_weight, 0);
+    assert_eq!(proposal_record.deny_vote_weight, Some(10));
+
+    let delegation_record = vote_delegation_test
+        .bench
+        .get_anchor_account::<Delegation>(Delegation::get_pda_address(
+            &realm_cookie.address,
+            &realm_cookie.community_mint_cookie.address,
+            &delegator.wallet.address,
+            &proposal.address,
+            Some(VoterWeightAction::CastVote),
+        ))
+        .await;
+
+    assert_eq!(delegation_record.delegate, delegator.wallet.address);
+
+    let delegate_vwr = vote_delegation_test
+        .bench
+        .get_anchor_account::<VoterWeightRecord>(vwr_cookie.address)
+        .await;
+
+    assert_eq!(delegate_vwr.voter_weight, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_override_vote_with_multi_choice() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .with_unassigned_tokens(&realm_cookie)
+        .await?;
+    let token_owner_record = vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_multi_choice_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+    vote_delegation_test
+        .aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &[&delegator])
+        .await?;
+
+    vote_delegation_test
+        .governance
+        .cast_vote_using(
+            &realm_cookie,
+            &proposal,
+            &wallet,
+            &token_owner_record,
+            &vwr_cookie,
+            GovernanceVote::Approve(vec![
+                GovernanceVoteChoice {
+                    rank: 0,
+                    weight_percentage: 100,
+                },
+                GovernanceVoteChoice {
+                    rank: 0,
+                    weight_percentage: 0,
+                },
+                GovernanceVoteChoice {
+                    rank: 0,
+                    weight_percentage: 0,
+                },
+            ]),
+        )
+        .await?;
+
+    vote_delegation_test.bench.advance_clock_a_lot().await;
+
+    // Act
+    vote_delegation_test
+        .override_vote(
+            &realm_cookie,
+            &delegator,
+            &vwr_cookie,
+            &proposal,
+            &token_owner_record,
+            Vote::Approve(vec![
+                VoteChoice {
+                    rank: 0,
+                    weight_percentage: 0,
+                },
+                VoteChoice {
+                    rank: 0,
+                    weight_percentage: 100,
+                },
+                VoteChoice {
+                    rank: 0,
+                    weight_percentage: 100,
+                },
+            ]),
+        )
+        .await?;
+
+    // Assert
+    let proposal_record = vote_delegation_test
+        .bench
+        .get_borsh_account::<ProposalV2>(&proposal.address)
+        .await;
+
+    assert_eq!(proposal_record.options[0].vote_weight, 0);
+    assert_eq!(proposal_record.options[1].vote_weight, 10);
+    assert_eq!(proposal_record.options[2].vote_weight, 10);
+
+    let delegation_record = vote_delegation_test
+        .bench
+        .get_anchor_account::<Delegation>(Delegation::get_pda_address(
+            &realm_cookie.address,
+            &realm_cookie.community_mint_cookie.address,
+            &delegator.wallet.address,
+            &proposal.address,
+            Some(VoterWeightAction::CastVote),
+        ))
+        .await;
+
+    assert_eq!(delegation_record.delegate, delegator.wallet.address);
+
+    let delegate_vwr = vote_delegation_test
+        .bench
+        .get_anchor_account::<VoterWeightRecord>(vwr_cookie.address)
+        .await;
+
+    assert_eq!(delegate_vwr.voter_weight, 0);
+
+    Ok(())
+}
@@ -1,13 +1,20 @@
+#![cfg(not(feature = "upstream-governance"))]
+
 use crate::program_test::tools::assert_vote_delegation_err;
 
-use program_test::delegation_test::DelegationTest;
+use program_test::delegation_test::{DelegationTest, VoterWeightRecordCookie};
 
 use solana_program_test::tokio;
-use solana_sdk::transport::TransportError;
+use solana_sdk::{signer::Signer, transport::TransportError};
+use spl_governance::state::vote_record::get_vote_record_address;
 
 use vote_delegation::{
     error::DelegationError,
-    state::{delegation::Delegation, voter_weight_record::VoterWeightAction},
+    state::{
+        delegation::Delegation,
+        rent_payer::RentPayer,
+        voter_weight_record::{VoterWeightAction, VoterWeightRecord},
+    },
 };
 
 mod program_test;
@@ -78,8 +85,13 @@ async fn test_reclaim_voter_weight_record() -> TestOutcome {
         .bench
         .get_account(&vwr_cookie.address)
         .await;
+    let rent_payer_record = vote_delegation_test
+        .bench
+        .get_account(&RentPayer::get_pda_address(&vwr_cookie.address))
+        .await;
 
     assert!(voter_weight_record.is_none());
+    assert!(rent_payer_record.is_none());
 
     Ok(())
 }
@@ -152,6 +164,49 @@ async fn test_reclaim_voter_weight_record_early_err() -> TestOutcome {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_reclaim_voter_weight_record_with_other_target_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+
+    // The realm is not a proposal, so a CastVote record targeting it could be reclaimed
+    // at any time.
+    let other_target_cookie = VoterWeightRecordCookie {
+        target: realm_cookie.address,
+        ..vwr_cookie
+    };
+
+    // Act
+    let err = vote_delegation_test
+        .reclaim_voter_weight_record(&wallet, &proposal, &other_target_cookie)
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::IncorrectTarget);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_reclaim_delegation_record() -> TestOutcome {
     // Arrange
@@ -298,3 +353,1006 @@ async fn test_reclaim_delegation_record_before_vwr_err() -> TestOutcome {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_reclaim_delegation_record_with_finished_target() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    let token_owner_record = vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+    vote_delegation_test
+        .aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &[&delegator])
+        .await?;
+
+    vote_delegation_test
+        .governance
+        .cast_vote(
+            &realm_cookie,
+            &proposal,
+            &wallet,
+            &token_owner_record,
+            &vwr_cookie,
+        )
+        .await?;
+
+    vote_delegation_test.bench.advance_clock_a_lot().await;
+    let vwr_record_before = vote_delegation_test
+        .bench
+        .get_anchor_account::<VoterWeightRecord>(vwr_cookie.address)
+        .await;
+
+    // Act
+    vote_delegation_test
+        .reclaim_delegation_record_with_target(&realm_cookie, &wallet, &vwr_cookie, &delegator)
+        .await?;
+
+    // Assert
+    let delegation_record = vote_delegation_test
+        .bench
+        .get_account(&Delegation::get_pda_address(
+            &realm_cookie.address,
+            &realm_cookie.community_mint_cookie.address,
+            &delegator.wallet.address,
+            &delegator.source_vwr.target,
+            Some(delegator.source_vwr.action),
+        ))
+        .await;
+    let vwr_record = vote_delegation_test
+        .bench
+        .get_anchor_account::<VoterWeightRecord>(vwr_cookie.address)
+        .await;
+
+    assert!(delegation_record.is_none());
+    // The weight cannot be delegated again while the record still holds it.
+    assert_eq!(vwr_record.voter_weight, vwr_record_before.voter_weight - 10);
+
+    // The delegate's VoterWeightRecord can still be reclaimed afterwards.
+    vote_delegation_test
+        .reclaim_voter_weight_record(&wallet, &proposal, &vwr_cookie)
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reclaim_delegation_record_with_unfinished_target_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .with_unassigned_tokens(&realm_cookie)
+        .await?;
+    let token_owner_record = vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+    vote_delegation_test
+        .aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &[&delegator])
+        .await?;
+
+    vote_delegation_test
+        .governance
+        .cast_vote(
+            &realm_cookie,
+            &proposal,
+            &wallet,
+            &token_owner_record,
+            &vwr_cookie,
+        )
+        .await?;
+
+    // Act
+    let err = vote_delegation_test
+        .reclaim_delegation_record_with_target(&realm_cookie, &wallet, &vwr_cookie, &delegator)
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::ReclaimTargetWrongState);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reclaim_voter_weight_records_skips_ineligible() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let other_wallet = vote_delegation_test.bench.with_wallet().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    let token_owner_record = vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let comment_vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CommentProposal,
+        )
+        .await?;
+    let other_vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &other_wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+    vote_delegation_test
+        .aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &[&delegator])
+        .await?;
+
+    vote_delegation_test
+        .governance
+        .cast_vote(
+            &realm_cookie,
+            &proposal,
+            &wallet,
+            &token_owner_record,
+            &vwr_cookie,
+        )
+        .await?;
+
+    vote_delegation_test.bench.advance_clock_a_lot().await;
+
+    // Act
+    vote_delegation_test
+        .reclaim_voter_weight_records(
+            &wallet,
+            &[&vwr_cookie, &other_vwr_cookie, &comment_vwr_cookie],
+        )
+        .await?;
+
+    // Assert
+    for reclaimed in [&vwr_cookie, &comment_vwr_cookie] {
+        let voter_weight_record = vote_delegation_test
+            .bench
+            .get_account(&reclaimed.address)
+            .await;
+        let rent_payer_record = vote_delegation_test
+            .bench
+            .get_account(&RentPayer::get_pda_address(&reclaimed.address))
+            .await;
+
+        assert!(voter_weight_record.is_none());
+        assert!(rent_payer_record.is_none());
+    }
+
+    let other_voter_weight_record = vote_delegation_test
+        .bench
+        .get_account(&other_vwr_cookie.address)
+        .await;
+
+    assert!(other_voter_weight_record.is_some());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reclaim_delegation_records() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    let token_owner_record = vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator1 = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+    let delegator2 = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+    vote_delegation_test
+        .aggregate_delegation(
+            &realm_cookie,
+            &wallet,
+            &vwr_cookie,
+            &[&delegator1, &delegator2],
+        )
+        .await?;
+
+    vote_delegation_test
+        .governance
+        .cast_vote(
+            &realm_cookie,
+            &proposal,
+            &wallet,
+            &token_owner_record,
+            &vwr_cookie,
+        )
+        .await?;
+
+    vote_delegation_test.bench.advance_clock_a_lot().await;
+
+    vote_delegation_test
+        .reclaim_voter_weight_record(&wallet, &proposal, &vwr_cookie)
+        .await?;
+
+    // Act
+    vote_delegation_test
+        .reclaim_delegation_records(
+            &realm_cookie,
+            &wallet,
+            &vwr_cookie,
+            &[&delegator1, &delegator2],
+        )
+        .await?;
+
+    // Assert
+    for delegator in [&delegator1, &delegator2] {
+        let delegation_record = vote_delegation_test
+            .bench
+            .get_account(&Delegation::get_pda_address(
+                &realm_cookie.address,
+                &realm_cookie.community_mint_cookie.address,
+                &delegator.wallet.address,
+                &delegator.source_vwr.target,
+                Some(delegator.source_vwr.action),
+            ))
+            .await;
+
+        assert!(delegation_record.is_none());
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reclaim_delegation_records_skips_wrong_rent_payer() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    let token_owner_record = vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let mut delegators = vec![];
+    for _ in 0..3 {
+        delegators.push(
+            vote_delegation_test
+                .with_delegator(
+                    &realm_cookie,
+                    &precursor_cookie,
+                    wallet.address,
+                    10,
+                    Some(u64::max_value()),
+                    VoterWeightAction::CastVote,
+                    proposal.address,
+                )
+                .await?,
+        );
+    }
+    let delegators: Vec<_> = delegators.iter().collect();
+    vote_delegation_test
+        .aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &delegators)
+        .await?;
+
+    vote_delegation_test
+        .governance
+        .cast_vote(
+            &realm_cookie,
+            &proposal,
+            &wallet,
+            &token_owner_record,
+            &vwr_cookie,
+        )
+        .await?;
+
+    vote_delegation_test.bench.advance_clock_a_lot().await;
+
+    vote_delegation_test
+        .reclaim_voter_weight_record(&wallet, &proposal, &vwr_cookie)
+        .await?;
+
+    let other_wallet = vote_delegation_test.bench.with_wallet().await;
+
+    // Act
+    vote_delegation_test
+        .reclaim_delegation_records_using_ix(
+            &realm_cookie,
+            &wallet,
+            &vwr_cookie,
+            &delegators,
+            |ix| {
+                // The rent payer of the second record, after the 3 named accounts and the 3
+                // accounts of the first record.
+                ix.accounts[8].pubkey = other_wallet.address;
+            },
+        )
+        .await?;
+
+    // Assert
+    let mut delegation_records = vec![];
+    for delegator in &delegators {
+        delegation_records.push(
+            vote_delegation_test
+                .bench
+                .get_account(&Delegation::get_pda_address(
+                    &realm_cookie.address,
+                    &realm_cookie.community_mint_cookie.address,
+                    &delegator.wallet.address,
+                    &delegator.source_vwr.target,
+                    Some(delegator.source_vwr.action),
+                ))
+                .await,
+        );
+    }
+
+    assert!(delegation_records[0].is_none());
+    assert!(delegation_records[1].is_some());
+    assert!(delegation_records[2].is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_refund_voter_weight_record() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    let token_owner_record = vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+    vote_delegation_test
+        .aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &[&delegator])
+        .await?;
+
+    vote_delegation_test
+        .governance
+        .cast_vote(
+            &realm_cookie,
+            &proposal,
+            &wallet,
+            &token_owner_record,
+            &vwr_cookie,
+        )
+        .await?;
+
+    vote_delegation_test.bench.advance_clock_a_lot().await;
+
+    // Act
+    let rent_payer = vote_delegation_test.bench.payer.pubkey();
+    vote_delegation_test
+        .refund_voter_weight_record(&rent_payer, &vwr_cookie)
+        .await?;
+
+    // Assert
+    let voter_weight_record = vote_delegation_test
+        .bench
+        .get_account(&vwr_cookie.address)
+        .await;
+    let rent_payer_record = vote_delegation_test
+        .bench
+        .get_account(&RentPayer::get_pda_address(&vwr_cookie.address))
+        .await;
+
+    assert!(voter_weight_record.is_none());
+    assert!(rent_payer_record.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_refund_voter_weight_record_early_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .with_unassigned_tokens(&realm_cookie)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+
+    // Act
+    let rent_payer = vote_delegation_test.bench.payer.pubkey();
+    let err = vote_delegation_test
+        .refund_voter_weight_record(&rent_payer, &vwr_cookie)
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::ReclaimTargetWrongState);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reclaim_delegation_record_to_other_rent_payer_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    let token_owner_record = vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+    vote_delegation_test
+        .aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &[&delegator])
+        .await?;
+
+    vote_delegation_test
+        .governance
+        .cast_vote(
+            &realm_cookie,
+            &proposal,
+            &wallet,
+            &token_owner_record,
+            &vwr_cookie,
+        )
+        .await?;
+
+    vote_delegation_test.bench.advance_clock_a_lot().await;
+
+    vote_delegation_test
+        .reclaim_voter_weight_record(&wallet, &proposal, &vwr_cookie)
+        .await?;
+
+    // The record was paid for by the payer of the aggregation, not the delegate.
+    let mut reclaim_delegation_ix = vote_delegation_test.get_reclaim_delegation_instruction(
+        &realm_cookie,
+        &wallet,
+        &vwr_cookie,
+        &delegator,
+    );
+    reclaim_delegation_ix.accounts[5].pubkey = wallet.address;
+
+    // Act
+    let err = vote_delegation_test
+        .bench
+        .process_transaction(&[reclaim_delegation_ix], None)
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::IncorrectRentPayer);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reclaim_create_proposal_record() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.account.governance,
+            VoterWeightAction::CreateProposal,
+        )
+        .await?;
+
+    // Act
+    vote_delegation_test
+        .reclaim_voter_weight_record(&wallet, &proposal, &vwr_cookie)
+        .await?;
+
+    // Assert
+    let vwr = vote_delegation_test
+        .bench
+        .get_account(&vwr_cookie.address)
+        .await;
+
+    assert!(vwr.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reclaim_create_proposal_record_unexpired_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.account.governance,
+            VoterWeightAction::CreateProposal,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    // Aggregation keeps the record valid for a while, during which proposals can be created.
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CreateProposal,
+            proposal.account.governance,
+        )
+        .await?;
+    vote_delegation_test
+        .aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &[&delegator])
+        .await?;
+
+    // Act
+    let err = vote_delegation_test
+        .reclaim_voter_weight_record(&wallet, &proposal, &vwr_cookie)
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::ReclaimTargetWrongState);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reclaim_create_governance_record() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            realm_cookie.address,
+            VoterWeightAction::CreateGovernance,
+        )
+        .await?;
+
+    // Act
+    vote_delegation_test
+        .reclaim_voter_weight_record(&wallet, &proposal, &vwr_cookie)
+        .await?;
+
+    // Assert
+    let vwr = vote_delegation_test
+        .bench
+        .get_account(&vwr_cookie.address)
+        .await;
+
+    assert!(vwr.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reclaim_create_governance_record_unexpired_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            realm_cookie.address,
+            VoterWeightAction::CreateGovernance,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    // Aggregation keeps the record valid for a while, during which governances can be created.
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CreateGovernance,
+            realm_cookie.address,
+        )
+        .await?;
+    vote_delegation_test
+        .aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &[&delegator])
+        .await?;
+
+    // Act
+    let err = vote_delegation_test
+        .reclaim_voter_weight_record(&wallet, &proposal, &vwr_cookie)
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::ReclaimTargetWrongState);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reclaim_revoke_vote_record_unrelinquished_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    let token_owner_record = vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+    vote_delegation_test
+        .aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &[&delegator])
+        .await?;
+
+    vote_delegation_test
+        .governance
+        .cast_vote(
+            &realm_cookie,
+            &proposal,
+            &wallet,
+            &token_owner_record,
+            &vwr_cookie,
+        )
+        .await?;
+
+    let vote_record = get_vote_record_address(
+        &vote_delegation_test.governance.program_id,
+        &proposal.address,
+        &token_owner_record.address,
+    );
+    // RevokeVote records cannot be created with create_voter_weight_record.
+    let revoke_vwr_cookie = vote_delegation_test
+        .with_revoke_vote_record(&realm_cookie, &wallet, vote_record)
+        .await?;
+
+    // Act
+    let err = vote_delegation_test
+        .reclaim_voter_weight_record(&wallet, &proposal, &revoke_vwr_cookie)
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::ReclaimTargetWrongState);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reclaim_revoke_vote_record_relinquished() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    let token_owner_record = vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+    vote_delegation_test
+        .aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &[&delegator])
+        .await?;
+
+    vote_delegation_test
+        .governance
+        .cast_vote(
+            &realm_cookie,
+            &proposal,
+            &wallet,
+            &token_owner_record,
+            &vwr_cookie,
+        )
+        .await?;
+
+    let vote_record = get_vote_record_address(
+        &vote_delegation_test.governance.program_id,
+        &proposal.address,
+        &token_owner_record.address,
+    );
+    // RevokeVote records cannot be created with create_voter_weight_record.
+    let revoke_vwr_cookie = vote_delegation_test
+        .with_revoke_vote_record(&realm_cookie, &wallet, vote_record)
+        .await?;
+
+    // The proposal has already been decided, so the vote record is kept as relinquished.
+    vote_delegation_test
+        .governance
+        .relinquish_vote(&realm_cookie, &proposal, &wallet, &token_owner_record)
+        .await?;
+
+    // Act
+    vote_delegation_test
+        .reclaim_voter_weight_record(&wallet, &proposal, &revoke_vwr_cookie)
+        .await?;
+
+    // Assert
+    let vwr = vote_delegation_test
+        .bench
+        .get_account(&revoke_vwr_cookie.address)
+        .await;
+
+    assert!(vwr.is_none());
+
+    Ok(())
+}
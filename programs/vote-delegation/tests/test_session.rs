@@ -0,0 +1,605 @@
+#[cfg(feature = "upstream-governance")]
+extern crate spl_governance_upstream as spl_governance;
+
+use crate::program_test::tools::{assert_anchor_err, assert_vote_delegation_err};
+
+use anchor_lang::prelude::ErrorCode;
+use program_test::delegation_test::{DelegationTest, VoterWeightRecordCookie};
+
+use solana_program_test::tokio;
+use solana_sdk::{signature::Keypair, signer::Signer, transport::TransportError};
+use vote_delegation::{
+    error::DelegationError,
+    state::{
+        delegation::Delegation,
+        voter_weight_record::{VoterWeightAction, VoterWeightRecord},
+    },
+};
+
+mod program_test;
+
+type TestOutcome = Result<(), TransportError>;
+
+#[tokio::test]
+async fn test_aggregate_delegation_with_session() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let session_key = vote_delegation_test.bench.with_wallet().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+
+    let session = vote_delegation_test
+        .with_session(
+            &wallet,
+            &session_key.address,
+            Some(realm_cookie.address),
+            vec![VoterWeightAction::CastVote],
+            u64::max_value(),
+        )
+        .await?;
+
+    // Act
+    vote_delegation_test
+        .aggregate_delegation_with_session(
+            &realm_cookie,
+            &session_key,
+            session,
+            &vwr_cookie,
+            &[&delegator],
+        )
+        .await?;
+
+    // Assert
+    let vwr_record = vote_delegation_test
+        .bench
+        .get_anchor_account::<VoterWeightRecord>(vwr_cookie.address)
+        .await;
+
+    assert_eq!(vwr_record.voter_weight, 10);
+
+    let delegation_record = vote_delegation_test
+        .bench
+        .get_anchor_account::<Delegation>(Delegation::get_pda_address(
+            &realm_cookie.address,
+            &realm_cookie.community_mint_cookie.address,
+            &delegator.wallet.address,
+            &proposal.address,
+            Some(VoterWeightAction::CastVote),
+        ))
+        .await;
+
+    // The delegation is recorded for the delegate, not for the session key.
+    assert_eq!(delegation_record.delegate, wallet.address);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_aggregate_delegation_with_revoked_session_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let session_key = vote_delegation_test.bench.with_wallet().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+
+    let session = vote_delegation_test
+        .with_session(
+            &wallet,
+            &session_key.address,
+            None,
+            vec![VoterWeightAction::CastVote],
+            u64::max_value(),
+        )
+        .await?;
+    vote_delegation_test
+        .revoke_session(&wallet, session)
+        .await?;
+
+    // Act
+    let err = vote_delegation_test
+        .aggregate_delegation_with_session(
+            &realm_cookie,
+            &session_key,
+            session,
+            &vwr_cookie,
+            &[&delegator],
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_anchor_err(err, ErrorCode::AccountNotInitialized);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_aggregate_delegation_with_expired_session_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let session_key = vote_delegation_test.bench.with_wallet().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+
+    let expiry = vote_delegation_test.bench.get_clock().await.slot;
+    let session = vote_delegation_test
+        .with_session(
+            &wallet,
+            &session_key.address,
+            None,
+            vec![VoterWeightAction::CastVote],
+            expiry,
+        )
+        .await?;
+    vote_delegation_test.bench.advance_clock_a_lot().await;
+
+    // Act
+    let err = vote_delegation_test
+        .aggregate_delegation_with_session(
+            &realm_cookie,
+            &session_key,
+            session,
+            &vwr_cookie,
+            &[&delegator],
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::SessionExpired);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_aggregate_delegation_with_session_for_other_action_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let session_key = vote_delegation_test.bench.with_wallet().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+
+    let session = vote_delegation_test
+        .with_session(
+            &wallet,
+            &session_key.address,
+            None,
+            vec![VoterWeightAction::CreateProposal],
+            u64::max_value(),
+        )
+        .await?;
+
+    // Act
+    let err = vote_delegation_test
+        .aggregate_delegation_with_session(
+            &realm_cookie,
+            &session_key,
+            session,
+            &vwr_cookie,
+            &[&delegator],
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::SessionScopeExceeded);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_aggregate_delegation_with_session_for_other_record_action_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let session_key = vote_delegation_test.bench.with_wallet().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+
+    let session = vote_delegation_test
+        .with_session(
+            &wallet,
+            &session_key.address,
+            None,
+            vec![VoterWeightAction::CreateProposal],
+            u64::max_value(),
+        )
+        .await?;
+
+    // The record was created to cast a vote, not to create a proposal.
+    let vwr = VoterWeightRecordCookie {
+        address: vwr_cookie.address,
+        governing_token_mint: vwr_cookie.governing_token_mint,
+        owner: vwr_cookie.owner,
+        action: VoterWeightAction::CreateProposal,
+        target: vwr_cookie.target,
+    };
+
+    // Act
+    let err = vote_delegation_test
+        .aggregate_delegation_with_session(
+            &realm_cookie,
+            &session_key,
+            session,
+            &vwr,
+            &[&delegator],
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::InvalidActionType);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_aggregate_delegation_with_session_for_other_record_target_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let session_key = vote_delegation_test.bench.with_wallet().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+
+    let session = vote_delegation_test
+        .with_session(
+            &wallet,
+            &session_key.address,
+            None,
+            vec![VoterWeightAction::CastVote],
+            u64::max_value(),
+        )
+        .await?;
+
+    let vwr = VoterWeightRecordCookie {
+        address: vwr_cookie.address,
+        governing_token_mint: vwr_cookie.governing_token_mint,
+        owner: vwr_cookie.owner,
+        action: vwr_cookie.action,
+        target: Keypair::new().pubkey(),
+    };
+
+    // Act
+    let err = vote_delegation_test
+        .aggregate_delegation_with_session(
+            &realm_cookie,
+            &session_key,
+            session,
+            &vwr,
+            &[&delegator],
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::IncorrectTarget);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_aggregate_delegation_with_session_for_other_realm_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let session_key = vote_delegation_test.bench.with_wallet().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+
+    let session = vote_delegation_test
+        .with_session(
+            &wallet,
+            &session_key.address,
+            Some(Keypair::new().pubkey()),
+            vec![VoterWeightAction::CastVote],
+            u64::max_value(),
+        )
+        .await?;
+
+    // Act
+    let err = vote_delegation_test
+        .aggregate_delegation_with_session(
+            &realm_cookie,
+            &session_key,
+            session,
+            &vwr_cookie,
+            &[&delegator],
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::SessionScopeExceeded);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_aggregate_delegation_with_session_of_other_key_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let session_key = vote_delegation_test.bench.with_wallet().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+
+    let session = vote_delegation_test
+        .with_session(
+            &wallet,
+            &session_key.address,
+            None,
+            vec![VoterWeightAction::CastVote],
+            u64::max_value(),
+        )
+        .await?;
+    let other_key = vote_delegation_test.bench.with_wallet().await;
+
+    // Act
+    let err = vote_delegation_test
+        .aggregate_delegation_with_session(
+            &realm_cookie,
+            &other_key,
+            session,
+            &vwr_cookie,
+            &[&delegator],
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::InvalidSession);
+
+    Ok(())
+}
@@ -0,0 +1,354 @@
+#[cfg(feature = "upstream-governance")]
+extern crate spl_governance_upstream as spl_governance;
+
+use crate::program_test::tools::assert_vote_delegation_err;
+
+use anchor_lang::prelude::{AccountMeta, Pubkey};
+use program_test::{
+    delegation_test::{DelegationTest, DelegatorCookie},
+    governance_test::RealmCookie,
+};
+
+use solana_program::instruction::Instruction;
+use solana_program_test::tokio;
+use solana_sdk::{signature::Keypair, transport::TransportError};
+use vote_delegation::{
+    error::DelegationError,
+    state::{
+        delegation::Delegation,
+        signed_delegation::{DelegationMessage, SignedDelegation},
+        voter_weight_record::{VoterWeightAction, VoterWeightRecord},
+    },
+};
+
+mod program_test;
+
+type TestOutcome = Result<(), TransportError>;
+
+fn delegation_message(
+    realm: &RealmCookie,
+    delegate: Pubkey,
+    scope: Option<VoterWeightAction>,
+    nonce: u64,
+) -> DelegationMessage {
+    DelegationMessage {
+        realm: realm.address,
+        governing_token_mint: realm.community_mint_cookie.address,
+        delegate: Some(delegate),
+        scope,
+        expiry: u64::max_value(),
+        nonce,
+    }
+}
+
+/// Replaces the TokenOwnerRecord of the first delegator with their SignedDelegation
+fn use_signed_delegation(
+    realm: &RealmCookie,
+    delegator: &DelegatorCookie,
+) -> impl Fn(&mut Instruction) {
+    let signed_delegation = SignedDelegation::get_pda_address(
+        &realm.address,
+        &realm.community_mint_cookie.address,
+        &delegator.wallet.address,
+    );
+    move |instruction| instruction.accounts[8] = AccountMeta::new_readonly(signed_delegation, false)
+}
+
+#[tokio::test]
+async fn test_aggregate_signed_delegation() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    // The TokenOwnerRecord delegates elsewhere, so only the signed message delegates to wallet.
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            Pubkey::new_unique(),
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+    vote_delegation_test
+        .delegate_with_signature(
+            &delegator.wallet.address,
+            &delegator.wallet.signer,
+            &delegation_message(
+                &realm_cookie,
+                wallet.address,
+                Some(VoterWeightAction::CastVote),
+                0,
+            ),
+        )
+        .await?;
+
+    // Act
+    vote_delegation_test
+        .aggregate_delegation_using_ix(
+            &realm_cookie,
+            &wallet,
+            &vwr_cookie,
+            &[&delegator],
+            use_signed_delegation(&realm_cookie, &delegator),
+        )
+        .await?;
+
+    // Assert
+    let vwr_record = vote_delegation_test
+        .bench
+        .get_anchor_account::<VoterWeightRecord>(vwr_cookie.address)
+        .await;
+
+    assert_eq!(vwr_record.voter_weight, 10);
+
+    let delegation_record = vote_delegation_test
+        .bench
+        .get_anchor_account::<Delegation>(Delegation::get_pda_address(
+            &realm_cookie.address,
+            &realm_cookie.community_mint_cookie.address,
+            &delegator.wallet.address,
+            &proposal.address,
+            Some(VoterWeightAction::CastVote),
+        ))
+        .await;
+
+    assert_eq!(delegation_record.delegate, wallet.address);
+    assert!(delegation_record.signed);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_delegate_with_signature_replayed_nonce_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let delegator = vote_delegation_test.bench.with_wallet().await;
+    let delegate = vote_delegation_test.bench.with_wallet().await;
+
+    vote_delegation_test
+        .delegate_with_signature(
+            &delegator.address,
+            &delegator.signer,
+            &delegation_message(&realm_cookie, delegate.address, None, 0),
+        )
+        .await?;
+
+    // Act
+    let err = vote_delegation_test
+        .delegate_with_signature(
+            &delegator.address,
+            &delegator.signer,
+            &delegation_message(
+                &realm_cookie,
+                delegate.address,
+                Some(VoterWeightAction::CreateProposal),
+                0,
+            ),
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::InvalidDelegationNonce);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_delegate_with_signature_by_other_signer_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let delegator = vote_delegation_test.bench.with_wallet().await;
+    let delegate = vote_delegation_test.bench.with_wallet().await;
+
+    // Act
+    let err = vote_delegation_test
+        .delegate_with_signature(
+            &delegator.address,
+            &Keypair::new(),
+            &delegation_message(&realm_cookie, delegate.address, None, 0),
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::InvalidSignatureVerification);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_aggregate_signed_delegation_outside_scope_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            Pubkey::new_unique(),
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+    vote_delegation_test
+        .delegate_with_signature(
+            &delegator.wallet.address,
+            &delegator.wallet.signer,
+            &delegation_message(
+                &realm_cookie,
+                wallet.address,
+                Some(VoterWeightAction::CreateProposal),
+                0,
+            ),
+        )
+        .await?;
+
+    // Act
+    let err = vote_delegation_test
+        .aggregate_delegation_using_ix(
+            &realm_cookie,
+            &wallet,
+            &vwr_cookie,
+            &[&delegator],
+            use_signed_delegation(&realm_cookie, &delegator),
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::VoterWeightNotDelegatedToDelegate);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_revoke_stale_signed_delegation_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .with_unassigned_tokens(&realm_cookie)
+        .await?;
+    let token_owner_record = vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let proposal = vote_delegation_test
+        .governance
+        .with_proposal(&realm_cookie)
+        .await?;
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            proposal.address,
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            Pubkey::new_unique(),
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            proposal.address,
+        )
+        .await?;
+    vote_delegation_test
+        .delegate_with_signature(
+            &delegator.wallet.address,
+            &delegator.wallet.signer,
+            &delegation_message(&realm_cookie, wallet.address, None, 0),
+        )
+        .await?;
+    vote_delegation_test
+        .aggregate_delegation_using_ix(
+            &realm_cookie,
+            &wallet,
+            &vwr_cookie,
+            &[&delegator],
+            use_signed_delegation(&realm_cookie, &delegator),
+        )
+        .await?;
+
+    vote_delegation_test.bench.advance_clock_a_lot().await;
+
+    // Act
+    // The TokenOwnerRecord has never delegated to wallet, which would make any other
+    // delegation stale.
+    let err = vote_delegation_test
+        .revoke_stale_delegation(
+            &realm_cookie,
+            &delegator,
+            &vwr_cookie,
+            &proposal,
+            &token_owner_record,
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::DelegationStillCurrent);
+
+    Ok(())
+}
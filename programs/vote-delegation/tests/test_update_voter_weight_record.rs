@@ -1,5 +1,11 @@
+#[cfg(feature = "upstream-governance")]
+extern crate spl_governance_upstream as spl_governance;
+
 use anchor_lang::prelude::Pubkey;
-use program_test::{delegation_test::DelegationTest, tools::assert_vote_delegation_err};
+use program_test::{
+    delegation_test::{get_delegation_bump, DelegationTest},
+    tools::assert_vote_delegation_err,
+};
 use solana_program::instruction::Instruction;
 use solana_program_test::tokio;
 use solana_sdk::{signature::Keypair, signer::Signer, transport::TransportError};
@@ -302,6 +308,73 @@ async fn test_update_voter_weight_record_with_non_delegating_user() -> TestOutco
     Ok(())
 }
 
+#[tokio::test]
+async fn test_update_voter_weight_record_with_source_of_other_owner_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let fake_proposal = Keypair::new();
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            fake_proposal.pubkey(),
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            fake_proposal.pubkey(),
+        )
+        .await?;
+    let other_delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            Pubkey::new_unique(),
+            100,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            fake_proposal.pubkey(),
+        )
+        .await?;
+
+    // Act
+    // The delegator's own TokenOwnerRecord is paired with the source record of someone else.
+    let other_source = other_delegator.source_vwr.address;
+    let err = vote_delegation_test
+        .aggregate_delegation_using_ix(
+            &realm_cookie,
+            &wallet,
+            &vwr_cookie,
+            &[&delegator],
+            move |ix: &mut Instruction| ix.accounts[7].pubkey = other_source,
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::InvalidVoterWeightRecordSource);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_update_voter_weight_record_with_duplicate_delegators_err() -> TestOutcome {
     // Arrange
@@ -355,6 +428,155 @@ async fn test_update_voter_weight_record_with_duplicate_delegators_err() -> Test
     Ok(())
 }
 
+#[tokio::test]
+async fn test_update_voter_weight_record_with_non_canonical_bump_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let fake_proposal = Keypair::new();
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            fake_proposal.pubkey(),
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            fake_proposal.pubkey(),
+        )
+        .await?;
+
+    let encoded_action = borsh::to_vec(&Some(VoterWeightAction::CastVote)).unwrap();
+    let seeds = Delegation::get_pda_seeds(
+        &realm_cookie.address,
+        &realm_cookie.community_mint_cookie.address,
+        &delegator.wallet.address,
+        &fake_proposal.pubkey(),
+        &encoded_action,
+    );
+    let canonical_bump = get_delegation_bump(
+        &realm_cookie.address,
+        &realm_cookie.community_mint_cookie.address,
+        &delegator.wallet.address,
+        &fake_proposal.pubkey(),
+        Some(VoterWeightAction::CastVote),
+    );
+    let (non_canonical_address, non_canonical_bump) = (0..canonical_bump)
+        .rev()
+        .find_map(|bump| Delegation::create_pda_address(&seeds, bump).map(|key| (key, bump)))
+        .unwrap();
+
+    let use_non_canonical_bump = |ix: &mut Instruction| {
+        ix.accounts[9].pubkey = non_canonical_address;
+        ix.data = anchor_lang::InstructionData::data(
+            &vote_delegation::instruction::UpdateVoterWeightRecord {
+                voter_weight_action: VoterWeightAction::CastVote,
+                target: Some(fake_proposal.pubkey()),
+                delegation_bumps: vec![non_canonical_bump],
+            },
+        );
+    };
+
+    // Act
+    let err = vote_delegation_test
+        .aggregate_delegation_using_ix(
+            &realm_cookie,
+            &wallet,
+            &vwr_cookie,
+            &[&delegator],
+            use_non_canonical_bump,
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::NonCanonicalDelegationBump);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_update_voter_weight_record_with_missing_bump_err() -> TestOutcome {
+    // Arrange
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let fake_proposal = Keypair::new();
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            fake_proposal.pubkey(),
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let delegator = vote_delegation_test
+        .with_delegator(
+            &realm_cookie,
+            &precursor_cookie,
+            wallet.address,
+            10,
+            Some(u64::max_value()),
+            VoterWeightAction::CastVote,
+            fake_proposal.pubkey(),
+        )
+        .await?;
+
+    let remove_bumps = |ix: &mut Instruction| {
+        ix.data = anchor_lang::InstructionData::data(
+            &vote_delegation::instruction::UpdateVoterWeightRecord {
+                voter_weight_action: VoterWeightAction::CastVote,
+                target: Some(fake_proposal.pubkey()),
+                delegation_bumps: vec![],
+            },
+        );
+    };
+
+    // Act
+    let err = vote_delegation_test
+        .aggregate_delegation_using_ix(
+            &realm_cookie,
+            &wallet,
+            &vwr_cookie,
+            &[&delegator],
+            remove_bumps,
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_vote_delegation_err(err, DelegationError::MissingDelegationBumps);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_repeat_update_voter_weight_record_err() -> TestOutcome {
     // Arrange
@@ -556,3 +778,65 @@ async fn test_update_voter_weight_record_with_own_weight() -> TestOutcome {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_update_voter_weight_record_compute_units() -> TestOutcome {
+    // Arrange
+    const DELEGATOR_COUNT: u64 = 7;
+    const COMPUTE_BUDGET: u64 = 200_000;
+    // A ceiling on the cost of each additional delegator, not a measured baseline.
+    const UNITS_PER_DELEGATOR: u64 = 20_000;
+
+    let mut vote_delegation_test = DelegationTest::start_new().await;
+    let realm_cookie = vote_delegation_test.governance.with_realm().await?;
+    let wallet = vote_delegation_test.bench.with_wallet().await;
+    vote_delegation_test
+        .governance
+        .with_token_owner_record(&realm_cookie, &wallet)
+        .await?;
+    let fake_proposal = Keypair::new();
+    let vwr_cookie = vote_delegation_test
+        .with_vwr(
+            &realm_cookie,
+            &wallet,
+            fake_proposal.pubkey(),
+            VoterWeightAction::CastVote,
+        )
+        .await?;
+    let precursor_cookie = vote_delegation_test
+        .with_precursor_program(&realm_cookie)
+        .await?;
+
+    let mut delegators = vec![];
+    for _ in 0..DELEGATOR_COUNT {
+        delegators.push(
+            vote_delegation_test
+                .with_delegator(
+                    &realm_cookie,
+                    &precursor_cookie,
+                    wallet.address,
+                    10,
+                    Some(u64::max_value()),
+                    VoterWeightAction::CastVote,
+                    fake_proposal.pubkey(),
+                )
+                .await?,
+        );
+    }
+    let delegator_refs = delegators.iter().collect::<Vec<_>>();
+
+    // Act
+    let units_for_one = vote_delegation_test
+        .simulate_aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &delegator_refs[..1])
+        .await?;
+    let units_for_all = vote_delegation_test
+        .simulate_aggregate_delegation(&realm_cookie, &wallet, &vwr_cookie, &delegator_refs)
+        .await?;
+
+    // Assert
+    let units_per_delegator = (units_for_all - units_for_one) / (DELEGATOR_COUNT - 1);
+    assert!(units_per_delegator <= UNITS_PER_DELEGATOR);
+    assert!(units_for_all < COMPUTE_BUDGET);
+
+    Ok(())
+}